@@ -0,0 +1,359 @@
+// cli.rs
+// Command-line interface: subcommands, their flags and how the flags are read
+
+use crate::v2::config::V2Config;
+use crate::v2::config::parameters::{IntegrationMethod, IntegrationParams};
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::optimize::OptimizerConfig;
+use crate::v2::sampling::{self, Dimension, SamplingStrategy};
+use crate::v2::sensitivity::SensitivityConfig;
+use crate::v2::survival::Grouping;
+use crate::v2::sweep::SweepAxis;
+use crate::v2::trajectory::TrajectorySelection;
+use clap::{Args, Parser, Subcommand};
+
+#[derive(Debug, Parser)]
+#[command(name = "rust-ecosystem", version, about = "Keep a sealed ecosystem in a bottle alive",
+          long_about = "Keep a sealed ecosystem in a bottle alive.\n\nWithout a subcommand the original game starts.")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Play the v2 game
+    Play(PlayArgs),
+    /// Play the original game
+    PlayV1,
+    /// Play a v2 game from a script without prompts and print the outcome as JSON
+    Script(ScriptArgs),
+    /// Run many randomized v2 bottles and report how they fare
+    Montecarlo(MonteCarloArgs),
+    /// Re-run one run of a Monte Carlo batch with full output
+    Replay(ReplayArgs),
+    /// Vary one or two settings over a grid and map survival
+    Sweep(SweepArgs),
+    /// Search for the most robust bottle at a difficulty
+    Optimize(OptimizeArgs),
+    /// Find which rate constants matter most for survival
+    Sensitivity(SensitivityArgs),
+    /// Check a configuration file and list every problem in it
+    ValidateConfig {
+        /// TOML or JSON configuration file
+        path: String,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct PlayArgs {
+    /// Start from this configuration file instead of the setup prompts
+    #[arg(long)]
+    pub config: Option<String>,
+    /// Write the configuration chosen at setup to this file
+    #[arg(long, value_name = "PATH")]
+    pub save_config: Option<String>,
+    /// Carry on a game saved from the action prompt
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["config", "save_config"])]
+    pub resume: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ScriptArgs {
+    /// TOML or JSON script with the setup and the day-by-day actions
+    pub script: String,
+    /// Play this configuration file instead of the script's bottle
+    #[arg(long)]
+    pub config: Option<String>,
+    /// Seed instead of the script's
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Write the outcome to this file instead of standard output
+    #[arg(long)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct MonteCarloArgs {
+    /// Number of simulated bottles
+    #[arg(long, default_value_t = 1000, value_parser = positive)]
+    pub runs: usize,
+    /// Days each bottle is simulated for at most
+    #[arg(long, default_value_t = 30, value_parser = positive)]
+    pub days: usize,
+    /// Master seed; the same seed reproduces the batch
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Every run uses this bottle, stocking and climate included; only the starting chemistry varies
+    #[arg(long)]
+    pub config: Option<String>,
+    /// Write every run to a .csv, .json or .jsonl file
+    #[arg(long)]
+    pub output: Option<String>,
+    /// Write the Kaplan-Meier survival curves to a CSV file
+    #[arg(long, value_name = "PATH")]
+    pub survival_csv: Option<String>,
+    /// Runs whose half-day trajectories are recorded: all, collapsed, survived or sample:<fraction>
+    #[arg(long, value_name = "SELECTION")]
+    pub trajectories: Option<TrajectorySelection>,
+    /// Write the recorded trajectories to a JSON file of columns (records all runs unless --trajectories is given)
+    #[arg(long, value_name = "PATH")]
+    pub trajectory_output: Option<String>,
+    /// Compare survival between groups of runs, by difficulty, soil, window or rocks
+    #[arg(long, value_name = "GROUPING")]
+    pub compare: Option<Grouping>,
+    #[command(flatten)]
+    pub sampling: SamplingArgs,
+    #[command(flatten)]
+    pub integration: IntegrationArgs,
+    /// Worker threads; defaults to the number of cores
+    #[arg(long, value_parser = positive)]
+    pub threads: Option<usize>,
+    /// Do not draw the progress line
+    #[arg(long)]
+    pub no_progress: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// Master seed of the batch
+    pub seed: u64,
+    /// Run to replay, counting from 0
+    pub run_id: usize,
+    /// Take the batch's setup from its `--output` JSON instead of the options below
+    #[arg(long, value_name = "FILE", conflicts_with_all = [
+        "days", "runs", "config", "sampling", "dimensions", "difficulty_min", "difficulty_max", "integrator", "sub_steps",
+    ])]
+    pub from: Option<String>,
+    /// Days the batch simulated
+    #[arg(long, default_value_t = 30, value_parser = positive)]
+    pub days: usize,
+    /// Size of the batch, which stratified sampling plans depend on
+    #[arg(long, value_parser = positive)]
+    pub runs: Option<usize>,
+    /// Bottle the batch was run with
+    #[arg(long)]
+    pub config: Option<String>,
+    #[command(flatten)]
+    pub sampling: SamplingArgs,
+    #[command(flatten)]
+    pub integration: IntegrationArgs,
+}
+
+#[derive(Debug, Args)]
+#[command(after_long_help = sweep_fields())]
+pub struct SweepArgs {
+    /// One or two axes: field=low:high:steps or field=v1,v2,...
+    #[arg(required = true, num_args = 1..=2, value_name = "AXIS")]
+    pub axes: Vec<SweepAxis>,
+    /// Runs per grid cell
+    #[arg(long, default_value_t = 100, value_parser = positive)]
+    pub replicates: usize,
+    /// Days each bottle is simulated for at most
+    #[arg(long, default_value_t = 30, value_parser = positive)]
+    pub days: usize,
+    /// Master seed shared by every cell
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Write the grid to a CSV file
+    #[arg(long)]
+    pub output: Option<String>,
+    #[command(flatten)]
+    pub sampling: SamplingArgs,
+    /// Do not show which cell is running
+    #[arg(long)]
+    pub no_progress: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct OptimizeArgs {
+    /// Difficulty the bottle has to survive
+    #[arg(long, default_value_t = OptimizerConfig::default().difficulty, value_parser = level)]
+    pub difficulty: f32,
+    #[arg(long, default_value_t = OptimizerConfig::default().generations, value_parser = positive)]
+    pub generations: usize,
+    /// Candidates per generation
+    #[arg(long, default_value_t = OptimizerConfig::default().population, value_parser = positive)]
+    pub population: usize,
+    /// Randomized starts each candidate is scored on
+    #[arg(long, default_value_t = OptimizerConfig::default().runs_per_candidate, value_parser = positive)]
+    pub runs: usize,
+    /// Days each start is simulated for at most
+    #[arg(long, default_value_t = OptimizerConfig::default().day_cap, value_parser = positive)]
+    pub days: usize,
+    /// Fresh starts the finalists are re-scored on
+    #[arg(long, default_value_t = OptimizerConfig::default().validation_runs, value_parser = positive)]
+    pub validation_runs: usize,
+    /// Narrow a setting's search range: name=low:high
+    #[arg(long = "dim", value_name = "SPEC")]
+    pub dimensions: Vec<Dimension>,
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Worker threads; defaults to the number of cores
+    #[arg(long, value_parser = positive)]
+    pub threads: Option<usize>,
+}
+
+#[derive(Debug, Args)]
+pub struct SensitivityArgs {
+    /// Base sample size of the Sobol design
+    #[arg(long, default_value_t = SensitivityConfig::default().samples, value_parser = positive)]
+    pub samples: usize,
+    /// Days each bottle is simulated for at most
+    #[arg(long, default_value_t = SensitivityConfig::default().day_cap, value_parser = positive)]
+    pub days: usize,
+    /// Morris screening trajectories
+    #[arg(long, default_value_t = SensitivityConfig::default().trajectories, value_parser = positive)]
+    pub trajectories: usize,
+    /// Difficulty of the baseline the rates are varied around
+    #[arg(long, default_value_t = SensitivityConfig::default().difficulty, value_parser = level)]
+    pub difficulty: f32,
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Worker threads; defaults to the number of cores
+    #[arg(long, value_parser = positive)]
+    pub threads: Option<usize>,
+}
+
+/// How the random inputs of a batch are drawn
+#[derive(Debug, Args)]
+pub struct SamplingArgs {
+    /// uniform, lhs, sobol, halton or grid:<levels>
+    #[arg(long, value_name = "STRATEGY")]
+    pub sampling: Option<SamplingStrategy>,
+    /// Override an input's range: name=low:high[:uniform|normal[:std_dev]|log-uniform]
+    #[arg(long = "dim", value_name = "SPEC")]
+    pub dimensions: Vec<Dimension>,
+    /// Lowest difficulty drawn
+    #[arg(long, value_parser = level)]
+    pub difficulty_min: Option<f32>,
+    /// Highest difficulty drawn
+    #[arg(long, value_parser = level)]
+    pub difficulty_max: Option<f32>,
+}
+
+impl SamplingArgs {
+    pub fn strategy(&self) -> SamplingStrategy {
+        self.sampling.unwrap_or_default()
+    }
+
+    /// The default input ranges with every override applied, the difficulty bounds last
+    pub fn dimensions(&self) -> EcosystemResult<Vec<Dimension>> {
+        let mut dimensions = with_overrides(&self.dimensions);
+        if let Some(difficulty) = dimensions.iter_mut().find(|d| d.name == "difficulty") {
+            difficulty.low = self.difficulty_min.unwrap_or(difficulty.low);
+            difficulty.high = self.difficulty_max.unwrap_or(difficulty.high);
+            if difficulty.low > difficulty.high {
+                return Err(EcosystemError::ConfigurationError {
+                    message: format!("--difficulty-min {} is above --difficulty-max {}", difficulty.low, difficulty.high),
+                });
+            }
+        }
+        Ok(dimensions)
+    }
+}
+
+/// The simulation's integrator; unset flags keep the defaults or a loaded file's values
+#[derive(Debug, Args)]
+pub struct IntegrationArgs {
+    /// euler, rk4 or rk45
+    #[arg(long, value_name = "METHOD")]
+    pub integrator: Option<IntegrationMethod>,
+    /// Integration sub-steps per half-day
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub sub_steps: Option<u32>,
+}
+
+impl IntegrationArgs {
+    pub fn apply(&self, integration: &mut IntegrationParams) {
+        if let Some(method) = self.integrator {
+            integration.method = method;
+        }
+        if let Some(sub_steps) = self.sub_steps {
+            integration.sub_steps = sub_steps;
+        }
+    }
+}
+
+/// The default input ranges with `overrides` in place of the defaults of the same name
+pub fn with_overrides(overrides: &[Dimension]) -> Vec<Dimension> {
+    let mut dimensions = Dimension::defaults();
+    for dimension in overrides {
+        sampling::override_dimension(&mut dimensions, dimension.clone());
+    }
+    dimensions
+}
+
+/// Every field an axis can vary, for `sweep --help`
+fn sweep_fields() -> String {
+    format!("Fields: difficulty, {}", V2Config::numeric_fields().join(", "))
+}
+
+fn positive(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn level(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(level) if (0.0..=1.0).contains(&level) => Ok(level),
+        Ok(level) => Err(format!("{} is not between 0.0 and 1.0", level)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_bad_values_are_errors() {
+        let parse = |args: &[&str]| Cli::try_parse_from(std::iter::once("rust-ecosystem").chain(args.iter().copied()));
+        assert!(parse(&["montecarlo", "--runs", "lots"]).is_err());
+        assert!(parse(&["montecarlo", "--runs", "0"]).is_err());
+        assert!(parse(&["montecarlo", "--difficulty-max", "1.5"]).is_err());
+        assert!(parse(&["montecarlo", "--integrator", "leapfrog"]).is_err());
+        assert!(parse(&["sweep"]).is_err());
+        assert!(parse(&["sweep", "worms=1:3:3", "shrimp=1,2", "rocks=1,2"]).is_err());
+
+        let Ok(Cli { command: Some(Command::Montecarlo(args)) }) =
+            parse(&["montecarlo", "--runs", "20", "--difficulty-min", "0.6", "--difficulty-max", "0.9", "--no-progress"])
+        else {
+            panic!("montecarlo arguments should parse");
+        };
+        assert_eq!(args.runs, 20);
+        assert!(args.no_progress);
+        let difficulty = args.sampling.dimensions().unwrap().into_iter().find(|d| d.name == "difficulty").unwrap();
+        assert_eq!((difficulty.low, difficulty.high), (0.6, 0.9));
+    }
+
+    #[test]
+    fn test_printed_replay_command_reads_back_the_batch() {
+        use crate::v2::montecarlo::{BatchSetup, MonteCarloConfig};
+
+        let mut dimensions = Dimension::defaults();
+        dimensions[0] = "difficulty=0.6:0.9".parse().unwrap();
+        let mc_config = MonteCarloConfig { num_runs: 64, day_cap: 12, sampling: SamplingStrategy::Sobol, dimensions, ..MonteCarloConfig::default() };
+        let setup = BatchSetup::of(&mc_config);
+        let command = setup.replay_command(5, "3");
+
+        let Ok(Cli { command: Some(Command::Replay(args)) }) =
+            Cli::try_parse_from(std::iter::once("rust-ecosystem").chain(command.split(' ')))
+        else {
+            panic!("'{}' should parse", command);
+        };
+        assert_eq!((args.seed, args.run_id, args.runs, args.days), (5, 3, Some(64), 12));
+        assert_eq!(args.sampling.strategy(), SamplingStrategy::Sobol);
+        assert_eq!(args.sampling.dimensions().unwrap(), setup.dimensions);
+        assert_eq!(args.integration.integrator, Some(setup.integration.method));
+    }
+}
@@ -6,73 +6,276 @@ mod v1 {
     pub mod input;
 }
 
+mod cli;
+
 // Use the new modular v2 structure
 mod v2;
 
-// Temporarily disable montecarlo for v2 compatibility
-#[cfg(feature = "v1-montecarlo")]
-mod montecarlo;
-
+use clap::Parser;
+use cli::{Cli, Command};
+use std::process::ExitCode;
 use v1::config::setup_game;
 use v1::state::EcosystemState;
 use v1::game::run_game;
+use v2::config::V2Config;
+use v2::errors::{EcosystemError, EcosystemResult};
+use v2::montecarlo::MonteCarloConfig;
+use v2::sampling::SamplingStrategy;
 
-#[cfg(feature = "v1-montecarlo")]
-use montecarlo::run_montecarlo_simulations;
-
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 && args[1] == "v2" {
-        if args.len() > 2 && args[2] == "montecarlo" {
-            let num_runs = if args.len() > 3 {
-                args[3].parse::<usize>().unwrap_or(1000)
-            } else {
-                1000
-            };
-            let day_cap = if args.len() > 4 {
-                args[4].parse::<usize>().unwrap_or(30)
-            } else {
-                30
-            };
-            
-            let mc_config = v2::montecarlo::MonteCarloConfig {
-                num_runs,
-                day_cap,
-                difficulty_range: (0.3, 0.7),
-                randomize_environment: true,
-                randomize_organisms: true,
-                show_progress: true,
-            };
-            
-            let results = v2::montecarlo::run_monte_carlo_v2(mc_config);
-            v2::montecarlo::print_monte_carlo_results(&results);
-        } else {
-            v2::game::run_game_v2();
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        None | Some(Command::PlayV1) => {
+            let config = setup_game();
+            let state = EcosystemState::new();
+            run_game(config, state);
+            Ok(())
         }
-    } else if args.len() > 1 && args[1] == "montecarlo" {
-        #[cfg(feature = "v1-montecarlo")]
-        {
-            let num_runs = if args.len() > 2 {
-                args[2].parse::<usize>().unwrap_or(100)
-            } else {
-                100
-            };
-            let day_cap = if args.len() > 3 {
-                args[3].parse::<usize>().unwrap_or(30)
-            } else {
-                30
-            };
-            run_montecarlo_simulations(num_runs, day_cap, montecarlo::MonteCarloModel::V1);
+        Some(Command::Play(args)) => play(args),
+        Some(Command::Script(args)) => script(args),
+        Some(Command::Montecarlo(args)) => montecarlo(args),
+        Some(Command::Replay(args)) => replay(args),
+        Some(Command::Sweep(args)) => sweep(args),
+        Some(Command::Optimize(args)) => optimize(args),
+        Some(Command::Sensitivity(args)) => sensitivity(args),
+        Some(Command::ValidateConfig { path }) => validate_config(&path),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Load a configuration file, printing its warnings
+fn load_config(path: Option<&str>) -> EcosystemResult<Option<V2Config>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let config = V2Config::load(path)?;
+    for warning in config.check().warnings() {
+        println!("⚠️  {}", warning);
+    }
+    Ok(Some(config))
+}
+
+/// Every run uses `config`'s bottle; its integrator holds unless flags change it
+fn use_config(mc_config: &mut MonteCarloConfig, config: Option<V2Config>, integration: &cli::IntegrationArgs) {
+    if let Some(config) = config {
+        mc_config.integration = config.parameters.integration.clone();
+        mc_config.species = config.species.clone();
+        mc_config.collapse = config.parameters.collapse;
+        mc_config.base_config = Some(config);
+    }
+    integration.apply(&mut mc_config.integration);
+}
+
+fn play(args: cli::PlayArgs) -> EcosystemResult<()> {
+    if let Some(path) = &args.resume {
+        return v2::game::resume_game_v2(path);
+    }
+    let config = load_config(args.config.as_deref())?;
+    v2::game::run_game_v2(config, args.save_config.as_deref());
+    Ok(())
+}
+
+fn script(args: cli::ScriptArgs) -> EcosystemResult<()> {
+    let mut script = v2::script::GameScript::load(&args.script)?;
+    if args.seed.is_some() {
+        script.seed = args.seed;
+    }
+    let config = match &args.config {
+        Some(path) => V2Config::load(path)?,
+        None => script.configuration()?,
+    };
+
+    let outcome = v2::script::run_script(&script, config)?;
+    let json = serde_json::to_string_pretty(&outcome).map_err(|e| EcosystemError::SimulationError { message: e.to_string() })?;
+    match &args.output {
+        Some(path) => std::fs::write(path, json + "\n").map_err(|e| EcosystemError::ConfigurationError {
+            message: format!("Could not write {}: {}", path, e),
+        }),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+fn montecarlo(args: cli::MonteCarloArgs) -> EcosystemResult<()> {
+    if let Some(path) = &args.output {
+        v2::export::ExportFormat::from_path(path)?;
+    }
+    let config = load_config(args.config.as_deref())?;
+    let record_trajectories = match args.trajectories {
+        Some(selection) => selection,
+        None if args.trajectory_output.is_some() => v2::trajectory::TrajectorySelection::All,
+        None => v2::trajectory::TrajectorySelection::None,
+    };
+
+    let mut mc_config = MonteCarloConfig {
+        num_runs: args.runs,
+        day_cap: args.days,
+        seed: args.seed,
+        threads: args.threads,
+        sampling: args.sampling.strategy(),
+        dimensions: args.sampling.dimensions()?,
+        record_trajectories,
+        show_progress: !args.no_progress,
+        ..MonteCarloConfig::default()
+    };
+    use_config(&mut mc_config, config, &args.integration);
+
+    let results = v2::montecarlo::run_monte_carlo_v2(mc_config)?;
+    v2::montecarlo::print_monte_carlo_results(&results);
+    if let Some(path) = &args.output {
+        v2::export::export_results(&results, path)?;
+        println!("\n💾 {} runs written to {}", results.results.len(), path);
+    }
+
+    if record_trajectories != v2::trajectory::TrajectorySelection::None {
+        let bands = v2::trajectory::trajectory_bands(&results.results);
+        v2::trajectory::print_trajectory_bands(&bands, &["Plant Biomass", "Air Oxygen", "Soil pH", "Toxicity"]);
+        if let Some(path) = &args.trajectory_output {
+            let runs = v2::trajectory::write_trajectories(path, &results.results)?;
+            println!("\n💾 Trajectories of {} runs written to {}", runs, path);
+        }
+    }
+
+    let comparison = args.compare.map(|grouping| {
+        let comparison = v2::survival::compare_groups(&results.results, grouping);
+        v2::survival::print_group_comparison(&comparison);
+        comparison
+    });
+    if let Some(path) = &args.survival_csv {
+        let mut curves = vec![("all", &results.survival_curve)];
+        if let Some(comparison) = &comparison {
+            curves.extend(comparison.groups.iter().map(|g| (g.label.as_str(), &g.curve)));
         }
-        
-        #[cfg(not(feature = "v1-montecarlo"))]
-        {
-            println!("Monte Carlo simulation temporarily disabled during v2 refactoring");
-            println!("Use 'cargo run' to play the regular game or 'cargo run v2' for the new version");
+        v2::survival::write_survival_csv(path, &curves)?;
+        println!("\n💾 Survival curves written to {}", path);
+    }
+    Ok(())
+}
+
+fn replay(args: cli::ReplayArgs) -> EcosystemResult<()> {
+    let mc_config = match &args.from {
+        Some(path) => {
+            let (master_seed, setup) = v2::export::read_batch_setup(path)?;
+            if master_seed != args.seed {
+                return Err(EcosystemError::ConfigurationError {
+                    message: format!("{} is the batch with seed {}, not {}", path, master_seed, args.seed),
+                });
+            }
+            setup.monte_carlo_config()
         }
-    } else {
-        let config = setup_game();
-        let state = EcosystemState::new();
-        run_game(config, state);
+        None => {
+            let config = load_config(args.config.as_deref())?;
+            let mut mc_config = MonteCarloConfig {
+                num_runs: args.runs.unwrap_or(args.run_id + 1),
+                day_cap: args.days,
+                sampling: args.sampling.strategy(),
+                dimensions: args.sampling.dimensions()?,
+                ..MonteCarloConfig::default()
+            };
+            use_config(&mut mc_config, config, &args.integration);
+            // Stratified plans depend on the batch size, so guessing it would replay a different run
+            if args.runs.is_none() && mc_config.base_config.is_none() && mc_config.sampling != SamplingStrategy::Uniform {
+                return Err(EcosystemError::ConfigurationError {
+                    message: format!(
+                        "Replaying a {} batch needs its size: pass --runs, or --from with the batch's JSON export",
+                        mc_config.sampling
+                    ),
+                });
+            }
+            mc_config
+        }
+    };
+    if args.run_id >= mc_config.num_runs {
+        return Err(EcosystemError::ConfigurationError {
+            message: format!("Run {} is not in a batch of {} runs", args.run_id, mc_config.num_runs),
+        });
+    }
+
+    v2::montecarlo::replay_run(&mc_config, args.seed, args.run_id)?;
+    Ok(())
+}
+
+fn sweep(args: cli::SweepArgs) -> EcosystemResult<()> {
+    let mut axes = args.axes.into_iter();
+    let sweep = v2::sweep::SweepConfig {
+        x: axes.next().expect("clap requires one axis"),
+        y: axes.next(),
+        replicates: args.replicates,
+        base: MonteCarloConfig {
+            day_cap: args.days,
+            seed: args.seed,
+            sampling: args.sampling.strategy(),
+            dimensions: args.sampling.dimensions()?,
+            show_progress: !args.no_progress,
+            ..MonteCarloConfig::default()
+        },
+    };
+
+    let results = v2::sweep::run_sweep(&sweep)?;
+    v2::sweep::print_sweep_results(&results, sweep.base.day_cap);
+    if let Some(path) = &args.output {
+        v2::sweep::write_sweep_csv(path, &results)?;
+        println!("\n💾 {} cells written to {}", results.cells.len(), path);
+    }
+    Ok(())
+}
+
+fn optimize(args: cli::OptimizeArgs) -> EcosystemResult<()> {
+    let opt_config = v2::optimize::OptimizerConfig {
+        difficulty: args.difficulty,
+        generations: args.generations,
+        population: args.population,
+        runs_per_candidate: args.runs,
+        day_cap: args.days,
+        validation_runs: args.validation_runs,
+        dimensions: cli::with_overrides(&args.dimensions),
+        seed: args.seed,
+        threads: args.threads,
+        ..v2::optimize::OptimizerConfig::default()
+    };
+
+    let results = v2::optimize::run_optimizer(&opt_config)?;
+    v2::optimize::print_optimizer_results(&results);
+    Ok(())
+}
+
+fn sensitivity(args: cli::SensitivityArgs) -> EcosystemResult<()> {
+    let sa_config = v2::sensitivity::SensitivityConfig {
+        samples: args.samples,
+        day_cap: args.days,
+        trajectories: args.trajectories,
+        difficulty: args.difficulty,
+        seed: args.seed,
+        threads: args.threads,
+        ..v2::sensitivity::SensitivityConfig::default()
+    };
+
+    let results = v2::sensitivity::run_sensitivity_analysis(&sa_config)?;
+    v2::sensitivity::print_sensitivity_results(&results, sa_config.show_top);
+    Ok(())
+}
+
+/// Print every problem in the file; fails if any of them is an error
+fn validate_config(path: &str) -> EcosystemResult<()> {
+    let report = V2Config::load_unchecked(path)?.check();
+    if report.issues.is_empty() {
+        println!("✅ {} is valid", path);
+        return Ok(());
+    }
+
+    print!("{}", report);
+    let summary = format!("{} error(s), {} warning(s) in {}", report.errors().count(), report.warnings().count(), path);
+    if report.has_errors() {
+        return Err(EcosystemError::ConfigurationError { message: summary });
     }
+    println!("{}", summary);
+    Ok(())
 }
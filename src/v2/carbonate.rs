@@ -0,0 +1,225 @@
+// v2/carbonate.rs
+// Carbonate equilibrium chemistry for the water column
+
+use crate::v2::config::parameters::CarbonateParams;
+use crate::v2::climate::headspace_liters;
+use crate::v2::ledger::MOLAR_GAS_VOLUME;
+use crate::v2::integrator::Derivatives;
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::types::*;
+
+/// Dissolved carbonate system: concentrations in mmol/L and meq/L
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaterChemistry {
+    pub ph: f32,
+    pub dic: f32,
+    pub alkalinity: f32,
+}
+
+/// Equilibrium constants at one temperature, in mol/L
+#[derive(Debug, Clone, Copy)]
+struct Constants {
+    k1: f64,
+    k2: f64,
+    kw: f64,
+    henry: f64,
+    calcite: f64,
+}
+
+impl Constants {
+    /// Freshwater fits: Plummer & Busenberg for K1, K2 and calcite, Weiss for CO2 solubility
+    fn at(celsius: f32) -> Self {
+        let t = celsius as f64 + 273.15;
+        let pk1 = 3404.71 / t + 0.032786 * t - 14.8435;
+        let pk2 = 2902.39 / t + 0.02379 * t - 6.4980;
+        let pkw = 4470.99 / t - 6.0875 + 0.01706 * t;
+        let ln_henry = -58.0931 + 90.5069 * (100.0 / t) + 22.2940 * (t / 100.0).ln();
+        let log_calcite = -171.9065 - 0.077993 * t + 2839.319 / t + 71.595 * t.log10();
+
+        Self {
+            k1: 10f64.powf(-pk1),
+            k2: 10f64.powf(-pk2),
+            kw: 10f64.powf(-pkw),
+            henry: ln_henry.exp(),
+            calcite: 10f64.powf(log_calcite),
+        }
+    }
+
+    /// Fractions of DIC present as CO2(aq), bicarbonate and carbonate
+    fn speciation(&self, h: f64) -> (f64, f64, f64) {
+        let denominator = h * h + self.k1 * h + self.k1 * self.k2;
+        (h * h / denominator, self.k1 * h / denominator, self.k1 * self.k2 / denominator)
+    }
+}
+
+/// Solve the charge balance for pH given DIC (mmol/L) and alkalinity (meq/L)
+pub fn solve_ph(dic: f32, alkalinity: f32, celsius: f32) -> f32 {
+    let constants = Constants::at(celsius);
+    let dic = dic.max(0.0) as f64 / 1000.0;
+    let alkalinity = alkalinity as f64 / 1000.0;
+
+    // Alkalinity falls monotonically with [H+], so bisect on pH
+    let (mut low, mut high) = (2.0f64, 12.0f64);
+    for _ in 0..60 {
+        let mid = 0.5 * (low + high);
+        let h = 10f64.powf(-mid);
+        let (_, bicarbonate, carbonate) = constants.speciation(h);
+        let charge = dic * (bicarbonate + 2.0 * carbonate) + constants.kw / h - h;
+        if charge > alkalinity {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    (0.5 * (low + high)) as f32
+}
+
+/// Dissolved CO2 in equilibrium with the bottle air, mmol/L
+pub fn equilibrium_co2(air_co2: CarbonDioxide, celsius: f32) -> f32 {
+    let partial_pressure = air_co2.value() as f64 / 100.0; // atm
+    (Constants::at(celsius).henry * partial_pressure * 1000.0) as f32
+}
+
+/// Water of the given alkalinity left to settle against the bottle air
+pub fn equilibrium_water(alkalinity: f32, air_co2: CarbonDioxide, celsius: f32) -> WaterChemistry {
+    let constants = Constants::at(celsius);
+    let co2 = equilibrium_co2(air_co2, celsius) as f64 / 1000.0;
+    let alkalinity_molar = alkalinity as f64 / 1000.0;
+
+    let (mut low, mut high) = (2.0f64, 12.0f64);
+    for _ in 0..60 {
+        let mid = 0.5 * (low + high);
+        let h = 10f64.powf(-mid);
+        let charge = co2 * (constants.k1 / h + 2.0 * constants.k1 * constants.k2 / (h * h))
+            + constants.kw / h
+            - h;
+        if charge > alkalinity_molar {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    let ph = 0.5 * (low + high);
+    let (co2_fraction, _, _) = constants.speciation(10f64.powf(-ph));
+
+    WaterChemistry {
+        ph: ph as f32,
+        dic: (co2 / co2_fraction * 1000.0) as f32,
+        alkalinity,
+    }
+}
+
+/// Add the rates of CO2 exchange with the air and limestone dissolution
+pub fn carbonate_rates(state: &EcosystemStateV2, params: &CarbonateParams, rates: &mut Derivatives) {
+    let water = state.water_liters.value();
+    if water <= 0.01 {
+        return;
+    }
+
+    exchange_co2(state, params, water, rates);
+    dissolve_limestone(state, params, water, rates);
+}
+
+/// Henry's-law exchange between the headspace and the water surface
+fn exchange_co2(state: &EcosystemStateV2, params: &CarbonateParams, water: f32, rates: &mut Derivatives) {
+    let celsius = state.temperature.celsius();
+    let constants = Constants::at(celsius);
+    let headspace = headspace_liters(state) as f64;
+
+    let h = 10f64.powf(-(state.water_ph.value() as f64));
+    let (co2_fraction, _, _) = constants.speciation(h);
+    let dissolved_co2 = state.water_dic as f64 * co2_fraction;
+    let target = equilibrium_co2(state.air_co2, celsius) as f64;
+
+    // mmol/L per half-day into the water, and the matching loss from the headspace
+    let transfer = params.gas_exchange_rate as f64 * (target - dissolved_co2);
+    let air_change = transfer * water as f64 / 1000.0 * MOLAR_GAS_VOLUME / headspace * 100.0;
+    rates.water_dic += transfer as f32;
+    rates.air_co2 -= air_change as f32;
+}
+
+/// Undersaturated water eats into limestone rocks: CaCO3 + CO2 + H2O -> Ca2+ + 2 HCO3-
+fn dissolve_limestone(state: &EcosystemStateV2, params: &CarbonateParams, water: f32, rates: &mut Derivatives) {
+    if state.rocks == 0 {
+        return;
+    }
+
+    let constants = Constants::at(state.temperature.celsius());
+    let (_, _, carbonate_fraction) = constants.speciation(10f64.powf(-(state.water_ph.value() as f64)));
+    let calcium = state.water_alkalinity.max(0.0) as f64 / 2000.0;
+    let carbonate = state.water_dic as f64 / 1000.0 * carbonate_fraction;
+    let saturation = calcium * carbonate / constants.calcite;
+
+    let undersaturation = (1.0 - saturation).max(0.0) as f32;
+    let dissolved = params.limestone_dissolution_rate * state.rocks as f32 * undersaturation / water;
+    rates.water_dic += dissolved;
+    rates.water_alkalinity += 2.0 * dissolved;
+
+    // The rocks are outside the ledger, so their carbon counts as an inflow
+    rates.limestone_carbon += dissolved * water / 1000.0;
+}
+
+/// Dilute the water column with fresh tap water of the configured alkalinity
+pub fn mix_fresh_water(state: &mut EcosystemStateV2, params: &CarbonateParams, old_liters: f32, added_liters: f32) {
+    let total = old_liters + added_liters;
+    if total <= 0.0 {
+        return;
+    }
+    let fresh = equilibrium_water(params.initial_alkalinity, state.air_co2, state.temperature.celsius());
+    state.water_dic = (state.water_dic * old_liters + fresh.dic * added_liters) / total;
+    state.water_alkalinity = (state.water_alkalinity * old_liters + fresh.alkalinity * added_liters) / total;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::config::V2Config;
+    use crate::v2::config::parameters::PhModel;
+    use crate::v2::errors::EcosystemResult;
+    use crate::v2::integrator::integrate;
+
+    fn water_chemistry_rates(state: &EcosystemStateV2, config: &V2Config) -> EcosystemResult<Derivatives> {
+        let mut rates = Derivatives::default();
+        carbonate_rates(state, &config.parameters.carbonate, &mut rates);
+        Ok(rates)
+    }
+
+    #[test]
+    fn test_equilibrium_ph_with_room_air() {
+        // Freshwater with 1 meq/L alkalinity under 400 ppm CO2 settles just above pH 8
+        let water = equilibrium_water(1.0, CarbonDioxide::new(0.04).unwrap(), 25.0);
+        assert!((8.0..8.4).contains(&water.ph), "pH was {}", water.ph);
+        assert!((solve_ph(water.dic, water.alkalinity, 25.0) - water.ph).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_co2_buildup_acidifies_water() {
+        let mut config = V2Config::new();
+        config.parameters.ph_model = PhModel::Carbonate;
+        config.environment.rocks = 0;
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        let start_ph = state.water_ph.value();
+        let start_carbon = state.water_dic * state.water_liters.value();
+
+        state.air_co2 = CarbonDioxide::new(2.0).unwrap();
+        integrate(&mut state, &config, 10.0, water_chemistry_rates).unwrap();
+
+        assert!(state.water_ph.value() < start_ph - 0.5);
+        assert!(state.water_dic * state.water_liters.value() > start_carbon);
+        assert!(state.air_co2.value() < 2.0);
+    }
+
+    #[test]
+    fn test_limestone_raises_alkalinity() {
+        let mut config = V2Config::new();
+        config.parameters.ph_model = PhModel::Carbonate;
+        config.environment.rocks = 5;
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        let start_alkalinity = state.water_alkalinity;
+
+        state.air_co2 = CarbonDioxide::new(1.0).unwrap();
+        integrate(&mut state, &config, 10.0, water_chemistry_rates).unwrap();
+
+        assert!(state.water_alkalinity > start_alkalinity);
+    }
+}
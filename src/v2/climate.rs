@@ -0,0 +1,165 @@
+// v2/climate.rs
+// Thermal and hygrometric model for the air inside the bottle
+
+use crate::v2::config::V2Config;
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::integrator::Derivatives;
+use crate::v2::types::*;
+
+/// Total inner volume of the bottle
+pub const BOTTLE_VOLUME_LITERS: f32 = 4.0;
+
+/// Heat capacity of the glass, soil and air, before water and rocks are added
+const BASE_HEAT_CAPACITY: f32 = 2.0;
+const WATER_HEAT_CAPACITY_PER_LITER: f32 = 4.18;
+const ROCK_HEAT_CAPACITY: f32 = 0.8;
+
+/// Specific gas constant of water vapour, J/(kg·K)
+const WATER_VAPOR_GAS_CONSTANT: f32 = 461.5;
+
+/// Add the rates of heating, evaporation and condensation for the current phase of the day
+pub fn microclimate_rates(state: &EcosystemStateV2, config: &V2Config, rates: &mut Derivatives) {
+    let params = &config.parameters.climate;
+    let temperature = state.temperature.celsius();
+    let saturation = saturation_vapor_pressure(temperature);
+    let vapor_pressure = state.humidity.percentage() / 100.0 * saturation;
+
+    // Temperature relaxes toward the balance of solar gain and heat lost to the room
+    let ambient = ambient_temperature(config, state.day_phase);
+    let equilibrium = ambient + params.solar_gain * state.light_level() / params.heat_loss_rate;
+    let temperature_rate = params.heat_loss_rate / heat_capacity(state) * (equilibrium - temperature);
+
+    // Open water and leaves push vapour toward saturation at the air temperature
+    let transpiration = if state.day_phase.is_daylight() {
+        params.transpiration_rate * state.plant_biomass.value() * state.day_phase.solar_fraction()
+    } else {
+        0.0
+    };
+    let evaporation_rate = params.evaporation_rate * water_surface(state.water_liters) + transpiration;
+    let mut vapor_rate = evaporation_rate * (saturation - vapor_pressure).max(0.0);
+
+    // Anything above saturation at the glass temperature condenses and runs back down
+    let glass_saturation = saturation_vapor_pressure(glass_temperature(temperature, ambient, params.glass_coupling));
+    vapor_rate -= params.condensation_rate * (vapor_pressure - glass_saturation).max(0.0);
+
+    // Relative humidity also moves as warming air raises its saturation point
+    let saturation_slope = 17.62 * 243.12 / (243.12 + temperature).powi(2);
+    let humidity_rate = 100.0 * vapor_rate / saturation
+        - state.humidity.percentage() * saturation_slope * temperature_rate;
+
+    let water_rate = -vapor_to_liters(vapor_rate, headspace_liters(state), temperature);
+
+    rates.temperature += temperature_rate;
+    rates.humidity += humidity_rate;
+    rates.water_liters += water_rate;
+
+    // Evaporation leaves dissolved carbon, oxygen and alkalinity behind
+    let water = state.water_liters.value();
+    if water > 0.01 {
+        rates.water_dic -= state.water_dic / water * water_rate;
+        rates.water_alkalinity -= state.water_alkalinity / water * water_rate;
+        rates.water_o2 -= state.water_o2.percentage() / water * water_rate;
+    }
+
+    // The same gas spreads through the headspace the water gives up
+    let dilution = water_rate / headspace_liters(state);
+    rates.air_n2 += state.air_n2.value() * dilution;
+    rates.air_o2 += state.air_o2.percentage() * dilution;
+    rates.air_co2 += state.air_co2.value() * dilution;
+}
+
+/// Room temperature the bottle loses heat to; rooms cool off at night
+pub fn ambient_temperature(config: &V2Config, phase: DayPhase) -> f32 {
+    let room = config.environment.room_temperature.celsius();
+    if phase.is_daylight() {
+        room
+    } else {
+        room - config.parameters.climate.night_temperature_drop
+    }
+}
+
+/// Heat capacity of the bottle contents; water and rocks damp temperature swings
+pub fn heat_capacity(state: &EcosystemStateV2) -> f32 {
+    BASE_HEAT_CAPACITY
+        + WATER_HEAT_CAPACITY_PER_LITER * state.water_liters.value()
+        + ROCK_HEAT_CAPACITY * state.rocks as f32
+}
+
+/// Saturation vapour pressure in hPa (Magnus formula)
+pub fn saturation_vapor_pressure(celsius: f32) -> f32 {
+    6.112 * (17.62 * celsius / (243.12 + celsius)).exp()
+}
+
+/// Dew point of the bottle air in °C
+pub fn dew_point(temperature: Temperature, humidity: Humidity) -> f32 {
+    let gamma = (humidity.percentage().max(1.0) / 100.0).ln()
+        + 17.62 * temperature.celsius() / (243.12 + temperature.celsius());
+    243.12 * gamma / (17.62 - gamma)
+}
+
+/// The glass sits between the bottle air and the room
+fn glass_temperature(inside: f32, ambient: f32, coupling: f32) -> f32 {
+    inside - (inside - ambient) * coupling.clamp(0.0, 1.0)
+}
+
+/// Evaporating surface grows more slowly than the water volume
+fn water_surface(water: WaterVolume) -> f32 {
+    water.value().max(0.0).sqrt()
+}
+
+/// Air volume above the water
+pub fn headspace_liters(state: &EcosystemStateV2) -> f32 {
+    (BOTTLE_VOLUME_LITERS - state.water_liters.value()).max(0.5)
+}
+
+/// Liquid water needed to change the headspace vapour pressure by `delta_hpa`
+fn vapor_to_liters(delta_hpa: f32, headspace_liters: f32, celsius: f32) -> f32 {
+    let pascal = delta_hpa * 100.0;
+    let cubic_meters = headspace_liters / 1000.0;
+    // One kilogram of water is one liter
+    pascal * cubic_meters / (WATER_VAPOR_GAS_CONSTANT * (celsius + 273.15))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::simulation_refactored::update_ecosystem_v2;
+
+    fn day_night_range(config: &V2Config) -> (f32, f32) {
+        let mut state = EcosystemStateV2::new(config).expect("Failed to create state");
+        update_ecosystem_v2(config, &mut state, true).unwrap();
+        let day = state.temperature.celsius();
+        update_ecosystem_v2(config, &mut state, false).unwrap();
+        (day, state.temperature.celsius())
+    }
+
+    #[test]
+    fn test_sunny_windowsill_warms_and_night_cools() {
+        let mut config = V2Config::new();
+        config.environment.window_proximity = 0;
+        let (day, night) = day_night_range(&config);
+
+        assert!(day > config.environment.room_temperature.celsius());
+        assert!(night < day);
+    }
+
+    #[test]
+    fn test_water_damps_temperature_swings() {
+        let mut small = V2Config::new();
+        small.environment.window_proximity = 0;
+        small.environment.water_volume = WaterVolume::new(0.2).unwrap();
+        let mut large = small.clone();
+        large.environment.water_volume = WaterVolume::new(2.5).unwrap();
+
+        let (small_day, small_night) = day_night_range(&small);
+        let (large_day, large_night) = day_night_range(&large);
+        assert!(small_day - small_night > large_day - large_night);
+    }
+
+    #[test]
+    fn test_saturation_vapor_pressure() {
+        assert!((saturation_vapor_pressure(20.0) - 23.4).abs() < 0.3);
+        let dew = dew_point(Temperature::new(25.0).unwrap(), Humidity::new(100.0).unwrap());
+        assert!((dew - 25.0).abs() < 0.1);
+    }
+}
@@ -1,9 +1,13 @@
 // v2/config/difficulty.rs
 // Difficulty configuration for ecosystem simulation
 
+use super::validation;
 use crate::v2::errors::{EcosystemError, EcosystemResult};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+/// Stored as just the level; the scaling is derived from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct DifficultyConfig {
     pub level: f32,
     pub scaling: DifficultyScaling,
@@ -38,12 +42,7 @@ impl DifficultyConfig {
     }
 
     pub fn validate(&self) -> EcosystemResult<()> {
-        if !(0.0..=1.0).contains(&self.level) {
-            return Err(EcosystemError::ConfigurationError {
-                message: format!("Difficulty level {} must be between 0.0 and 1.0", self.level),
-            });
-        }
-        Ok(())
+        validation::validate_with(|report| validation::check_difficulty(self, report))
     }
 
     pub fn easy() -> Self {
@@ -63,6 +62,20 @@ impl DifficultyConfig {
     }
 }
 
+impl TryFrom<f32> for DifficultyConfig {
+    type Error = EcosystemError;
+
+    fn try_from(level: f32) -> Result<Self, Self::Error> {
+        Self::new(level)
+    }
+}
+
+impl From<DifficultyConfig> for f32 {
+    fn from(difficulty: DifficultyConfig) -> f32 {
+        difficulty.level
+    }
+}
+
 impl Default for DifficultyConfig {
     fn default() -> Self {
         Self::new(0.5).unwrap() // Medium difficulty
@@ -1,10 +1,16 @@
 // v2/config/environment.rs
 // Environmental configuration for ecosystem simulation
 
-use crate::v2::errors::{EcosystemError, EcosystemResult};
+use super::validation;
+use crate::v2::errors::EcosystemResult;
 use crate::v2::types::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+/// Temperature of the room the bottle sits in during the day
+pub const DEFAULT_ROOM_TEMPERATURE: f32 = 22.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct EnvironmentConfig {
     pub water_volume: WaterVolume,
     pub rocks: usize,
@@ -12,21 +18,40 @@ pub struct EnvironmentConfig {
     pub initial_temperature: Temperature,
     pub initial_humidity: Humidity,
     pub soil_type: SoilType,
+    pub room_temperature: Temperature,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SoilType {
     Porous,
     NonPorous,
     Balanced,
+    Peat,
+    Clay,
+    Sand,
+    ActivatedCharcoal,
 }
 
 impl SoilType {
+    pub const ALL: [SoilType; 7] = [
+        SoilType::Porous,
+        SoilType::NonPorous,
+        SoilType::Balanced,
+        SoilType::Peat,
+        SoilType::Clay,
+        SoilType::Sand,
+        SoilType::ActivatedCharcoal,
+    ];
+
     pub fn aeration_modifier(&self) -> f32 {
         match self {
             SoilType::Porous => 1.5,
             SoilType::NonPorous => 0.7,
             SoilType::Balanced => 1.0,
+            SoilType::Peat => 1.2,
+            SoilType::Clay => 0.5,
+            SoilType::Sand => 1.6,
+            SoilType::ActivatedCharcoal => 1.4,
         }
     }
 
@@ -35,6 +60,10 @@ impl SoilType {
             SoilType::Porous => 0.8,
             SoilType::NonPorous => 1.3,
             SoilType::Balanced => 1.0,
+            SoilType::Peat => 1.6,
+            SoilType::Clay => 1.4,
+            SoilType::Sand => 0.5,
+            SoilType::ActivatedCharcoal => 0.9,
         }
     }
 
@@ -43,6 +72,78 @@ impl SoilType {
             SoilType::Porous => 1.2,
             SoilType::NonPorous => 0.9,
             SoilType::Balanced => 1.0,
+            SoilType::Peat => 1.1,
+            SoilType::Clay => 0.9,
+            SoilType::Sand => 0.7,
+            SoilType::ActivatedCharcoal => 1.3,
+        }
+    }
+
+    /// pH the soil drifts back toward on its own
+    pub fn native_ph(&self) -> f32 {
+        match self {
+            SoilType::Porous => 6.8,
+            SoilType::NonPorous => 7.0,
+            SoilType::Balanced => 7.0,
+            SoilType::Peat => 5.0,
+            SoilType::Clay => 7.2,
+            SoilType::Sand => 6.5,
+            SoilType::ActivatedCharcoal => 8.0,
+        }
+    }
+
+    /// Fraction of the gap to the native pH closed per half-day
+    pub fn ph_buffering(&self) -> f32 {
+        match self {
+            SoilType::Porous => 0.3,
+            SoilType::NonPorous => 0.45,
+            SoilType::Balanced => 0.4,
+            SoilType::Peat => 0.35,
+            SoilType::Clay => 0.6,
+            SoilType::Sand => 0.15,
+            SoilType::ActivatedCharcoal => 0.5,
+        }
+    }
+
+    /// Fraction of soil nitrogen held against leaching
+    pub fn nitrogen_retention(&self) -> f32 {
+        match self {
+            SoilType::Porous => 0.75,
+            SoilType::NonPorous => 0.9,
+            SoilType::Balanced => 0.85,
+            SoilType::Peat => 0.85,
+            SoilType::Clay => 0.95,
+            SoilType::Sand => 0.6,
+            SoilType::ActivatedCharcoal => 0.9,
+        }
+    }
+
+    /// Fraction of dissolved ammonia bound per half-day; charcoal is an adsorbent
+    pub fn ammonia_adsorption(&self) -> f32 {
+        match self {
+            SoilType::ActivatedCharcoal => 0.25,
+            SoilType::Clay => 0.05,
+            SoilType::Peat => 0.03,
+            _ => 0.0,
+        }
+    }
+
+    /// How quickly moisture above or below field capacity evens out; the inverse of retention
+    pub fn drainage_rate(&self) -> f32 {
+        0.5 / self.water_retention_modifier()
+    }
+}
+
+impl std::fmt::Display for SoilType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoilType::Porous => write!(f, "Porous"),
+            SoilType::NonPorous => write!(f, "Non-porous"),
+            SoilType::Balanced => write!(f, "Balanced"),
+            SoilType::Peat => write!(f, "Peat"),
+            SoilType::Clay => write!(f, "Clay"),
+            SoilType::Sand => write!(f, "Sand"),
+            SoilType::ActivatedCharcoal => write!(f, "Activated charcoal"),
         }
     }
 }
@@ -63,27 +164,22 @@ impl EnvironmentConfig {
             initial_temperature: Temperature::new(initial_temperature)?,
             initial_humidity: Humidity::new(initial_humidity)?,
             soil_type,
+            room_temperature: Temperature::new(DEFAULT_ROOM_TEMPERATURE)?,
         })
     }
 
-    pub fn validate(&self) -> EcosystemResult<()> {
-        if self.window_proximity > 6 {
-            return Err(EcosystemError::ConfigurationError {
-                message: format!("Window proximity {} must be 0-6", self.window_proximity),
-            });
-        }
-
-        if self.rocks > 10 {
-            return Err(EcosystemError::ConfigurationError {
-                message: format!("Too many rocks: {} (max 10)", self.rocks),
-            });
-        }
+    pub fn with_room_temperature(mut self, celsius: f32) -> EcosystemResult<Self> {
+        self.room_temperature = Temperature::new(celsius)?;
+        Ok(self)
+    }
 
-        Ok(())
+    pub fn validate(&self) -> EcosystemResult<()> {
+        validation::validate_with(|report| validation::check_environment(self, report))
     }
 
+    /// Peak (noon) light level for the configured placement
     pub fn light_level(&self) -> f32 {
-        (6 - self.window_proximity) as f32
+        crate::v2::environmental::placement_light(self.window_proximity)
     }
 
     pub fn is_near_window(&self) -> bool {
@@ -104,6 +200,7 @@ impl Default for EnvironmentConfig {
             initial_temperature: Temperature::new(22.0).unwrap(),
             initial_humidity: Humidity::new(60.0).unwrap(),
             soil_type: SoilType::Balanced,
+            room_temperature: Temperature::new(DEFAULT_ROOM_TEMPERATURE).unwrap(),
         }
     }
 }
\ No newline at end of file
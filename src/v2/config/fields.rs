@@ -0,0 +1,181 @@
+// v2/config/fields.rs
+// Numeric access to configuration fields by their path, for sweeps and other tools
+
+use super::V2Config;
+use super::environment::SoilType;
+use super::parameters::PhModel;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::types::*;
+
+/// Expands to the list of plain `f32` parameter paths and a getter and setter for them
+macro_rules! rate_fields {
+    ($($($field:ident).+),+ $(,)?) => {
+        pub(crate) const RATE_FIELDS: &[&str] = &[$(stringify!($($field).+)),+];
+
+        pub(crate) fn rate(config: &V2Config, path: &str) -> Option<f32> {
+            $(
+                if path == stringify!($($field).+) {
+                    return Some(config.$($field).+);
+                }
+            )+
+            None
+        }
+
+        fn set_rate(config: &mut V2Config, path: &str, value: f32) -> bool {
+            $(
+                if path == stringify!($($field).+) {
+                    config.$($field).+ = value;
+                    return true;
+                }
+            )+
+            false
+        }
+    };
+}
+
+rate_fields!(
+    parameters.photosynthesis.base_rate,
+    parameters.photosynthesis.co2_efficiency,
+    parameters.photosynthesis.light_dependency,
+    parameters.photosynthesis.humidity_dependency,
+    parameters.respiration.base_rate,
+    parameters.respiration.co2_production,
+    parameters.microbial.nitrogen_fixation_rate,
+    parameters.microbial.growth_rate,
+    parameters.microbial.death_rate,
+    parameters.microbial.respiration_rate,
+    parameters.microbial.respiration_co2_ratio,
+    parameters.worm.aeration_rate,
+    parameters.worm.decomposition_rate,
+    parameters.worm.growth_rate,
+    parameters.worm.death_rate,
+    parameters.shrimp.detritus_consumption_rate,
+    parameters.shrimp.growth_rate,
+    parameters.shrimp.death_rate,
+    parameters.environmental.ph_acidification_rate,
+    parameters.environmental.rock_buffer_rate,
+    parameters.environmental.water_buffer_rate,
+    parameters.climate.solar_gain,
+    parameters.climate.heat_loss_rate,
+    parameters.climate.night_temperature_drop,
+    parameters.climate.evaporation_rate,
+    parameters.climate.transpiration_rate,
+    parameters.climate.condensation_rate,
+    parameters.climate.glass_coupling,
+    parameters.nitrogen.nitritation_rate,
+    parameters.nitrogen.nitratation_rate,
+    parameters.nitrogen.nitrifier_half_saturation,
+    parameters.nitrogen.denitrification_rate,
+    parameters.nitrogen.fixation_inhibition,
+    parameters.carbonate.initial_alkalinity,
+    parameters.carbonate.gas_exchange_rate,
+    parameters.carbonate.limestone_dissolution_rate,
+    parameters.carbonate.soil_water_coupling,
+    parameters.mass_balance.tolerance,
+    parameters.integration.tolerance,
+    organisms.plants.initial_biomass,
+);
+
+/// Fields whose values need converting or checking
+const OTHER_FIELDS: &[&str] = &[
+    "parameters.ph_model",
+    "parameters.integration.sub_steps",
+    "environment.water_volume",
+    "environment.rocks",
+    "environment.window_proximity",
+    "environment.initial_temperature",
+    "environment.initial_humidity",
+    "environment.soil_type",
+    "environment.room_temperature",
+    "organisms.microbes.initial_count",
+    "organisms.worms.initial_count",
+    "organisms.shrimp.initial_count",
+];
+
+/// Short names for the inputs the Monte Carlo samples
+const ALIASES: [(&str, &str); 11] = [
+    ("water_volume", "environment.water_volume"),
+    ("rocks", "environment.rocks"),
+    ("window_proximity", "environment.window_proximity"),
+    ("temperature", "environment.initial_temperature"),
+    ("humidity", "environment.initial_humidity"),
+    ("room_temperature", "environment.room_temperature"),
+    ("soil_type", "environment.soil_type"),
+    ("microbes", "organisms.microbes.initial_count"),
+    ("worms", "organisms.worms.initial_count"),
+    ("shrimp", "organisms.shrimp.initial_count"),
+    ("plant_biomass", "organisms.plants.initial_biomass"),
+];
+
+/// Config path of a sampled input's short name; anything else is taken to be a path already
+pub(crate) fn field_path(name: &str) -> &str {
+    ALIASES.iter().find(|(alias, _)| *alias == name).map_or(name, |(_, path)| path)
+}
+
+impl V2Config {
+    /// Every path `set_field` accepts
+    pub fn numeric_fields() -> Vec<&'static str> {
+        RATE_FIELDS.iter().chain(OTHER_FIELDS).copied().collect()
+    }
+
+    /// Set a field from a number. Counts are rounded, `parameters.ph_model` takes 0 (linear)
+    /// or 1 (carbonate) and `environment.soil_type` an index into `SoilType::ALL`
+    pub fn set_field(&mut self, path: &str, value: f32) -> EcosystemResult<()> {
+        if set_rate(self, path, value) {
+            return Ok(());
+        }
+
+        let count = || match value {
+            value if value.is_finite() && value >= 0.0 => Ok(value.round() as usize),
+            value => Err(EcosystemError::ConfigurationError {
+                message: format!("{}: {} is not a count", path, value),
+            }),
+        };
+        match path {
+            "parameters.ph_model" => {
+                self.parameters.ph_model = if value < 0.5 { PhModel::Linear } else { PhModel::Carbonate };
+            }
+            "parameters.integration.sub_steps" => self.parameters.integration.sub_steps = count()?.max(1) as u32,
+            "environment.water_volume" => self.environment.water_volume = WaterVolume::new(value)?,
+            "environment.rocks" => self.environment.rocks = count()?,
+            "environment.window_proximity" => self.environment.window_proximity = count()?.min(u8::MAX as usize) as u8,
+            "environment.initial_temperature" => self.environment.initial_temperature = Temperature::new(value)?,
+            "environment.initial_humidity" => self.environment.initial_humidity = Humidity::new(value)?,
+            "environment.soil_type" => {
+                self.environment.soil_type = *SoilType::ALL.get(count()?).ok_or_else(|| EcosystemError::ConfigurationError {
+                    message: format!("Soil type index {} is out of range (0 to {})", value, SoilType::ALL.len() - 1),
+                })?;
+            }
+            "environment.room_temperature" => self.environment.room_temperature = Temperature::new(value)?,
+            "organisms.microbes.initial_count" => self.organisms.microbes.initial_count = count()?,
+            "organisms.worms.initial_count" => self.organisms.worms.initial_count = count()?,
+            "organisms.shrimp.initial_count" => self.organisms.shrimp.initial_count = count()?,
+            _ => {
+                return Err(EcosystemError::ConfigurationError {
+                    message: format!("Unknown configuration field '{}'", path),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_every_field() {
+        let mut config = V2Config::new();
+        for path in V2Config::numeric_fields() {
+            config.set_field(path, 1.0).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        }
+        assert_eq!(config.parameters.worm.death_rate, 1.0);
+        assert_eq!(config.environment.soil_type, SoilType::ALL[1]);
+        assert_eq!(config.parameters.ph_model, PhModel::Carbonate);
+
+        assert!(config.set_field("parameters.worm.colour", 1.0).is_err());
+        assert!(config.set_field("environment.soil_type", 99.0).is_err());
+        assert!(config.set_field("environment.rocks", -5.0).is_err());
+    }
+}
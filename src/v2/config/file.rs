@@ -0,0 +1,168 @@
+// v2/config/file.rs
+// Reading and writing whole configurations as TOML or JSON
+
+use super::V2Config;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Chosen by the file extension
+    pub fn from_path(path: &str) -> EcosystemResult<Self> {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("json") => Ok(ConfigFormat::Json),
+            _ => Err(invalid(format!("Cannot tell the format of '{}' (expected a .toml or .json file)", path))),
+        }
+    }
+}
+
+fn invalid(message: String) -> EcosystemError {
+    EcosystemError::ConfigurationError { message }
+}
+
+/// `context: reason`, without repeating the "Configuration error" prefix of `reason`
+fn locate(context: &str, reason: impl std::fmt::Display) -> EcosystemError {
+    let prefix = invalid(String::new()).to_string();
+    let reason = reason.to_string();
+    invalid(format!("{}: {}", context, reason.strip_prefix(&prefix).unwrap_or(&reason)))
+}
+
+fn read(path: &str) -> EcosystemResult<(String, ConfigFormat)> {
+    let format = ConfigFormat::from_path(path)?;
+    let text = std::fs::read_to_string(path).map_err(|e| invalid(format!("Could not read {}: {}", path, e)))?;
+    Ok((text, format))
+}
+
+impl V2Config {
+    pub fn load(path: &str) -> EcosystemResult<Self> {
+        let (text, format) = read(path)?;
+        Self::parse(&text, format).map_err(|e| locate(path, e))
+    }
+
+    /// Read a file without validating it, so `check` can report on everything in it
+    pub fn load_unchecked(path: &str) -> EcosystemResult<Self> {
+        let (text, format) = read(path)?;
+        Self::parse_unchecked(&text, format).map_err(|e| locate(path, e))
+    }
+
+    pub fn save(&self, path: &str) -> EcosystemResult<()> {
+        let text = self.to_text(ConfigFormat::from_path(path)?)?;
+        std::fs::write(path, text).map_err(|e| invalid(format!("Could not write {}: {}", path, e)))
+    }
+
+    /// Fields left out keep their values at the difficulty the text gives, or those of
+    /// `V2Config::new` if it gives none, so a file only needs what it changes
+    pub fn parse(text: &str, format: ConfigFormat) -> EcosystemResult<Self> {
+        let config = Self::parse_unchecked(text, format)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn parse_unchecked(text: &str, format: ConfigFormat) -> EcosystemResult<Self> {
+        let overrides: Value = match format {
+            ConfigFormat::Toml => toml::from_str(text).map_err(|e| invalid(e.to_string().trim_end().to_string()))?,
+            ConfigFormat::Json => serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?,
+        };
+        let base = match overrides.get("difficulty").and_then(Value::as_f64) {
+            // An invalid level is reported against `difficulty` below
+            Some(level) => V2Config::with_difficulty(level as f32).unwrap_or_default(),
+            None => V2Config::new(),
+        };
+
+        let mut merged = to_value(&base)?;
+        merge(&mut merged, overrides);
+        serde_path_to_error::deserialize(merged)
+            .map_err(|e| locate(&e.path().to_string(), e.inner()))
+    }
+
+    pub fn to_text(&self, format: ConfigFormat) -> EcosystemResult<String> {
+        let value = to_value(self)?;
+        match format {
+            ConfigFormat::Toml => toml::to_string_pretty(&value).map_err(|e| invalid(e.to_string())),
+            ConfigFormat::Json => serde_json::to_string_pretty(&value).map_err(|e| invalid(e.to_string())),
+        }
+    }
+}
+
+/// Every float in a config is an `f32`, which widens on the way out (0.1 becomes
+/// 0.10000000149011612); write each as the shortest decimal that reads back the same
+fn to_value(config: &V2Config) -> EcosystemResult<Value> {
+    fn tidy(value: &mut Value) {
+        match value {
+            Value::Number(number) if number.is_f64() => {
+                let short = number.as_f64().and_then(|x| (x as f32).to_string().parse::<f64>().ok());
+                if let Some(short) = short.and_then(serde_json::Number::from_f64) {
+                    *number = short;
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(tidy),
+            Value::Object(fields) => fields.values_mut().for_each(tidy),
+            _ => {}
+        }
+    }
+
+    let mut value = serde_json::to_value(config).map_err(|e| invalid(e.to_string()))?;
+    tidy(&mut value);
+    Ok(value)
+}
+
+/// Lay `overrides` over `base`, table by table
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_partial_files() {
+        let mut config = V2Config::with_difficulty(0.8).unwrap();
+        config.parameters.worm.death_rate = 0.1;
+        config.remove_species("shrimp");
+        for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+            let text = config.to_text(format).unwrap();
+            let loaded = V2Config::parse(&text, format).unwrap();
+            assert_eq!(loaded.to_text(format).unwrap(), text);
+            assert_eq!(loaded.species.names(), vec!["plants", "microbes", "worms"]);
+        }
+
+        // Anything not given comes from the difficulty
+        let partial = V2Config::parse("difficulty = 0.8\n[parameters.worm]\ndeath_rate = 0.1\n", ConfigFormat::Toml).unwrap();
+        let hard = V2Config::with_difficulty(0.8).unwrap();
+        assert_eq!(partial.parameters.worm.death_rate, 0.1);
+        assert_eq!(partial.parameters.worm.growth_rate, hard.parameters.worm.growth_rate);
+    }
+
+    #[test]
+    fn test_errors_name_the_field() {
+        let error = |text: &str| V2Config::parse(text, ConfigFormat::Toml).unwrap_err().to_string();
+        assert!(error("[parameters.worm]\ndeath_rate = \"fast\"\n").contains("parameters.worm.death_rate"));
+        assert!(error("[parameters.worm]\ndeath_rte = 0.1\n").contains("parameters.worm"));
+        assert!(error("[environment]\ninitial_temperature = 99.0\n").contains("environment.initial_temperature"));
+        assert!(error("difficulty = 1.5\n").contains("difficulty"));
+        assert!(error("species = [\"plants\", \"dragons\"]\n").contains("species"));
+    }
+}
@@ -3,15 +3,25 @@
 
 pub mod difficulty;
 pub mod environment;
+mod fields;
+mod file;
 pub mod organisms;
 pub mod parameters;
+pub mod validation;
 
-use crate::v2::errors::{EcosystemError, EcosystemResult};
+pub use file::ConfigFormat;
+pub(crate) use fields::{field_path, rate, RATE_FIELDS};
+
+use crate::v2::errors::EcosystemResult;
+use crate::v2::organisms::OrganismRegistry;
 use crate::v2::types::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct V2Config {
     pub organisms: organisms::OrganismConfig,
+    pub species: OrganismRegistry,
     pub environment: environment::EnvironmentConfig,
     pub parameters: parameters::SimulationParameters,
     pub difficulty: difficulty::DifficultyConfig,
@@ -29,12 +39,21 @@ impl V2Config {
         Ok(config)
     }
 
+    /// Fails with every error `check` finds
     pub fn validate(&self) -> EcosystemResult<()> {
-        self.organisms.validate()?;
-        self.environment.validate()?;
-        self.parameters.validate()?;
-        self.difficulty.validate()?;
-        Ok(())
+        self.check().into_result()
+    }
+
+    /// Leave a species out of the bottle entirely
+    pub fn remove_species(&mut self, name: &str) -> bool {
+        match name {
+            "plants" => self.organisms.plants.initial_biomass = 0.0,
+            "microbes" => self.organisms.microbes.initial_count = 0,
+            "worms" => self.organisms.worms.initial_count = 0,
+            "shrimp" => self.organisms.shrimp.initial_count = 0,
+            _ => {}
+        }
+        self.species.remove(name)
     }
 
     pub fn window_proximity(&self) -> u8 {
@@ -74,6 +93,7 @@ impl Default for V2Config {
     fn default() -> Self {
         Self {
             organisms: organisms::OrganismConfig::default(),
+            species: OrganismRegistry::standard(),
             environment: environment::EnvironmentConfig::default(),
             parameters: parameters::SimulationParameters::default(),
             difficulty: difficulty::DifficultyConfig::default(),
@@ -118,6 +138,7 @@ impl From<LegacyV2Config> for V2Config {
                 shrimp: organisms::ShrimpConfig { initial_count: legacy.num_shrimp },
                 plants: organisms::PlantConfig { initial_biomass: 1.0 },
             },
+            species: OrganismRegistry::standard(),
             environment: environment::EnvironmentConfig {
                 water_volume: WaterVolume::new(legacy.water_liters).unwrap_or_else(|_| WaterVolume::new(0.5).unwrap()),
                 rocks: legacy.rocks,
@@ -125,6 +146,7 @@ impl From<LegacyV2Config> for V2Config {
                 initial_temperature: Temperature::new(legacy.initial_temp).unwrap_or_else(|_| Temperature::new(22.0).unwrap()),
                 initial_humidity: Humidity::new(legacy.initial_humidity).unwrap_or_else(|_| Humidity::new(60.0).unwrap()),
                 soil_type: environment::SoilType::Balanced,
+                room_temperature: Temperature::new(environment::DEFAULT_ROOM_TEMPERATURE).unwrap(),
             },
             parameters: parameters::SimulationParameters::default(),
             difficulty: difficulty::DifficultyConfig::default(),
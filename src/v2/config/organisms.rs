@@ -1,9 +1,12 @@
 // v2/config/organisms.rs
 // Organism configuration for ecosystem simulation
 
-use crate::v2::errors::{EcosystemError, EcosystemResult};
+use super::validation;
+use crate::v2::errors::EcosystemResult;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct OrganismConfig {
     pub microbes: MicrobeConfig,
     pub worms: WormConfig,
@@ -11,22 +14,26 @@ pub struct OrganismConfig {
     pub plants: PlantConfig,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct MicrobeConfig {
     pub initial_count: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct WormConfig {
     pub initial_count: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ShrimpConfig {
     pub initial_count: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PlantConfig {
     pub initial_biomass: f32,
 }
@@ -50,45 +57,8 @@ impl OrganismConfig {
     }
 
     pub fn validate(&self) -> EcosystemResult<()> {
-        // Validate microbes
-        if self.microbes.initial_count == 0 {
-            return Err(EcosystemError::ConfigurationError {
-                message: "Microbe count cannot be zero".to_string(),
-            });
-        }
-        if self.microbes.initial_count > 100_000 {
-            return Err(EcosystemError::ConfigurationError {
-                message: format!("Too many microbes: {} (max 100,000)", self.microbes.initial_count),
-            });
-        }
-
-        // Validate worms
-        if self.worms.initial_count > 50 {
-            return Err(EcosystemError::ConfigurationError {
-                message: format!("Too many worms: {} (max 50)", self.worms.initial_count),
-            });
-        }
-
-        // Validate shrimp
-        if self.shrimp.initial_count > 20 {
-            return Err(EcosystemError::ConfigurationError {
-                message: format!("Too many shrimp: {} (max 20)", self.shrimp.initial_count),
-            });
-        }
-
-        // Validate plants
-        if self.plants.initial_biomass <= 0.0 {
-            return Err(EcosystemError::ConfigurationError {
-                message: "Plant biomass must be positive".to_string(),
-            });
-        }
-        if self.plants.initial_biomass > 100.0 {
-            return Err(EcosystemError::ConfigurationError {
-                message: format!("Too much initial plant biomass: {} (max 100)", self.plants.initial_biomass),
-            });
-        }
-
-        Ok(())
+        // Zero counts are fine here; V2Config checks them against the species in the bottle
+        validation::validate_with(|report| validation::check_organisms(self, report))
     }
 
     pub fn total_organisms(&self) -> usize {
@@ -2,9 +2,12 @@
 // Simulation parameters for ecosystem modeling
 
 use crate::v2::config::difficulty::DifficultyConfig;
+use crate::v2::config::validation;
 use crate::v2::errors::{EcosystemError, EcosystemResult};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SimulationParameters {
     pub photosynthesis: PhotosynthesisParams,
     pub respiration: RespirationParams,
@@ -12,9 +15,84 @@ pub struct SimulationParameters {
     pub worm: WormParams,
     pub shrimp: ShrimpParams,
     pub environmental: EnvironmentalParams,
+    pub climate: ClimateParams,
+    pub nitrogen: NitrogenCycleParams,
+    pub ph_model: PhModel,
+    pub carbonate: CarbonateParams,
+    pub mass_balance: MassBalanceParams,
+    pub integration: IntegrationParams,
+    pub collapse: CollapseCriteria,
 }
 
-#[derive(Debug, Clone)]
+/// How water and soil pH are simulated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PhModel {
+    /// Soil and water share one pH nudged by fixed microbe, rock and water terms
+    #[default]
+    Linear,
+    /// Water pH is solved from dissolved inorganic carbon and alkalinity
+    Carbonate,
+}
+
+/// Which deaths end the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CollapseCriteria {
+    /// Losing any species in the bottle collapses it
+    #[default]
+    AnySpecies,
+    /// Only losing every producer or every decomposer collapses the bottle
+    ProducersOrDecomposers,
+    /// The bottle lives on while anything in it is alive
+    AllSpecies,
+}
+
+impl std::str::FromStr for CollapseCriteria {
+    type Err = EcosystemError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "any" => Ok(CollapseCriteria::AnySpecies),
+            "producers-or-decomposers" => Ok(CollapseCriteria::ProducersOrDecomposers),
+            "all" => Ok(CollapseCriteria::AllSpecies),
+            _ => Err(EcosystemError::ConfigurationError {
+                message: format!("Unknown collapse rule '{}'; expected any, producers-or-decomposers or all", name),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for CollapseCriteria {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollapseCriteria::AnySpecies => write!(f, "any species dies out"),
+            CollapseCriteria::ProducersOrDecomposers => write!(f, "all producers or all decomposers die out"),
+            CollapseCriteria::AllSpecies => write!(f, "everything dies out"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CarbonateParams {
+    pub initial_alkalinity: f32,
+    pub gas_exchange_rate: f32,
+    pub limestone_dissolution_rate: f32,
+    pub soil_water_coupling: f32,
+}
+
+impl Default for CarbonateParams {
+    fn default() -> Self {
+        Self {
+            initial_alkalinity: 1.0,
+            gas_exchange_rate: 2.0,
+            limestone_dissolution_rate: 0.02,
+            soil_water_coupling: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PhotosynthesisParams {
     pub base_rate: f32,
     pub co2_efficiency: f32,
@@ -22,13 +100,15 @@ pub struct PhotosynthesisParams {
     pub humidity_dependency: f32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RespirationParams {
     pub base_rate: f32,
     pub co2_production: f32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct MicrobialParams {
     pub nitrogen_fixation_rate: f32,
     pub growth_rate: f32,
@@ -37,7 +117,8 @@ pub struct MicrobialParams {
     pub respiration_co2_ratio: f32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct WormParams {
     pub aeration_rate: f32,
     pub decomposition_rate: f32,
@@ -45,20 +126,140 @@ pub struct WormParams {
     pub death_rate: f32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ShrimpParams {
     pub detritus_consumption_rate: f32,
-    pub waste_production_rate: f32,
     pub growth_rate: f32,
     pub death_rate: f32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct EnvironmentalParams {
     pub ph_acidification_rate: f32,
     pub rock_buffer_rate: f32,
     pub water_buffer_rate: f32,
-    pub plant_nitrogen_uptake: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClimateParams {
+    pub solar_gain: f32,
+    pub heat_loss_rate: f32,
+    pub night_temperature_drop: f32,
+    pub evaporation_rate: f32,
+    pub transpiration_rate: f32,
+    pub condensation_rate: f32,
+    pub glass_coupling: f32,
+}
+
+impl Default for ClimateParams {
+    fn default() -> Self {
+        Self {
+            solar_gain: 20.0,
+            heat_loss_rate: 15.0,
+            night_temperature_drop: 3.0,
+            evaporation_rate: 3.0,
+            transpiration_rate: 0.5,
+            condensation_rate: 4.0,
+            glass_coupling: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NitrogenCycleParams {
+    pub nitritation_rate: f32,
+    pub nitratation_rate: f32,
+    pub nitrifier_half_saturation: f32,
+    pub denitrification_rate: f32,
+    pub fixation_inhibition: f32,
+}
+
+impl Default for NitrogenCycleParams {
+    fn default() -> Self {
+        Self {
+            nitritation_rate: 0.6,
+            nitratation_rate: 0.8,
+            nitrifier_half_saturation: 500.0,
+            denitrification_rate: 0.05,
+            fixation_inhibition: 0.2,
+        }
+    }
+}
+
+/// How the C/O/N ledger reacts to matter appearing or vanishing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MassBalanceParams {
+    pub strict: bool,
+    pub tolerance: f32,
+}
+
+impl Default for MassBalanceParams {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            tolerance: 0.01,
+        }
+    }
+}
+
+/// Numerical scheme used to advance the state through each phase of the day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IntegrationMethod {
+    /// Explicit Euler; cheap, needs several sub-steps to stay stable
+    #[default]
+    Euler,
+    /// Classic fourth-order Runge-Kutta
+    Rk4,
+    /// Adaptive Dormand-Prince 5(4), starting from the sub-step size
+    Rk45,
+}
+
+impl std::str::FromStr for IntegrationMethod {
+    type Err = EcosystemError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "euler" => Ok(IntegrationMethod::Euler),
+            "rk4" => Ok(IntegrationMethod::Rk4),
+            "rk45" => Ok(IntegrationMethod::Rk45),
+            _ => Err(EcosystemError::ConfigurationError {
+                message: format!("Unknown integrator '{}'; expected euler, rk4 or rk45", name),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for IntegrationMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrationMethod::Euler => write!(f, "Euler"),
+            IntegrationMethod::Rk4 => write!(f, "RK4"),
+            IntegrationMethod::Rk45 => write!(f, "RK45"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IntegrationParams {
+    pub method: IntegrationMethod,
+    pub sub_steps: u32,
+    pub tolerance: f32,
+}
+
+impl Default for IntegrationParams {
+    fn default() -> Self {
+        Self {
+            method: IntegrationMethod::default(),
+            sub_steps: 8,
+            tolerance: 1e-4,
+        }
+    }
 }
 
 impl SimulationParameters {
@@ -90,39 +291,12 @@ impl SimulationParameters {
         self.environmental.rock_buffer_rate *= 1.0 - scaling.buffer_reduction;
         self.environmental.water_buffer_rate *= 1.0 - scaling.buffer_reduction;
 
-        // Increase nutrient uptake with difficulty
-        self.environmental.plant_nitrogen_uptake *= 1.0 + scaling.growth_penalty;
+        // Acidify faster with difficulty
         self.environmental.ph_acidification_rate *= 1.0 + scaling.death_rate_increase;
     }
 
     pub fn validate(&self) -> EcosystemResult<()> {
-        // Validate that all rates are positive
-        if self.photosynthesis.base_rate <= 0.0 {
-            return Err(EcosystemError::ConfigurationError {
-                message: "Photosynthesis base rate must be positive".to_string(),
-            });
-        }
-
-        if self.respiration.base_rate <= 0.0 {
-            return Err(EcosystemError::ConfigurationError {
-                message: "Respiration base rate must be positive".to_string(),
-            });
-        }
-
-        if self.microbial.growth_rate <= 0.0 {
-            return Err(EcosystemError::ConfigurationError {
-                message: "Microbial growth rate must be positive".to_string(),
-            });
-        }
-
-        // Validate ranges
-        if self.photosynthesis.co2_efficiency > 5.0 {
-            return Err(EcosystemError::ConfigurationError {
-                message: "CO2 efficiency too high".to_string(),
-            });
-        }
-
-        Ok(())
+        validation::validate_with(|report| validation::check_parameters(self, report))
     }
 
     pub fn balanced() -> Self {
@@ -156,7 +330,6 @@ impl SimulationParameters {
             },
             shrimp: ShrimpParams {
                 detritus_consumption_rate: 0.008,
-                waste_production_rate: 0.004,
                 growth_rate: 0.008,
                 death_rate: 0.003,
             },
@@ -164,8 +337,14 @@ impl SimulationParameters {
                 ph_acidification_rate: 0.0008,
                 rock_buffer_rate: 0.0015,
                 water_buffer_rate: 0.0008,
-                plant_nitrogen_uptake: 0.0015,
             },
+            climate: ClimateParams::default(),
+            nitrogen: NitrogenCycleParams::default(),
+            ph_model: PhModel::default(),
+            carbonate: CarbonateParams::default(),
+            mass_balance: MassBalanceParams::default(),
+            integration: IntegrationParams::default(),
+            collapse: CollapseCriteria::default(),
         }
     }
 }
@@ -198,7 +377,6 @@ impl Default for SimulationParameters {
             },
             shrimp: ShrimpParams {
                 detritus_consumption_rate: 0.01,
-                waste_production_rate: 0.005,
                 growth_rate: 0.01,
                 death_rate: 0.005,
             },
@@ -206,8 +384,14 @@ impl Default for SimulationParameters {
                 ph_acidification_rate: 0.001,
                 rock_buffer_rate: 0.002,
                 water_buffer_rate: 0.001,
-                plant_nitrogen_uptake: 0.002,
             },
+            climate: ClimateParams::default(),
+            nitrogen: NitrogenCycleParams::default(),
+            ph_model: PhModel::default(),
+            carbonate: CarbonateParams::default(),
+            mass_balance: MassBalanceParams::default(),
+            integration: IntegrationParams::default(),
+            collapse: CollapseCriteria::default(),
         }
     }
 }
\ No newline at end of file
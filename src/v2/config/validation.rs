@@ -0,0 +1,350 @@
+// v2/config/validation.rs
+// Checks every field of a configuration and reports all the problems at once
+
+use super::V2Config;
+use super::difficulty::DifficultyConfig;
+use super::environment::EnvironmentConfig;
+use super::fields::{RATE_FIELDS, rate};
+use super::organisms::OrganismConfig;
+use super::parameters::SimulationParameters;
+use crate::v2::climate::{dew_point, saturation_vapor_pressure};
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use std::fmt;
+
+/// Rates the simulation divides by or cannot do without
+const REQUIRED_RATES: [&str; 5] = [
+    "parameters.photosynthesis.base_rate",
+    "parameters.respiration.base_rate",
+    "parameters.microbial.growth_rate",
+    "parameters.climate.heat_loss_rate",
+    "parameters.integration.tolerance",
+];
+
+/// Species, where its starting amount lives, the most allowed and the usual range
+const STOCKING: [(&str, &str, f32, (f32, f32)); 4] = [
+    ("plants", "organisms.plants.initial_biomass", 100.0, (0.5, 3.0)),
+    ("microbes", "organisms.microbes.initial_count", 100_000.0, (100.0, 5000.0)),
+    ("worms", "organisms.worms.initial_count", 50.0, (1.0, 15.0)),
+    ("shrimp", "organisms.shrimp.initial_count", 20.0, (1.0, 8.0)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Runs, but is probably not what was meant
+    Warning,
+    /// The configuration is rejected
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// Field path as used in configuration files, e.g. `parameters.worm.death_rate`
+    pub path: String,
+    pub severity: Severity,
+    pub message: String,
+    /// Range the value should be moved into
+    pub suggested: Option<(f32, f32)>,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.severity, self.path, self.message)?;
+        if let Some((low, high)) = self.suggested {
+            write!(f, " (suggested {} – {})", significant(low), significant(high))?;
+        }
+        Ok(())
+    }
+}
+
+/// Three significant digits, so a tenth of 0.01 prints as 0.001 rather than 0.0009999999
+fn significant(value: f32) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return value.to_string();
+    }
+    let decimals = (3 - value.abs().log10().ceil() as i32).max(0) as usize;
+    let text = format!("{:.*}", decimals, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    fn push(&mut self, severity: Severity, path: &str, message: String, suggested: Option<(f32, f32)>) {
+        self.issues.push(Issue { path: path.to_string(), severity, message, suggested });
+    }
+
+    fn error(&mut self, path: &str, message: String, suggested: Option<(f32, f32)>) {
+        self.push(Severity::Error, path, message, suggested);
+    }
+
+    fn warning(&mut self, path: &str, message: String, suggested: Option<(f32, f32)>) {
+        self.push(Severity::Warning, path, message, suggested);
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// Every error in one `ConfigurationError`; warnings alone pass
+    pub fn into_result(self) -> EcosystemResult<()> {
+        let errors: Vec<String> = self.errors().map(|issue| issue.to_string()).collect();
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(EcosystemError::ConfigurationError { message: errors[0].clone() }),
+            n => Err(EcosystemError::ConfigurationError {
+                message: format!("{} problems\n   {}", n, errors.join("\n   ")),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run one of the section checks, failing with all of its errors
+pub(super) fn validate_with(check: impl FnOnce(&mut ValidationReport)) -> EcosystemResult<()> {
+    let mut report = ValidationReport::default();
+    check(&mut report);
+    report.into_result()
+}
+
+impl V2Config {
+    /// Every error and warning in the configuration, in field order
+    pub fn check(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        check_species(self, &mut report);
+        check_organisms(&self.organisms, &mut report);
+        check_environment(&self.environment, &mut report);
+        check_parameters(&self.parameters, &mut report);
+        check_difficulty(&self.difficulty, &mut report);
+        report
+    }
+
+    /// Fails if `check` would report an error for `value` at `path`, all else default
+    pub fn check_field(path: &str, value: f32) -> EcosystemResult<()> {
+        let mut config = V2Config::new();
+        config.set_field(path, value)?;
+        match config.check().errors().find(|issue| issue.path == path) {
+            Some(issue) => Err(EcosystemError::ConfigurationError { message: issue.to_string() }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Every species in the bottle needs something to start from
+fn check_species(config: &V2Config, report: &mut ValidationReport) {
+    if config.species.is_empty() {
+        report.error("species", "the bottle needs at least one species".to_string(), None);
+    }
+
+    let organisms = &config.organisms;
+    let amounts = [
+        organisms.plants.initial_biomass,
+        organisms.microbes.initial_count as f32,
+        organisms.worms.initial_count as f32,
+        organisms.shrimp.initial_count as f32,
+    ];
+    for ((species, path, _, usual), amount) in STOCKING.iter().zip(amounts) {
+        let present = config.species.contains(species);
+        if present && amount <= 0.0 {
+            report.error(path, format!("no {} to start with; add some or leave them out of `species`", species), Some(*usual));
+        } else if !present && amount > 0.0 {
+            report.warning(path, format!("is ignored because {} are not in `species`", species), None);
+        }
+    }
+}
+
+pub(super) fn check_organisms(organisms: &OrganismConfig, report: &mut ValidationReport) {
+    let amounts = [
+        organisms.plants.initial_biomass,
+        organisms.microbes.initial_count as f32,
+        organisms.worms.initial_count as f32,
+        organisms.shrimp.initial_count as f32,
+    ];
+    for ((species, path, max, usual), amount) in STOCKING.iter().zip(amounts) {
+        if amount < 0.0 {
+            report.error(path, "cannot be negative".to_string(), Some(*usual));
+        } else if amount > *max {
+            report.error(path, format!("{} is more {} than a bottle can hold (max {})", amount, species, max), Some(*usual));
+        } else if amount > 0.0 && !(usual.0..=usual.1).contains(&amount) {
+            report.warning(path, format!("{} is an unusual amount of {}", amount, species), Some(*usual));
+        }
+    }
+}
+
+pub(super) fn check_environment(environment: &EnvironmentConfig, report: &mut ValidationReport) {
+    let water = environment.water_volume.value();
+    if water < 0.2 {
+        // Seeded starts draw the soil moisture from 0.2 L up to the water volume
+        report.error("environment.water_volume", format!("{} L is too little water", water), Some((0.2, 2.0)));
+    } else if water > 5.0 {
+        report.warning("environment.water_volume", format!("{} L is a very large bottle", water), Some((0.2, 2.0)));
+    }
+    if environment.rocks > 10 {
+        report.error("environment.rocks", format!("{} rocks will not fit (max 10)", environment.rocks), Some((0.0, 10.0)));
+    }
+    if environment.window_proximity > 6 {
+        report.error("environment.window_proximity", format!("{} is past the far wall (0-6)", environment.window_proximity), Some((0.0, 6.0)));
+    }
+
+    let temperature = environment.initial_temperature;
+    if !temperature.is_optimal() {
+        report.warning("environment.initial_temperature",
+                       format!("{} °C is outside the range the organisms do best in", temperature.celsius()), Some((20.0, 28.0)));
+    }
+    let room = environment.room_temperature.celsius();
+    if !(15.0..=30.0).contains(&room) {
+        report.warning("environment.room_temperature", format!("a {} °C room pulls the bottle out of its comfortable range", room), Some((18.0, 26.0)));
+    }
+
+    let humidity = environment.initial_humidity;
+    if !humidity.is_optimal() {
+        report.warning("environment.initial_humidity", format!("{}% is outside the range plants grow best in", humidity.percentage()), Some((40.0, 80.0)));
+    }
+    // Air whose dew point is above the room temperature condenses on the glass at once
+    let dew = dew_point(temperature, humidity);
+    if dew > room {
+        let highest = 100.0 * saturation_vapor_pressure(room) / saturation_vapor_pressure(temperature.celsius());
+        report.warning("environment.initial_humidity",
+                       format!("{}% at {} °C has a dew point of {:.1} °C, above the {} °C room, so the glass fogs straight away",
+                               humidity.percentage(), temperature.celsius(), dew, room),
+                       Some((40.0f32.min(highest), highest.min(80.0))));
+    }
+}
+
+pub(super) fn check_parameters(parameters: &SimulationParameters, report: &mut ValidationReport) {
+    // Rates are read by their paths, so the parameters are looked at through a config
+    let config = V2Config { parameters: parameters.clone(), ..V2Config::new() };
+    let defaults = V2Config::new();
+
+    for path in RATE_FIELDS.iter().filter(|path| path.starts_with("parameters.")) {
+        let (Some(value), Some(default)) = (rate(&config, path), rate(&defaults, path)) else {
+            continue;
+        };
+        let usual = (default / 10.0, default * 10.0);
+        if !value.is_finite() {
+            report.error(path, "is not a number".to_string(), Some(usual));
+        } else if value < 0.0 {
+            report.error(path, format!("{} cannot be negative", value), Some(usual));
+        } else if value == 0.0 && REQUIRED_RATES.contains(path) {
+            report.error(path, "must be positive".to_string(), Some(usual));
+        } else if value == 0.0 {
+            report.warning(path, "is zero, which switches this process off".to_string(), Some(usual));
+        } else if default > 0.0 && !(usual.0..=usual.1).contains(&value) {
+            let side = if value > default { "above" } else { "below" };
+            report.warning(path, format!("{} is more than ten times {} the default of {}", value, side, default), Some(usual));
+        }
+    }
+
+    if parameters.photosynthesis.co2_efficiency > 5.0 {
+        report.error("parameters.photosynthesis.co2_efficiency",
+                     format!("{} is too efficient (max 5)", parameters.photosynthesis.co2_efficiency), Some((0.15, 5.0)));
+    }
+    let sub_steps = parameters.integration.sub_steps;
+    if sub_steps == 0 {
+        report.error("parameters.integration.sub_steps", "at least one sub-step per half-day is needed".to_string(), Some((1.0, 64.0)));
+    } else if sub_steps > 1000 {
+        report.warning("parameters.integration.sub_steps", format!("{} sub-steps per half-day will be slow", sub_steps), Some((1.0, 64.0)));
+    }
+}
+
+pub(super) fn check_difficulty(difficulty: &DifficultyConfig, report: &mut ValidationReport) {
+    if !(0.0..=1.0).contains(&difficulty.level) {
+        report.error("difficulty", format!("level {} must be between 0 and 1", difficulty.level), Some((0.0, 1.0)));
+    }
+
+    // `apply_difficulty` multiplies rates by these factors; one at or below zero flips their sign
+    let scaling = &difficulty.scaling;
+    let factors = [
+        ("difficulty.scaling.photosynthesis_penalty", "photosynthesis", 1.0 - scaling.photosynthesis_penalty, (0.0, 0.7)),
+        ("difficulty.scaling.respiration_increase", "respiration", 1.0 + scaling.respiration_increase, (0.0, 2.0)),
+        ("difficulty.scaling.growth_penalty", "growth", 1.0 - scaling.growth_penalty, (0.0, 0.7)),
+        ("difficulty.scaling.death_rate_increase", "death", 1.0 + scaling.death_rate_increase, (0.0, 1.5)),
+        ("difficulty.scaling.buffer_reduction", "buffering", 1.0 - scaling.buffer_reduction, (0.0, 0.8)),
+    ];
+    for (path, rates, factor, usual) in factors {
+        if factor <= 0.0 {
+            report.error(path, format!("scales {} rates by {:.2}, driving them to zero or below", rates, factor), Some(usual));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_every_problem() {
+        let mut config = V2Config::new();
+        config.parameters.worm.death_rate = -0.1;
+        config.parameters.shrimp.growth_rate = -0.2;
+        config.parameters.microbial.growth_rate = 0.0;
+        config.parameters.nitrogen.denitrification_rate = 50.0;
+        config.environment.rocks = 12;
+        config.difficulty.scaling.growth_penalty = 1.5;
+
+        let report = config.check();
+        let errors: Vec<&str> = report.errors().map(|issue| issue.path.as_str()).collect();
+        assert_eq!(errors, vec![
+            "environment.rocks",
+            "parameters.microbial.growth_rate",
+            "parameters.worm.death_rate",
+            "parameters.shrimp.growth_rate",
+            "difficulty.scaling.growth_penalty",
+        ]);
+        assert!(report.warnings().any(|issue| issue.path == "parameters.nitrogen.denitrification_rate"));
+        assert!(report.issues.iter().all(|issue| issue.suggested.is_some()));
+        assert!(config.validate().unwrap_err().to_string().contains("5 problems"));
+    }
+
+    #[test]
+    fn test_defaults_are_clean() {
+        for level in [0.0, 0.5, 1.0] {
+            let report = V2Config::with_difficulty(level).unwrap().check();
+            assert!(report.issues.is_empty(), "{}", report);
+        }
+    }
+
+    #[test]
+    fn test_humid_air_in_a_cold_room() {
+        let mut config = V2Config::new();
+        config.environment.initial_humidity = crate::v2::types::Humidity::new(95.0).unwrap();
+        config.environment.room_temperature = crate::v2::types::Temperature::new(16.0).unwrap();
+        let report = config.check();
+
+        let fog = report.warnings().find(|issue| issue.message.contains("dew point")).expect("fog warning");
+        let (_, highest) = fog.suggested.unwrap();
+        assert!(highest < 95.0);
+        assert!(!report.has_errors());
+    }
+}
@@ -8,9 +8,17 @@ pub fn temperature_efficiency(temp: Temperature) -> f32 {
     (-((temp.celsius() - 24.0).powi(2)) / 32.0).exp()
 }
 
-/// Humidity efficiency function - increases with humidity, plateaus at 100%
+/// Humidity efficiency function - increases with humidity, plateaus from 60%
+/// and falls off again near saturation, when stomata close and mold takes hold
 pub fn humidity_efficiency(humidity: Humidity) -> f32 {
-    (humidity.percentage() / 100.0).min(1.0)
+    let h = humidity.percentage();
+    if h < 60.0 {
+        h / 60.0
+    } else if h <= 90.0 {
+        1.0
+    } else {
+        1.0 - 0.03 * (h - 90.0)
+    }
 }
 
 /// Light efficiency function - linear increase with light, saturates at 6
@@ -18,6 +26,16 @@ pub fn light_efficiency(light_level: f32) -> f32 {
     (light_level / 6.0).min(1.0)
 }
 
+/// Peak light reaching the bottle at a given distance from the window (0 = on the sill)
+pub fn placement_light(window_proximity: u8) -> f32 {
+    6u8.saturating_sub(window_proximity) as f32
+}
+
+/// Light level for a placement at a given point in the day
+pub fn light_level(window_proximity: u8, phase: DayPhase) -> f32 {
+    placement_light(window_proximity) * phase.solar_fraction()
+}
+
 /// Nutrient efficiency function - saturating function for nitrogen
 pub fn nutrient_efficiency(nitrogen: Nitrogen) -> f32 {
     (nitrogen.value() / 2.0).min(1.0)
@@ -48,9 +66,37 @@ pub fn detritus_availability(detritus: Detritus) -> f32 {
     (detritus.value() / 2.0).min(1.0)
 }
 
-/// Toxicity factor - placeholder for future toxicity modeling
-pub fn toxicity_factor(_toxicity: f32) -> f32 {
-    0.0 // Placeholder - no toxicity effects implemented yet
+/// Feeding slows to a stop as the last of the detritus is eaten
+pub fn food_limitation(detritus: Detritus) -> f32 {
+    (detritus.value() / 0.1).min(1.0)
+}
+
+/// Respiration stalls as the last of the oxygen is used up
+pub fn aerobic_limitation(oxygen: Oxygen) -> f32 {
+    (oxygen.percentage() / 2.0).min(1.0)
+}
+
+/// Toxicity index at which animals start dying off noticeably faster
+pub const STRESSFUL_TOXICITY: f32 = 1.0;
+
+/// Toxicity index at which the ecosystem is considered poisoned
+pub const LETHAL_TOXICITY: f32 = 3.0;
+
+/// Toxicity factor - extra death rate from a toxicity index, quadrupling death at the stress level
+pub fn toxicity_factor(toxicity: f32) -> f32 {
+    3.0 * (toxicity.max(0.0) / STRESSFUL_TOXICITY).powi(2)
+}
+
+/// Fraction of total ammonia present as un-ionised NH3, which rises with pH and temperature
+pub fn free_ammonia_fraction(ph: Ph, temp: Temperature) -> f32 {
+    let pka = 0.09018 + 2729.92 / (temp.celsius() + 273.15);
+    1.0 / (1.0 + 10f32.powf(pka - ph.value()))
+}
+
+/// Combined toxicity index of free ammonia and nitrite
+pub fn nitrogen_toxicity(ammonia: Nitrogen, nitrite: Nitrogen, ph: Ph, temp: Temperature) -> f32 {
+    let free_ammonia = ammonia.value() * free_ammonia_fraction(ph, temp);
+    free_ammonia / 0.02 + nitrite.value() / 1.0
 }
 
 /// Water oxygen efficiency - similar to air oxygen but for aquatic organisms
@@ -118,6 +164,16 @@ mod tests {
         assert!(temperature_efficiency(optimal_temp) > temperature_efficiency(hot_temp));
     }
 
+    #[test]
+    fn test_humidity_efficiency() {
+        let dry = Humidity::new(30.0).unwrap();
+        let comfortable = Humidity::new(75.0).unwrap();
+        let saturated = Humidity::new(100.0).unwrap();
+
+        assert!(humidity_efficiency(comfortable) > humidity_efficiency(dry));
+        assert!(humidity_efficiency(comfortable) > humidity_efficiency(saturated));
+    }
+
     #[test]
     fn test_ph_efficiency() {
         let neutral_ph = Ph::new(7.0).unwrap();
@@ -128,6 +184,28 @@ mod tests {
         assert!(ph_efficiency(neutral_ph) > ph_efficiency(basic_ph));
     }
 
+    #[test]
+    fn test_light_follows_placement_and_phase() {
+        assert!(light_level(1, DayPhase::Noon) > light_level(4, DayPhase::Noon));
+        assert!(light_level(2, DayPhase::Noon) > light_level(2, DayPhase::Dawn));
+        assert_eq!(light_level(2, DayPhase::Dawn), light_level(2, DayPhase::Dusk));
+        assert_eq!(light_level(0, DayPhase::Night), 0.0);
+        assert_eq!(placement_light(9), 0.0);
+    }
+
+    #[test]
+    fn test_ammonia_toxicity_rises_with_ph() {
+        let ammonia = Nitrogen::new(1.0).unwrap();
+        let nitrite = Nitrogen::new(0.0).unwrap();
+        let temp = Temperature::new(25.0).unwrap();
+
+        let acidic = nitrogen_toxicity(ammonia, nitrite, Ph::new(6.0).unwrap(), temp);
+        let basic = nitrogen_toxicity(ammonia, nitrite, Ph::new(8.5).unwrap(), temp);
+        assert!(basic > acidic * 10.0);
+        assert_eq!(toxicity_factor(0.0), 0.0);
+        assert!(toxicity_factor(LETHAL_TOXICITY) > toxicity_factor(STRESSFUL_TOXICITY));
+    }
+
     #[test]
     fn test_oxygen_penalty() {
         let normal_oxygen = Oxygen::new(21.0).unwrap();
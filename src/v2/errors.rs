@@ -52,6 +52,7 @@ pub enum CollapseReason {
     ShrimpDied,
     OxygenDepletion,
     PhImbalance,
+    ToxicBuildup,
     TemperatureExtreme,
     Multiple(Vec<CollapseReason>),
 }
@@ -65,6 +66,7 @@ impl fmt::Display for CollapseReason {
             CollapseReason::ShrimpDied => write!(f, "All shrimp died"),
             CollapseReason::OxygenDepletion => write!(f, "Oxygen levels too low"),
             CollapseReason::PhImbalance => write!(f, "pH levels became toxic"),
+            CollapseReason::ToxicBuildup => write!(f, "Ammonia and nitrite built up to toxic levels"),
             CollapseReason::TemperatureExtreme => write!(f, "Temperature became extreme"),
             CollapseReason::Multiple(reasons) => {
                 write!(f, "Multiple failures: ")?;
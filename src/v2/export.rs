@@ -0,0 +1,235 @@
+// v2/export.rs
+// Export of Monte Carlo runs to CSV, JSON and JSON Lines
+
+use crate::v2::errors::{CollapseReason, EcosystemError, EcosystemResult};
+use crate::v2::montecarlo::{BatchSetup, MonteCarloResults, SimulationResult};
+use serde::{Deserialize, Serialize, Serializer};
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One row per run with nested values flattened into columns
+    Csv,
+    /// The batch summary and setup with every run in a `results` array
+    Json,
+    /// One JSON object per run
+    JsonLines,
+}
+
+impl ExportFormat {
+    /// Chosen from the file extension
+    pub fn from_path(path: &str) -> EcosystemResult<Self> {
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+        match extension.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "jsonl" | "ndjson" => Ok(ExportFormat::JsonLines),
+            _ => Err(EcosystemError::ConfigurationError {
+                message: format!("Cannot export to '{}': use a .csv, .json or .jsonl file", path),
+            }),
+        }
+    }
+}
+
+/// Collapse reasons are written as the same text the terminal report shows
+pub fn serialize_reasons<S: Serializer>(reasons: &[CollapseReason], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(reasons.iter().map(|reason| reason.to_string()))
+}
+
+#[derive(Serialize)]
+struct BatchExport<'a> {
+    master_seed: u64,
+    setup: &'a BatchSetup,
+    runs: usize,
+    /// Runs that stopped on a simulation error, left out of `survival_rate`
+    failed_runs: usize,
+    survival_rate: f32,
+    average_days_survived: f32,
+    results: &'a [SimulationResult],
+}
+
+/// Master seed and setup of a batch saved as JSON, which is all a replay of one of its runs needs
+pub fn read_batch_setup(path: &str) -> EcosystemResult<(u64, BatchSetup)> {
+    #[derive(Deserialize)]
+    struct Batch {
+        master_seed: u64,
+        setup: BatchSetup,
+    }
+
+    let invalid = |message: String| EcosystemError::ConfigurationError { message: format!("{}: {}", path, message) };
+    let text = std::fs::read_to_string(path).map_err(|e| invalid(format!("Could not read it: {}", e)))?;
+    let batch: Batch = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(&text))
+        .map_err(|e| invalid(format!("{}: {}", e.path(), e.inner())))?;
+    if let Some(config) = &batch.setup.config {
+        config.validate()?;
+    }
+    Ok((batch.master_seed, batch.setup))
+}
+
+pub fn export_results(results: &MonteCarloResults, path: &str) -> EcosystemResult<()> {
+    let format = ExportFormat::from_path(path)?;
+    let io_error = |e: std::io::Error| EcosystemError::ConfigurationError {
+        message: format!("Could not write {}: {}", path, e),
+    };
+    let json_error = |e: serde_json::Error| EcosystemError::ConfigurationError {
+        message: format!("Could not write {}: {}", path, e),
+    };
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(io_error)?);
+
+    match format {
+        ExportFormat::Csv => write_csv(&mut file, &results.results).map_err(io_error)?,
+        ExportFormat::Json => {
+            let batch = BatchExport {
+                master_seed: results.master_seed,
+                setup: &results.setup,
+                runs: results.results.len(),
+                failed_runs: results.failed_runs,
+                survival_rate: results.survival_rate,
+                average_days_survived: results.average_days_survived,
+                results: &results.results,
+            };
+            serde_json::to_writer_pretty(&mut file, &batch).map_err(json_error)?;
+            writeln!(file).map_err(io_error)?;
+        }
+        ExportFormat::JsonLines => {
+            for result in &results.results {
+                serde_json::to_writer(&mut file, result).map_err(json_error)?;
+                writeln!(file).map_err(io_error)?;
+            }
+        }
+    }
+    file.flush().map_err(io_error)
+}
+
+type Column = (&'static str, fn(&SimulationResult) -> String);
+
+fn optional(value: Option<f32>) -> String {
+    value.map_or_else(String::new, |v| v.to_string())
+}
+
+/// Every field of a run, in the order the CSV header lists them
+fn columns() -> Vec<Column> {
+    vec![
+        ("run_id", |r| r.run_id.to_string()),
+        ("seed", |r| r.seed.to_string()),
+        ("survived", |r| r.survived.to_string()),
+        ("days_survived", |r| r.days_survived.to_string()),
+        ("collapse_reasons", |r| r.collapse_reasons.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("; ")),
+        ("error", |r| r.error.clone().unwrap_or_default()),
+        ("difficulty", |r| r.config_snapshot.difficulty.to_string()),
+        ("species", |r| r.config_snapshot.species.join(";")),
+        ("microbe_count", |r| r.config_snapshot.microbe_count.to_string()),
+        ("worm_count", |r| r.config_snapshot.worm_count.to_string()),
+        ("shrimp_count", |r| r.config_snapshot.shrimp_count.to_string()),
+        ("plant_biomass", |r| r.config_snapshot.plant_biomass.to_string()),
+        ("water_volume", |r| r.config_snapshot.water_volume.to_string()),
+        ("rocks", |r| r.config_snapshot.rocks.to_string()),
+        ("window_proximity", |r| r.config_snapshot.window_proximity.to_string()),
+        ("soil_type", |r| format!("{:?}", r.config_snapshot.soil_type)),
+        ("room_temp", |r| r.config_snapshot.room_temp.to_string()),
+        ("initial_temp", |r| r.config_snapshot.initial_temp.to_string()),
+        ("initial_humidity", |r| r.config_snapshot.initial_humidity.to_string()),
+        ("initial_microbe_pop", |r| r.config_snapshot.initial_state.microbe_pop.to_string()),
+        ("initial_worm_pop", |r| r.config_snapshot.initial_state.worm_pop.to_string()),
+        ("initial_shrimp_pop", |r| r.config_snapshot.initial_state.shrimp_pop.to_string()),
+        ("initial_soil_ph", |r| r.config_snapshot.initial_state.soil_ph.to_string()),
+        ("initial_soil_ammonia", |r| r.config_snapshot.initial_state.soil_ammonia.to_string()),
+        ("initial_soil_nitrate", |r| r.config_snapshot.initial_state.soil_nitrate.to_string()),
+        ("initial_soil_moisture", |r| r.config_snapshot.initial_state.soil_moisture.to_string()),
+        ("initial_soil_aeration", |r| r.config_snapshot.initial_state.soil_aeration.to_string()),
+        ("initial_detritus", |r| r.config_snapshot.initial_state.detritus.to_string()),
+        ("initial_water_o2", |r| r.config_snapshot.initial_state.water_o2.to_string()),
+        ("initial_temperature", |r| r.config_snapshot.initial_state.temperature.to_string()),
+        ("initial_air_humidity", |r| r.config_snapshot.initial_state.humidity.to_string()),
+        ("final_plant_biomass", |r| optional(r.final_state.plant_biomass)),
+        ("final_microbe_pop", |r| optional(r.final_state.microbe_pop)),
+        ("final_worm_pop", |r| optional(r.final_state.worm_pop)),
+        ("final_shrimp_pop", |r| optional(r.final_state.shrimp_pop)),
+        ("final_soil_ph", |r| r.final_state.soil_ph.to_string()),
+        ("final_air_o2", |r| r.final_state.air_o2.to_string()),
+        ("final_temperature", |r| r.final_state.temperature.to_string()),
+        ("final_humidity", |r| r.final_state.humidity.to_string()),
+    ]
+}
+
+fn write_csv(out: &mut impl Write, results: &[SimulationResult]) -> std::io::Result<()> {
+    let columns = columns();
+    let header: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+    writeln!(out, "{}", header.join(","))?;
+    for result in results {
+        let row: Vec<String> = columns.iter().map(|(_, value)| csv_field(&value(result))).collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quote a CSV field when it holds a separator, quote or newline
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::montecarlo::{MonteCarloConfig, replay_run, run_monte_carlo_v2};
+    use crate::v2::sampling::{override_dimension, Dimension, SamplingStrategy};
+
+    #[test]
+    fn test_every_format_carries_each_run() {
+        let mc_config = MonteCarloConfig { num_runs: 4, day_cap: 2, show_progress: false, seed: Some(5), ..MonteCarloConfig::default() };
+        let results = run_monte_carlo_v2(mc_config).unwrap();
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &results.results).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0].split(',').count(), columns().len());
+        assert!(lines[1].starts_with(&format!("0,{},", results.results[0].seed)));
+
+        let row = serde_json::to_value(&results.results[3]).unwrap();
+        assert_eq!(row["run_id"], 3);
+        assert_eq!(row["seed"], results.results[3].seed);
+        assert!(row["config_snapshot"]["initial_state"]["microbe_pop"].is_number());
+        assert!(row["collapse_reasons"].is_array());
+        assert!(row["error"].is_null());
+    }
+
+    #[test]
+    fn test_replay_from_json_reproduces_the_run() {
+        let mut dimensions = Dimension::defaults();
+        override_dimension(&mut dimensions, "microbes=200:5000:log-uniform".parse().unwrap());
+        let mc_config = MonteCarloConfig {
+            num_runs: 6,
+            day_cap: 4,
+            sampling: SamplingStrategy::LatinHypercube,
+            dimensions,
+            show_progress: false,
+            seed: Some(3),
+            ..MonteCarloConfig::default()
+        };
+        let results = run_monte_carlo_v2(mc_config).unwrap();
+        let path = std::env::temp_dir().join(format!("replay-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        export_results(&results, path).unwrap();
+        let (master_seed, setup) = read_batch_setup(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(master_seed, 3);
+        let replayed = replay_run(&setup.monte_carlo_config(), master_seed, 4).unwrap();
+        assert_eq!(serde_json::to_value(&replayed).unwrap(), serde_json::to_value(&results.results[4]).unwrap());
+    }
+
+    #[test]
+    fn test_format_from_extension() {
+        assert_eq!(ExportFormat::from_path("out/results.CSV").unwrap(), ExportFormat::Csv);
+        assert_eq!(ExportFormat::from_path("results.json").unwrap(), ExportFormat::Json);
+        assert_eq!(ExportFormat::from_path("results.jsonl").unwrap(), ExportFormat::JsonLines);
+        assert!(ExportFormat::from_path("results.txt").is_err());
+    }
+}
@@ -2,62 +2,159 @@
 // Game loop and CLI for v2 - updated for refactored system
 
 use crate::v2::config::V2Config;
+use crate::v2::config::environment::SoilType;
+use crate::v2::config::parameters::{CollapseCriteria, PhModel};
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::organisms::OrganismRegistry;
 use crate::v2::state::EcosystemStateV2;
 use crate::v2::simulation_refactored::update_ecosystem_v2;
 use crate::v2::traits::{EcosystemDisplay, CollapseDetection, EcosystemValidation};
+use crate::v2::interventions::{apply_intervention, Intervention, InterventionBudget, InterventionOutcome};
+use crate::v2::input::{prompt, prompt_number};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
-pub fn run_game_v2() {
+/// Days a bottle has to survive to win
+pub const GOAL_DAYS: usize = 30;
+
+const DEFAULT_SAVE_FILE: &str = "ecosystem-save.json";
+
+/// A game in progress: the bottle, its state, the points left to spend and what was done
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameSession {
+    pub config: V2Config,
+    pub state: EcosystemStateV2,
+    pub budget: InterventionBudget,
+    /// The generator behind `StdRng`, named so its position can be saved
+    pub rng: ChaCha12Rng,
+    /// Half-days simulated so far; odd ones are daytime
+    pub half_day: usize,
+    pub goal_days: usize,
+    /// Every intervention carried out, with the day it was made on
+    pub history: Vec<(usize, Intervention)>,
+}
+
+impl GameSession {
+    /// A seeded game draws its starting chemistry from the seed, as the Monte Carlo does
+    pub fn new(config: V2Config, seed: Option<u64>, goal_days: usize) -> EcosystemResult<Self> {
+        let state = match seed {
+            Some(seed) => EcosystemStateV2::new_with_seed(&config, seed)?,
+            None => EcosystemStateV2::new(&config)?,
+        };
+        let rng = match seed {
+            Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+            None => ChaCha12Rng::from_entropy(),
+        };
+        let budget = InterventionBudget::for_difficulty(config.difficulty.level);
+        Ok(Self { config, state, budget, rng, half_day: 0, goal_days, history: Vec::new() })
+    }
+
+    /// Simulate the next half-day
+    pub fn advance(&mut self) -> EcosystemResult<()> {
+        self.half_day += 1;
+        let is_day = self.is_day();
+        update_ecosystem_v2(&self.config, &mut self.state, is_day)
+    }
+
+    pub fn is_day(&self) -> bool {
+        self.half_day % 2 == 1
+    }
+
+    /// Day of the game, counting from 1
+    pub fn day(&self) -> usize {
+        self.half_day.div_ceil(2)
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.half_day >= self.goal_days * 2
+    }
+
+    pub fn intervene(&mut self, intervention: &Intervention) -> EcosystemResult<InterventionOutcome> {
+        let outcome = apply_intervention(intervention, &mut self.config, &mut self.state, &mut self.budget, &mut self.rng)?;
+        self.history.push((self.day(), intervention.clone()));
+        Ok(outcome)
+    }
+}
+
+/// Play with `loaded` as the bottle, or set one up interactively; `save_config` keeps the bottle for next time
+pub fn run_game_v2(loaded: Option<V2Config>, save_config: Option<&str>) {
     println!("🧪 Rust Ecosystem v2 - Refactored Edition");
     println!("==========================================");
     
     // Create config with user input
-    let config = setup_game_v2();
+    let (config, seed) = setup_game_v2(loaded);
+    if let Some(path) = save_config {
+        match config.save(path) {
+            Ok(()) => println!("💾 Configuration saved to {}", path),
+            Err(e) => println!("❌ {}", e),
+        }
+    }
     
     // Create initial state
-    let mut state = match EcosystemStateV2::new(&config) {
-        Ok(state) => state,
+    let game = match GameSession::new(config, seed, GOAL_DAYS) {
+        Ok(game) => game,
         Err(e) => {
             println!("Error creating ecosystem: {}", e);
             return;
         }
     };
     
-    let mut day = 0;
-    let goal_days = 30;
-    
-    println!("\n🎯 Goal: Survive {} days without ecosystem collapse!", goal_days);
-    println!("{}", state.display_detailed());
-    
+    println!("\n🎯 Goal: Survive {} days without ecosystem collapse!", game.goal_days);
+    println!("🧰 You have {} intervention points to spend along the way", game.budget.total);
+    println!("{}", game.state.display_detailed());
+    play_session(game);
+}
+
+/// Carry on a game saved from the action prompt
+pub fn resume_game_v2(path: &str) -> EcosystemResult<()> {
+    let mut game = GameSession::resume(path)?;
+    println!("🧪 Rust Ecosystem v2 - Refactored Edition");
+    println!("==========================================");
+    println!("\n📂 Resumed {} on day {} of {} ({} intervention points left)",
+             path, game.day(), game.goal_days, game.budget.remaining());
+    println!("{}", game.state.display_detailed());
+
+    // Games are saved at the daytime prompt, so pick up where they were left
+    if game.is_day() && !get_user_action(&mut game) {
+        println!("👋 Game ended by user");
+        return Ok(());
+    }
+    play_session(game);
+    Ok(())
+}
+
+/// Simulate and prompt half-day by half-day until the game is won, lost or quit
+fn play_session(mut game: GameSession) {
     loop {
-        day += 1;
-        let is_day = day % 2 == 1; // Odd days are day, even are night
+        // Update ecosystem
+        let result = game.advance();
         
         println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("🌅 Day {} ({}) 🌅", (day + 1) / 2, if is_day { "Daytime" } else { "Nighttime" });
+        println!("🌅 Day {} ({}) 🌅", game.day(), if game.is_day() { "Daytime" } else { "Nighttime" });
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         
-        // Update ecosystem
-        if let Err(e) = update_ecosystem_v2(&config, &mut state, is_day) {
+        if let Err(e) = result {
             println!("❌ Simulation error: {}", e);
             break;
         }
         
         // Display status
-        println!("{}", state.display_summary());
+        println!("{}", game.state.display_summary());
         
         // Check for collapse
-        if state.is_collapsed() {
+        if game.state.is_collapsed() {
             println!("\n💀 ECOSYSTEM COLLAPSE! 💀");
-            let reasons = state.collapse_reasons();
+            let reasons = game.state.collapse_reasons();
             for reason in reasons {
                 println!("   • {}", reason);
             }
-            println!("You survived {} half-days ({} full days)", day, day / 2);
+            println!("You survived {} half-days ({} full days)", game.half_day, game.half_day / 2);
             break;
         }
         
         // Check for warnings
-        let warnings = state.health_warnings();
+        let warnings = game.state.health_warnings();
         if !warnings.is_empty() {
             println!("\n⚠️  Health Warnings:");
             for warning in warnings {
@@ -66,25 +163,23 @@ pub fn run_game_v2() {
         }
         
         // Show collapse risk
-        let risk = state.collapse_risk();
+        let risk = game.state.collapse_risk();
         if risk > 0.3 {
             println!("🚨 Collapse Risk: {:.1}%", risk * 100.0);
         }
         
         // User action (only during day)
-        if is_day {
-            if !get_user_action() {
-                println!("👋 Game ended by user");
-                break;
-            }
+        if game.is_day() && !get_user_action(&mut game) {
+            println!("👋 Game ended by user");
+            break;
         }
         
         // Check win condition
-        if day >= goal_days * 2 { // *2 because we count half-days
+        if game.is_won() {
             println!("\n🎉 VICTORY! 🎉");
-            println!("You successfully maintained your ecosystem for {} days!", goal_days);
+            println!("You successfully maintained your ecosystem for {} days!", game.goal_days);
             println!("Final ecosystem state:");
-            println!("{}", state.display_detailed());
+            println!("{}", game.state.display_detailed());
             break;
         }
         
@@ -93,79 +188,204 @@ pub fn run_game_v2() {
     }
 }
 
-fn setup_game_v2() -> V2Config {
-    use std::io::{self, Write};
-    
+fn setup_game_v2(loaded: Option<V2Config>) -> (V2Config, Option<u64>) {
     println!("\n🔧 Ecosystem Configuration");
     println!("==========================");
-    
-    let mut config = V2Config::new();
-    
-    print!("Choose difficulty [1=Easy, 2=Medium, 3=Hard, 4=Extreme] (default: 2): ");
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    
-    let difficulty_level = match input.trim() {
-        "1" => 0.2,
-        "2" => 0.5,
-        "3" => 0.8,
-        "4" => 1.0,
-        _ => 0.5,
-    };
-    
-    match V2Config::with_difficulty(difficulty_level) {
-        Ok(new_config) => config = new_config,
-        Err(e) => println!("Error setting difficulty: {}, using default", e),
-    }
-    
-    print!("Enter a seed for reproducible results (or press Enter for random): ");
-    io::stdout().flush().unwrap();
-    input.clear();
-    io::stdin().read_line(&mut input).unwrap();
-    
-    if !input.trim().is_empty() {
-        if let Ok(seed) = input.trim().parse::<u64>() {
-            println!("Using seed: {}", seed);
-            // We could implement seeded generation here if needed
-        }
+
+    let config = loaded.unwrap_or_else(choose_configuration);
+
+    let seed = prompt("Enter a seed for reproducible results (or press Enter for random): ")
+        .parse::<u64>()
+        .ok();
+    if let Some(seed) = seed {
+        println!("Using seed: {}", seed);
     }
     
     println!("\n✅ Configuration complete!");
-    println!("   Difficulty: {:.1}%", difficulty_level * 100.0);
+    println!("   Difficulty: {:.1}%", config.difficulty.level * 100.0);
     println!("   Organisms: {} microbes, {} worms, {} shrimp", 
              config.organisms.microbes.initial_count,
              config.organisms.worms.initial_count,
              config.organisms.shrimp.initial_count);
+    println!("   Species simulated: {}", config.species.names().join(", "));
     println!("   Environment: {:.1}°C, {:.1}% humidity, {:.1}L water",
              config.environment.initial_temperature.celsius(),
              config.environment.initial_humidity.percentage(),
              config.environment.water_volume.value());
+    println!("   Soil: {}", config.environment.soil_type);
+    println!("   pH model: {:?}", config.parameters.ph_model);
+    println!("   Collapse when: {}", config.parameters.collapse);
+    println!("   Placement: {} from the window ({})",
+             config.environment.window_proximity,
+             if config.environment.has_good_light() { "good light" } else { "dim - consider moving closer" });
     
-    config
+    (config, seed)
+}
+
+/// Answers to the setup prompts
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SetupChoices {
+    pub difficulty: f32,
+    pub soil: SoilType,
+    pub ph_model: PhModel,
+    /// Species left out of the bottle
+    pub leave_out: Vec<String>,
+    pub collapse: CollapseCriteria,
+}
+
+impl Default for SetupChoices {
+    fn default() -> Self {
+        Self {
+            difficulty: 0.5,
+            soil: SoilType::Balanced,
+            ph_model: PhModel::Linear,
+            leave_out: Vec::new(),
+            collapse: CollapseCriteria::AnySpecies,
+        }
+    }
+}
+
+impl SetupChoices {
+    pub fn configure(&self) -> EcosystemResult<V2Config> {
+        let mut config = V2Config::with_difficulty(self.difficulty)?;
+        config.environment.soil_type = self.soil;
+        config.parameters.ph_model = self.ph_model;
+        for species in &self.leave_out {
+            if !config.remove_species(&species.to_ascii_lowercase()) {
+                return Err(EcosystemError::ConfigurationError {
+                    message: format!("No species called '{}' in the bottle", species),
+                });
+            }
+        }
+        config.parameters.collapse = self.collapse;
+        config.validate()?;
+        Ok(config)
+    }
 }
 
-fn get_user_action() -> bool {
-    use std::io::{self, Write};
+/// Difficulty, soil, pH model, species and collapse rule from the prompts
+fn choose_configuration() -> V2Config {
+    let difficulty = match prompt("Choose difficulty [1=Easy, 2=Medium, 3=Hard, 4=Extreme] (default: 2): ").as_str() {
+        "1" => 0.2,
+        "2" => 0.5,
+        "3" => 0.8,
+        "4" => 1.0,
+        _ => 0.5,
+    };
+    let mut choices = SetupChoices { difficulty, ..SetupChoices::default() };
     
-    println!("\n🎮 What would you like to do?");
-    println!("   [Enter] Continue to next day");
-    println!("   [s] Show detailed status");
-    println!("   [q] Quit game");
+    println!("\nSoil types:");
+    for (i, soil) in SoilType::ALL.iter().enumerate() {
+        println!("   [{}] {} (pH {:.1}, retention {:.1}x, aeration {:.1}x)",
+                 i + 1, soil, soil.native_ph(), soil.water_retention_modifier(), soil.aeration_modifier());
+    }
+    let soil_choice = prompt_number("Choose soil type (default: 3): ", 3usize);
+    if let Some(soil) = soil_choice.checked_sub(1).and_then(|i| SoilType::ALL.get(i)) {
+        choices.soil = *soil;
+    }
     
-    print!("Action: ");
-    io::stdout().flush().unwrap();
+    if prompt("Model water pH with carbonate chemistry? [y/N]: ").eq_ignore_ascii_case("y") {
+        choices.ph_model = PhModel::Carbonate;
+    }
     
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
+    let left_out = prompt("Species to leave out, comma separated (e.g. worms,shrimp; Enter for none): ");
+    for species in left_out.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        if OrganismRegistry::standard().contains(&species.to_ascii_lowercase()) {
+            choices.leave_out.push(species.to_string());
+        } else {
+            println!("No species called '{}' in the bottle", species);
+        }
+    }
     
-    match input.trim().to_lowercase().as_str() {
-        "q" | "quit" => false,
-        "s" | "status" => {
-            // This would show detailed status if we had state access
-            println!("📊 Detailed status not implemented yet");
-            true
+    choices.collapse = match prompt("Collapse when [1=any species dies, 2=producers or decomposers die out, 3=everything dies] (default: 1): ").as_str() {
+        "2" => CollapseCriteria::ProducersOrDecomposers,
+        "3" => CollapseCriteria::AllSpecies,
+        _ => CollapseCriteria::AnySpecies,
+    };
+    match choices.configure() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}; putting every species back", e);
+            SetupChoices { leave_out: Vec::new(), ..choices }.configure().unwrap_or_default()
         }
-        _ => true,
     }
-}
\ No newline at end of file
+}
+
+/// Prompt for the day's action; returns false if the player quits
+fn get_user_action(game: &mut GameSession) -> bool {
+    loop {
+        println!("\n🎮 What would you like to do? ({} intervention points left)", game.budget.remaining());
+        println!("   [Enter] Continue to next day");
+        println!("   [s] Show detailed status");
+        println!("   [i] Intervene");
+        println!("   [w] Save game");
+        println!("   [q] Quit game");
+        
+        match prompt("Action: ").to_lowercase().as_str() {
+            "q" | "quit" => return false,
+            "w" | "save" => {
+                let path = prompt(&format!("Save to (default {}): ", DEFAULT_SAVE_FILE));
+                let path = if path.is_empty() { DEFAULT_SAVE_FILE } else { path.as_str() };
+                match game.save(path) {
+                    Ok(()) => println!("💾 Game saved to {}; continue it with `play --resume {}`", path, path),
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+            "s" | "status" => println!("{}", game.state.display_detailed()),
+            "i" | "intervene" => {
+                let intervention = match choose_intervention() {
+                    Ok(Some(intervention)) => intervention,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        println!("❌ {}", e);
+                        continue;
+                    }
+                };
+                match game.intervene(&intervention) {
+                    Ok(outcome) => {
+                        println!("🛠️  {} (-{} points)", outcome.intervention, outcome.cost);
+                        for effect in outcome.effects {
+                            println!("   • {}", effect);
+                        }
+                        return true;
+                    }
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+            _ => return true,
+        }
+    }
+}
+
+fn choose_intervention() -> EcosystemResult<Option<Intervention>> {
+    println!("\n🛠️  Interventions:");
+    println!("   [1] Open the bottle (2 pts)");
+    println!("   [2] Add water (1 pt)");
+    println!("   [3] Remove water (1 pt)");
+    println!("   [4] Add rocks (1 pt)");
+    println!("   [5] Move toward the window (1 pt)");
+    println!("   [6] Move away from the window (1 pt)");
+    println!("   [7] Inoculate microbes (2 pts)");
+    println!("   [8] Add worms (2 pts)");
+    println!("   [9] Add shrimp (3 pts)");
+    println!("   [10] Prune plants (1 pt)");
+    println!("   [Enter] Back");
+    
+    let (name, question) = match prompt("Intervention: ").as_str() {
+        "1" => ("open-bottle", None),
+        "2" => ("add-water", Some("Liters to add (default 0.25): ")),
+        "3" => ("remove-water", Some("Liters to remove (default 0.25): ")),
+        "4" => ("add-rocks", Some("Rocks to add (default 1): ")),
+        "5" => ("move-toward-window", None),
+        "6" => ("move-away-from-window", None),
+        "7" => ("inoculate-microbes", Some("Microbes to add (default 500): ")),
+        "8" => ("add-worms", Some("Worms to add (default 2): ")),
+        "9" => ("add-shrimp", Some("Shrimp to add (default 1): ")),
+        "10" => ("prune-plants", Some("Percent to prune (default 20): ")),
+        _ => return Ok(None),
+    };
+    // Amounts are read as a script's would be, so a negative or non-finite amount is refused
+    let amount = question.map(prompt).unwrap_or_default();
+    format!("{} {}", name, amount).parse().map(Some)
+}
@@ -1,7 +1,18 @@
 // v2/input.rs
-// User input handling for v2 (stub)
+// User input handling for v2
 
-#[allow(dead_code)]
-pub fn get_user_input_v2() {
-    // TODO: Implement user input for v2
+use std::io::{self, Write};
+
+/// Print a prompt on the same line and read back the trimmed answer
+pub fn prompt(message: &str) -> String {
+    print!("{}", message);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    input.trim().to_string()
+}
+
+/// Prompt for a number, falling back to a default on empty or invalid input
+pub fn prompt_number<T: std::str::FromStr + Copy>(message: &str, default: T) -> T {
+    prompt(message).parse::<T>().unwrap_or(default)
 }
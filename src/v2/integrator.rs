@@ -0,0 +1,381 @@
+// v2/integrator.rs
+// Derivative-based state vector and the numerical integrators that advance it
+
+use crate::v2::config::V2Config;
+use crate::v2::config::parameters::IntegrationMethod;
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::ledger::{soil_nitrogen_totals, ElementTotals};
+use crate::v2::types::*;
+
+/// Declares the continuous state variables once, so reading, writing and
+/// vector arithmetic can never disagree about which fields exist
+macro_rules! state_vector {
+    ($($field:ident),* $(,)?) => {
+        /// Rates of change per half-day, or a snapshot of the continuous state
+        #[derive(Debug, Clone, Copy, Default, PartialEq)]
+        pub struct Derivatives {
+            $(pub $field: f32,)*
+            /// Limestone carbon dissolved into the water, mol; an inflow from outside the ledger
+            pub limestone_carbon: f32,
+            /// Ammonia locked onto the soil, in soil nitrogen units; an outflow from the ledger
+            pub adsorbed_ammonia: f32,
+        }
+
+        impl Derivatives {
+            /// `self + scale * other`, component by component
+            pub fn scaled_add(&self, scale: f32, other: &Derivatives) -> Derivatives {
+                Derivatives {
+                    $($field: self.$field + scale * other.$field,)*
+                    limestone_carbon: self.limestone_carbon + scale * other.limestone_carbon,
+                    adsorbed_ammonia: self.adsorbed_ammonia + scale * other.adsorbed_ammonia,
+                }
+            }
+
+            fn is_finite(&self) -> bool {
+                $(self.$field.is_finite() &&)* self.limestone_carbon.is_finite() && self.adsorbed_ammonia.is_finite()
+            }
+
+            /// Largest component of the error estimate, relative to the size of the state
+            fn error_norm(&self, state: &Derivatives, tolerance: f32) -> f32 {
+                let mut norm: f32 = 0.0;
+                $(
+                    let scale = tolerance * (1.0 + state.$field.abs());
+                    norm = norm.max(self.$field.abs() / scale);
+                )*
+                norm
+            }
+        }
+    };
+}
+
+state_vector!(
+    plant_biomass,
+    microbe_pop,
+    worm_pop,
+    shrimp_pop,
+    soil_ammonia,
+    soil_nitrite,
+    soil_nitrate,
+    soil_ph,
+    soil_moisture,
+    soil_aeration,
+    detritus,
+    water_liters,
+    water_o2,
+    water_dic,
+    water_alkalinity,
+    air_n2,
+    air_o2,
+    air_co2,
+    temperature,
+    humidity,
+);
+
+impl Derivatives {
+    /// Snapshot the continuous part of the ecosystem state
+    pub fn from_state(state: &EcosystemStateV2) -> Self {
+        Self {
+            plant_biomass: state.plant_biomass.value(),
+            microbe_pop: state.microbe_pop.value(),
+            worm_pop: state.worm_pop.value(),
+            shrimp_pop: state.shrimp_pop.value(),
+            soil_ammonia: state.soil_ammonia.value(),
+            soil_nitrite: state.soil_nitrite.value(),
+            soil_nitrate: state.soil_nitrate.value(),
+            soil_ph: state.soil_ph.value(),
+            soil_moisture: state.soil_moisture.value(),
+            soil_aeration: state.soil_aeration.value(),
+            detritus: state.detritus.value(),
+            water_liters: state.water_liters.value(),
+            water_o2: state.water_o2.percentage(),
+            water_dic: state.water_dic,
+            water_alkalinity: state.water_alkalinity,
+            air_n2: state.air_n2.value(),
+            air_o2: state.air_o2.percentage(),
+            air_co2: state.air_co2.value(),
+            temperature: state.temperature.celsius(),
+            humidity: state.humidity.percentage(),
+            limestone_carbon: 0.0,
+            adsorbed_ammonia: 0.0,
+        }
+    }
+
+    /// Bring every variable back inside its physical range
+    fn clamped(&self) -> Derivatives {
+        Derivatives {
+            plant_biomass: self.plant_biomass.max(0.0),
+            microbe_pop: self.microbe_pop.max(0.0),
+            worm_pop: self.worm_pop.max(0.0),
+            shrimp_pop: self.shrimp_pop.max(0.0),
+            soil_ammonia: self.soil_ammonia.max(0.0),
+            soil_nitrite: self.soil_nitrite.max(0.0),
+            soil_nitrate: self.soil_nitrate.max(0.0),
+            soil_ph: self.soil_ph.clamp(0.0, 14.0),
+            soil_moisture: self.soil_moisture.max(0.0),
+            soil_aeration: self.soil_aeration.max(0.0),
+            detritus: self.detritus.max(0.0),
+            water_liters: self.water_liters.max(0.0),
+            water_o2: self.water_o2.clamp(0.0, 100.0),
+            water_dic: self.water_dic.max(0.0),
+            air_n2: self.air_n2.max(0.0),
+            air_o2: self.air_o2.clamp(0.0, 100.0),
+            air_co2: self.air_co2.max(0.0),
+            temperature: self.temperature.clamp(-10.0, 55.0),
+            humidity: self.humidity.clamp(0.0, 100.0),
+            ..*self
+        }
+    }
+
+    /// Write a snapshot back, clamping anything a large step pushed out of range
+    pub fn write_to(&self, state: &mut EcosystemStateV2) -> EcosystemResult<()> {
+        if !self.is_finite() {
+            return Err(EcosystemError::SimulationError {
+                message: "Integration produced a non-finite state; try more sub-steps".to_string(),
+            });
+        }
+
+        let y = self.clamped();
+        state.plant_biomass = Biomass::new(y.plant_biomass)?;
+        state.microbe_pop = Population::new(y.microbe_pop)?;
+        state.worm_pop = Population::new(y.worm_pop)?;
+        state.shrimp_pop = Population::new(y.shrimp_pop)?;
+        state.soil_ammonia = Nitrogen::new(y.soil_ammonia)?;
+        state.soil_nitrite = Nitrogen::new(y.soil_nitrite)?;
+        state.soil_nitrate = Nitrogen::new(y.soil_nitrate)?;
+        state.soil_ph = Ph::new(y.soil_ph)?;
+        state.soil_moisture = Moisture::new(y.soil_moisture)?;
+        state.soil_aeration = Aeration::new(y.soil_aeration)?;
+        state.detritus = Detritus::new(y.detritus)?;
+        state.water_liters = WaterVolume::new(y.water_liters)?;
+        state.water_o2 = Oxygen::new(y.water_o2)?;
+        state.water_dic = y.water_dic;
+        state.water_alkalinity = y.water_alkalinity;
+        state.air_n2 = Nitrogen::new(y.air_n2)?;
+        state.air_o2 = Oxygen::new(y.air_o2)?;
+        state.air_co2 = CarbonDioxide::new(y.air_co2)?;
+        state.temperature = Temperature::new(y.temperature)?;
+        state.humidity = Humidity::new(y.humidity)?;
+        Ok(())
+    }
+}
+
+/// Rates of every process at a given state; supplied by the simulation engine
+pub type RateFunction = fn(&EcosystemStateV2, &V2Config) -> EcosystemResult<Derivatives>;
+
+/// Advance the continuous state over `duration` half-days with the configured integrator
+pub fn integrate(
+    state: &mut EcosystemStateV2,
+    config: &V2Config,
+    duration: f32,
+    rates: RateFunction,
+) -> EcosystemResult<()> {
+    let settings = &config.parameters.integration;
+    let steps = (settings.sub_steps as f32 * duration).ceil().max(1.0) as usize;
+    let h = duration / steps as f32;
+
+    let start = Derivatives::from_state(state);
+    let mut y = start;
+    let mut trial = state.clone();
+
+    match settings.method {
+        IntegrationMethod::Euler => {
+            for _ in 0..steps {
+                let k1 = evaluate(&mut trial, config, &y, rates)?;
+                y = y.scaled_add(h, &k1).clamped();
+            }
+        }
+        IntegrationMethod::Rk4 => {
+            for _ in 0..steps {
+                y = rk4_step(&mut trial, config, &y, h, rates)?.clamped();
+            }
+        }
+        IntegrationMethod::Rk45 => {
+            y = rk45(&mut trial, config, &y, duration, h, settings.tolerance, rates)?;
+        }
+    }
+
+    y.write_to(state)?;
+    state.refresh_water_ph(config)?;
+    let carbon = (y.limestone_carbon - start.limestone_carbon) as f64;
+    state.ledger.record_inflow(ElementTotals::new(carbon, 2.0 * carbon, 0.0));
+    state.ledger.record_outflow(soil_nitrogen_totals(y.adsorbed_ammonia - start.adsorbed_ammonia));
+    Ok(())
+}
+
+/// Load a trial state vector and evaluate the rates there. Stage vectors stay unclamped so they
+/// combine at the method's order; only the trial reading them is held in range, and each step is
+/// clamped once it is complete
+fn evaluate(
+    trial: &mut EcosystemStateV2,
+    config: &V2Config,
+    y: &Derivatives,
+    rates: RateFunction,
+) -> EcosystemResult<Derivatives> {
+    y.write_to(trial)?;
+    trial.refresh_water_ph(config)?;
+    rates(trial, config)
+}
+
+/// Classic fourth-order Runge-Kutta
+fn rk4_step(
+    trial: &mut EcosystemStateV2,
+    config: &V2Config,
+    y: &Derivatives,
+    h: f32,
+    rates: RateFunction,
+) -> EcosystemResult<Derivatives> {
+    let k1 = evaluate(trial, config, y, rates)?;
+    let k2 = evaluate(trial, config, &y.scaled_add(h / 2.0, &k1), rates)?;
+    let k3 = evaluate(trial, config, &y.scaled_add(h / 2.0, &k2), rates)?;
+    let k4 = evaluate(trial, config, &y.scaled_add(h, &k3), rates)?;
+
+    Ok(y
+        .scaled_add(h / 6.0, &k1)
+        .scaled_add(h / 3.0, &k2)
+        .scaled_add(h / 3.0, &k3)
+        .scaled_add(h / 6.0, &k4))
+}
+
+/// Dormand-Prince tableau for the embedded 5(4) pair. Rates don't depend on time
+/// within a phase, so the stage times are not needed
+const DP_A: [&[f32]; 6] = [
+    &[1.0 / 5.0],
+    &[3.0 / 40.0, 9.0 / 40.0],
+    &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+    &[19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0],
+    &[9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0],
+    &[35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+/// Fifth-order weights minus fourth-order weights, for the error estimate
+const DP_E: [f32; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+/// Adaptive Dormand-Prince integration over `duration`, starting from step `h`
+fn rk45(
+    trial: &mut EcosystemStateV2,
+    config: &V2Config,
+    start: &Derivatives,
+    duration: f32,
+    initial_step: f32,
+    tolerance: f32,
+    rates: RateFunction,
+) -> EcosystemResult<Derivatives> {
+    let min_step = duration * 1e-4;
+    let mut y = *start;
+    let mut t = 0.0;
+    let mut h = initial_step;
+
+    while t < duration {
+        h = h.min(duration - t);
+
+        let mut k = [Derivatives::default(); 7];
+        k[0] = evaluate(trial, config, &y, rates)?;
+        for stage in 0..6 {
+            let mut y_stage = y;
+            for (weight, k_prev) in DP_A[stage].iter().zip(k.iter()) {
+                y_stage = y_stage.scaled_add(h * weight, k_prev);
+            }
+            k[stage + 1] = evaluate(trial, config, &y_stage, rates)?;
+        }
+
+        // The last stage is evaluated at the fifth-order solution (first same as last)
+        let mut y_next = y;
+        for (weight, k_stage) in DP_A[5].iter().zip(k.iter()) {
+            y_next = y_next.scaled_add(h * weight, k_stage);
+        }
+        let mut error = Derivatives::default();
+        for (weight, k_stage) in DP_E.iter().zip(k.iter()) {
+            error = error.scaled_add(h * weight, k_stage);
+        }
+
+        let norm = error.error_norm(&y_next, tolerance);
+        if norm <= 1.0 || h <= min_step {
+            t += h;
+            y = y_next.clamped();
+        }
+
+        // Standard step-size controller with safety factor and growth limits
+        let factor = if norm > 0.0 { 0.9 * norm.powf(-0.2) } else { 5.0 };
+        h = (h * factor.clamp(0.2, 5.0)).max(min_step);
+    }
+
+    Ok(y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::simulation_refactored::update_ecosystem_v2;
+
+    fn run_days(method: IntegrationMethod, sub_steps: u32, days: usize) -> EcosystemStateV2 {
+        let mut config = V2Config::new();
+        config.parameters.integration.method = method;
+        config.parameters.integration.sub_steps = sub_steps;
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        for _ in 0..days {
+            update_ecosystem_v2(&config, &mut state, true).unwrap();
+            update_ecosystem_v2(&config, &mut state, false).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_results_converge_as_steps_shrink() {
+        let coarse = run_days(IntegrationMethod::Rk4, 4, 5);
+        let fine = run_days(IntegrationMethod::Rk4, 16, 5);
+        let adaptive = run_days(IntegrationMethod::Rk45, 2, 5);
+
+        for other in [&coarse, &adaptive] {
+            let biomass = (other.plant_biomass.value() - fine.plant_biomass.value()).abs();
+            let oxygen = (other.air_o2.percentage() - fine.air_o2.percentage()).abs();
+            assert!(biomass < 0.01 * (1.0 + fine.plant_biomass.value()), "biomass differs by {}", biomass);
+            assert!(oxygen < 0.1, "oxygen differs by {}", oxygen);
+        }
+    }
+
+    #[test]
+    fn test_euler_error_halves_with_the_step() {
+        let reference = run_days(IntegrationMethod::Rk4, 32, 5);
+        let error = |sub_steps| {
+            let euler = run_days(IntegrationMethod::Euler, sub_steps, 5);
+            (euler.air_o2.percentage() - reference.air_o2.percentage()).abs()
+        };
+
+        let errors = [error(8), error(16), error(32)];
+        for pair in errors.windows(2) {
+            let ratio = pair[0] / pair[1];
+            assert!((1.6..2.5).contains(&ratio), "error fell by {} instead of 2 ({:?})", ratio, errors);
+        }
+    }
+
+    #[test]
+    fn test_survival_does_not_depend_on_step_size() {
+        use crate::v2::montecarlo::{run_parallel, MonteCarloConfig};
+
+        let outcomes = |method, sub_steps| {
+            let mut mc_config = MonteCarloConfig { num_runs: 24, day_cap: 30, show_progress: false, ..MonteCarloConfig::default() };
+            mc_config.integration.method = method;
+            mc_config.integration.sub_steps = sub_steps;
+            run_parallel(&mc_config, 11, 4).unwrap()
+        };
+        let fine = outcomes(IntegrationMethod::Rk4, 16);
+        let coarse = outcomes(IntegrationMethod::Rk4, 2);
+        let euler = outcomes(IntegrationMethod::Euler, 8);
+
+        assert!(fine.iter().any(|r| r.survived) && fine.iter().any(|r| !r.survived));
+        for ((fine, coarse), euler) in fine.iter().zip(&coarse).zip(&euler) {
+            assert_eq!((fine.survived, fine.days_survived), (coarse.survived, coarse.days_survived), "run {}", fine.run_id);
+            // First-order steps can shift a collapse by a day, but not whether the bottle survives
+            assert_eq!(fine.survived, euler.survived, "run {}", fine.run_id);
+            assert!(fine.days_survived.abs_diff(euler.days_survived) <= 1, "run {}", fine.run_id);
+        }
+    }
+}
@@ -0,0 +1,551 @@
+// v2/interventions.rs
+// Player interventions that act on the bottle between half-days
+
+use crate::v2::config::V2Config;
+use crate::v2::config::parameters::CarbonateParams;
+use crate::v2::ledger::element_totals;
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::types::*;
+use crate::v2::organisms::plants::{BiomassOps, GasOps};
+use crate::v2::organisms::microbes::PopulationOps;
+use crate::v2::organisms::{microbes::Microbes, shrimp::Shrimp, worms::Worms, Organism};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Room air composition the bottle equilibrates with when opened
+pub const ROOM_O2: f32 = 21.0;
+pub const ROOM_CO2: f32 = 0.04;
+pub const ROOM_N2: f32 = 78.0;
+pub const ROOM_HUMIDITY: f32 = 50.0;
+
+/// Physical limits of the bottle
+pub const MAX_WATER_LITERS: f32 = 3.0;
+pub const MIN_WATER_LITERS: f32 = 0.1;
+pub const MAX_ROCKS: usize = 10;
+pub const MAX_WINDOW_PROXIMITY: u8 = 6;
+
+/// Oxygen content of fresh tap water
+const FRESH_WATER_O2: f32 = 8.0;
+
+/// Scripts and saved games write interventions as commands, e.g. `add-water 0.5`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Intervention {
+    OpenBottle,
+    AddWater(f32),
+    RemoveWater(f32),
+    AddRocks(usize),
+    MoveTowardWindow,
+    MoveAwayFromWindow,
+    InoculateMicrobes(f32),
+    AddWorms(f32),
+    AddShrimp(f32),
+    PrunePlants(f32),
+}
+
+impl Intervention {
+    /// Intervention points spent by this action
+    pub fn cost(&self) -> u32 {
+        match self {
+            Intervention::OpenBottle => 2,
+            Intervention::AddWater(_) => 1,
+            Intervention::RemoveWater(_) => 1,
+            Intervention::AddRocks(_) => 1,
+            Intervention::MoveTowardWindow => 1,
+            Intervention::MoveAwayFromWindow => 1,
+            Intervention::InoculateMicrobes(_) => 2,
+            Intervention::AddWorms(_) => 2,
+            Intervention::AddShrimp(_) => 3,
+            Intervention::PrunePlants(_) => 1,
+        }
+    }
+
+    /// Whether the bottle has to be opened to carry out this action
+    pub fn opens_bottle(&self) -> bool {
+        !matches!(self, Intervention::MoveTowardWindow | Intervention::MoveAwayFromWindow)
+    }
+}
+
+impl fmt::Display for Intervention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Intervention::OpenBottle => write!(f, "Open the bottle"),
+            Intervention::AddWater(liters) => write!(f, "Add {:.2}L of water", liters),
+            Intervention::RemoveWater(liters) => write!(f, "Remove {:.2}L of water", liters),
+            Intervention::AddRocks(count) => write!(f, "Add {} rock(s)", count),
+            Intervention::MoveTowardWindow => write!(f, "Move toward the window"),
+            Intervention::MoveAwayFromWindow => write!(f, "Move away from the window"),
+            Intervention::InoculateMicrobes(count) => write!(f, "Inoculate {:.0} microbes", count),
+            Intervention::AddWorms(count) => write!(f, "Add {:.0} worm(s)", count),
+            Intervention::AddShrimp(count) => write!(f, "Add {:.0} shrimp", count),
+            Intervention::PrunePlants(fraction) => write!(f, "Prune {:.0}% of the plants", fraction * 100.0),
+        }
+    }
+}
+
+/// Largest share of the plants one pruning can take
+const MAX_PRUNE: f32 = 0.9;
+
+/// `name [amount]`; amounts default as they do at the prompts and plants are pruned by percent
+impl FromStr for Intervention {
+    type Err = EcosystemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EcosystemError::ConfigurationError {
+            message: format!(
+                "Cannot read intervention '{}' (expected open-bottle, add-water, remove-water, add-rocks, move-toward-window, \
+                 move-away-from-window, inoculate-microbes, add-worms, add-shrimp or prune-plants, with an optional amount)",
+                s
+            ),
+        };
+        let mut parts = s.split_whitespace();
+        let name = parts.next().ok_or_else(invalid)?.to_ascii_lowercase();
+        let amount = parts.next();
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        let number = |default: f32| match amount {
+            None => Ok(default),
+            Some(amount) => amount.parse::<f32>().ok().filter(|v| v.is_finite() && *v >= 0.0).ok_or_else(invalid),
+        };
+
+        let intervention = match name.as_str() {
+            "open-bottle" => Intervention::OpenBottle,
+            "add-water" => Intervention::AddWater(number(0.25)?),
+            "remove-water" => Intervention::RemoveWater(number(0.25)?),
+            "add-rocks" => Intervention::AddRocks(amount.map_or(Ok(1), |a| a.parse::<usize>().map_err(|_| invalid()))?),
+            "move-toward-window" => Intervention::MoveTowardWindow,
+            "move-away-from-window" => Intervention::MoveAwayFromWindow,
+            "inoculate-microbes" => Intervention::InoculateMicrobes(number(500.0)?),
+            "add-worms" => Intervention::AddWorms(number(2.0)?),
+            "add-shrimp" => Intervention::AddShrimp(number(1.0)?),
+            "prune-plants" => match number(20.0)? / 100.0 {
+                fraction if fraction <= MAX_PRUNE => Intervention::PrunePlants(fraction),
+                fraction => {
+                    return Err(EcosystemError::ConfigurationError {
+                        message: format!("Cannot prune {}% of the plants (at most {:.0}%)", fraction * 100.0, MAX_PRUNE * 100.0),
+                    });
+                }
+            },
+            _ => return Err(invalid()),
+        };
+        let takes_amount = !matches!(intervention, Intervention::OpenBottle | Intervention::MoveTowardWindow | Intervention::MoveAwayFromWindow);
+        if amount.is_some() && !takes_amount {
+            return Err(invalid());
+        }
+        Ok(intervention)
+    }
+}
+
+impl TryFrom<String> for Intervention {
+    type Error = EcosystemError;
+
+    fn try_from(command: String) -> Result<Self, Self::Error> {
+        command.parse()
+    }
+}
+
+impl From<Intervention> for String {
+    fn from(intervention: Intervention) -> Self {
+        match intervention {
+            Intervention::OpenBottle => "open-bottle".to_string(),
+            Intervention::AddWater(liters) => format!("add-water {}", liters),
+            Intervention::RemoveWater(liters) => format!("remove-water {}", liters),
+            Intervention::AddRocks(count) => format!("add-rocks {}", count),
+            Intervention::MoveTowardWindow => "move-toward-window".to_string(),
+            Intervention::MoveAwayFromWindow => "move-away-from-window".to_string(),
+            Intervention::InoculateMicrobes(count) => format!("inoculate-microbes {}", count),
+            Intervention::AddWorms(count) => format!("add-worms {}", count),
+            Intervention::AddShrimp(count) => format!("add-shrimp {}", count),
+            // Percentages are stored as fractions; rounding drops the f32 noise of converting back
+            Intervention::PrunePlants(fraction) => format!("prune-plants {}", (fraction * 100_000.0).round() / 1000.0),
+        }
+    }
+}
+
+/// Intervention points available over a whole game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterventionBudget {
+    pub total: u32,
+    pub spent: u32,
+}
+
+impl InterventionBudget {
+    pub fn new(total: u32) -> Self {
+        Self { total, spent: 0 }
+    }
+
+    /// Harder games get fewer points to spend
+    pub fn for_difficulty(level: f32) -> Self {
+        Self::new((16.0 - 10.0 * level.clamp(0.0, 1.0)).round() as u32)
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.total.saturating_sub(self.spent)
+    }
+
+    pub fn can_afford(&self, intervention: &Intervention) -> bool {
+        intervention.cost() <= self.remaining()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterventionOutcome {
+    pub intervention: Intervention,
+    pub cost: u32,
+    pub effects: Vec<String>,
+}
+
+/// Apply an intervention, charging its cost against the budget
+pub fn apply_intervention<R: Rng>(
+    intervention: &Intervention,
+    config: &mut V2Config,
+    state: &mut EcosystemStateV2,
+    budget: &mut InterventionBudget,
+    rng: &mut R,
+) -> EcosystemResult<InterventionOutcome> {
+    if !budget.can_afford(intervention) {
+        return Err(EcosystemError::SimulationError {
+            message: format!(
+                "'{}' costs {} points but only {} remain",
+                intervention,
+                intervention.cost(),
+                budget.remaining()
+            ),
+        });
+    }
+
+    if let Some(reason) = no_effect(intervention, state) {
+        return Err(EcosystemError::SimulationError {
+            message: format!("'{}' would change nothing: {}", intervention, reason),
+        });
+    }
+
+    // Work on copies so that a step failing part-way leaves the bottle as it was
+    let mut effects = Vec::new();
+    let mut changed_config = config.clone();
+    let mut changed = state.clone();
+
+    // Anything other than moving the bottle means lifting the lid
+    let room_temperature = config.environment.room_temperature.celsius();
+    if intervention.opens_bottle() && *intervention != Intervention::OpenBottle {
+        partial_air_exchange(&mut changed, room_temperature, 0.25)?;
+        effects.push("Lifting the lid let some room air in".to_string());
+    }
+
+    match intervention {
+        Intervention::OpenBottle => open_bottle(&mut changed, room_temperature, rng, &mut effects)?,
+        Intervention::AddWater(liters) => add_water(&mut changed, &config.parameters.carbonate, room_temperature, *liters, &mut effects)?,
+        Intervention::RemoveWater(liters) => remove_water(&mut changed, *liters, &mut effects)?,
+        Intervention::AddRocks(count) => add_rocks(&mut changed_config, &mut changed, *count, &mut effects)?,
+        Intervention::MoveTowardWindow => move_bottle(&mut changed_config, &mut changed, -1, &mut effects)?,
+        Intervention::MoveAwayFromWindow => move_bottle(&mut changed_config, &mut changed, 1, &mut effects)?,
+        Intervention::InoculateMicrobes(count) => inoculate_microbes(&mut changed, *count, &mut effects)?,
+        Intervention::AddWorms(count) => add_worms(&mut changed, *count, &mut effects)?,
+        Intervention::AddShrimp(count) => add_shrimp(&mut changed, *count, &mut effects)?,
+        Intervention::PrunePlants(fraction) => prune_plants(&mut changed, *fraction, &mut effects)?,
+    }
+
+    // Whatever the player added or took out is an exchange with the room, not drift
+    let exchanged = element_totals(&changed) - element_totals(state);
+    changed.ledger.record_inflow(exchanged);
+
+    *config = changed_config;
+    *state = changed;
+    budget.spent += intervention.cost();
+
+    Ok(InterventionOutcome {
+        intervention: intervention.clone(),
+        cost: intervention.cost(),
+        effects,
+    })
+}
+
+/// Why `intervention` would leave the bottle as it is, in which case it is refused rather than charged
+fn no_effect(intervention: &Intervention, state: &EcosystemStateV2) -> Option<String> {
+    let reason = match *intervention {
+        Intervention::AddWater(liters) if liters <= 0.0 => "no water to add".to_string(),
+        Intervention::AddWater(_) if state.water_liters.value() >= MAX_WATER_LITERS => "the bottle is full".to_string(),
+        Intervention::RemoveWater(liters) if liters <= 0.0 => "no water to remove".to_string(),
+        Intervention::RemoveWater(_) if state.water_liters.value() <= MIN_WATER_LITERS => "the bottle is nearly dry".to_string(),
+        Intervention::AddRocks(0) => "no rocks to add".to_string(),
+        Intervention::AddRocks(_) if state.rocks >= MAX_ROCKS => format!("there is no room for more rocks (max {})", MAX_ROCKS),
+        Intervention::MoveTowardWindow if state.window_proximity == 0 => {
+            "the bottle is already at the window".to_string()
+        }
+        Intervention::MoveAwayFromWindow if state.window_proximity >= MAX_WINDOW_PROXIMITY => {
+            format!("the bottle can't move any further (proximity {})", MAX_WINDOW_PROXIMITY)
+        }
+        Intervention::InoculateMicrobes(count) | Intervention::AddWorms(count) | Intervention::AddShrimp(count) if count <= 0.0 => {
+            "nothing to add".to_string()
+        }
+        Intervention::PrunePlants(fraction) if fraction <= 0.0 || state.plant_biomass.value() <= 0.0 => {
+            "nothing to prune".to_string()
+        }
+        _ => return None,
+    };
+    Some(reason)
+}
+
+/// Mix a fraction of the bottle air with room air
+fn partial_air_exchange(state: &mut EcosystemStateV2, room_temperature: f32, fraction: f32) -> EcosystemResult<()> {
+    let mix = |inside: f32, room: f32| inside * (1.0 - fraction) + room * fraction;
+
+    state.air_o2 = Oxygen::new(mix(state.air_o2.percentage(), ROOM_O2))?;
+    state.air_co2 = CarbonDioxide::new(mix(state.air_co2.value(), ROOM_CO2))?;
+    state.air_n2 = Nitrogen::new(mix(state.air_n2.value(), ROOM_N2))?;
+    state.humidity = Humidity::new(mix(state.humidity.percentage(), ROOM_HUMIDITY))?;
+    state.temperature = Temperature::new(mix(state.temperature.celsius(), room_temperature))?;
+    Ok(())
+}
+
+/// Fully re-equilibrate with room air; moisture escapes and spores may get in
+fn open_bottle<R: Rng>(
+    state: &mut EcosystemStateV2,
+    room_temperature: f32,
+    rng: &mut R,
+    effects: &mut Vec<String>,
+) -> EcosystemResult<()> {
+    partial_air_exchange(state, room_temperature, 1.0)?;
+    effects.push(format!(
+        "Air re-equilibrated with the room: {:.1}% O2, {:.2}% CO2",
+        ROOM_O2, ROOM_CO2
+    ));
+
+    let evaporated = state.water_liters.value() * 0.05;
+    state.water_liters = WaterVolume::new(state.water_liters.value() - evaporated)?;
+    effects.push(format!("{:.2}L of water evaporated while open", evaporated));
+
+    if rng.gen_bool(0.2) {
+        let spores = 0.3;
+        state.detritus = Detritus::new(state.detritus.value() + spores)?;
+        state.microbe_pop = state.microbe_pop.multiply(0.9)?;
+        effects.push("Mold spores got in and are competing with the soil microbes".to_string());
+    }
+    Ok(())
+}
+
+/// Top up with fresh water, which cools the bottle and refreshes dissolved O2
+fn add_water(
+    state: &mut EcosystemStateV2,
+    carbonate: &CarbonateParams,
+    room_temperature: f32,
+    liters: f32,
+    effects: &mut Vec<String>,
+) -> EcosystemResult<()> {
+    let current = state.water_liters.value();
+    let added = liters.min(MAX_WATER_LITERS - current);
+    let total = current + added;
+    let mixed_o2 = (state.water_o2.percentage() * current + FRESH_WATER_O2 * added) / total;
+    let mixed_temp = (state.temperature.celsius() * (current + 1.0) + room_temperature * added) / (total + 1.0);
+    crate::v2::carbonate::mix_fresh_water(state, carbonate, current, added);
+
+    state.water_liters = WaterVolume::new(total)?;
+    state.water_o2 = Oxygen::new(mixed_o2)?;
+    state.soil_moisture = Moisture::new(state.soil_moisture.value() + added * 0.5)?;
+    state.temperature = Temperature::new(mixed_temp)?;
+    effects.push(format!("Water is now {:.2}L; the fresh water cooled the bottle to {:.1}°C", total, mixed_temp));
+    Ok(())
+}
+
+/// Drain water; shrimp get crowded if too little is left
+fn remove_water(state: &mut EcosystemStateV2, liters: f32, effects: &mut Vec<String>) -> EcosystemResult<()> {
+    let current = state.water_liters.value();
+    let removed = liters.min(current - MIN_WATER_LITERS);
+    let remaining = current - removed;
+    state.water_liters = WaterVolume::new(remaining)?;
+    state.soil_moisture = Moisture::new((state.soil_moisture.value() - removed * 0.5).max(0.0))?;
+    effects.push(format!("Water is now {:.2}L", remaining));
+
+    // Roughly ten shrimp per liter before they start dying off
+    let shrimp_capacity = remaining * 10.0;
+    if state.shrimp_pop.value() > shrimp_capacity {
+        state.shrimp_pop = Population::new(shrimp_capacity.max(0.01))?;
+        effects.push(format!("Overcrowding killed shrimp down to {:.0}", state.shrimp_pop.value()));
+    }
+    Ok(())
+}
+
+/// Add rocks for pH buffering; placing them stirs up the sediment
+fn add_rocks(
+    config: &mut V2Config,
+    state: &mut EcosystemStateV2,
+    count: usize,
+    effects: &mut Vec<String>,
+) -> EcosystemResult<()> {
+    state.rocks += count.min(MAX_ROCKS.saturating_sub(state.rocks));
+    config.environment.rocks = state.rocks;
+    state.water_o2 = state.water_o2.subtract(state.water_o2.percentage() * 0.1)?;
+    effects.push(format!("Rocks: {}; stirred-up sediment lowered water oxygen", state.rocks));
+    Ok(())
+}
+
+/// Move the bottle; a negative step moves it toward the window
+fn move_bottle(
+    config: &mut V2Config,
+    state: &mut EcosystemStateV2,
+    step: i8,
+    effects: &mut Vec<String>,
+) -> EcosystemResult<()> {
+    let target = (state.window_proximity as i8 + step).clamp(0, MAX_WINDOW_PROXIMITY as i8) as u8;
+    state.window_proximity = target;
+    config.environment.window_proximity = target;
+    // Carrying the bottle into or out of the sun is a small thermal shock
+    let shock = if step < 0 { 1.5 } else { -1.0 };
+    state.temperature = Temperature::new(state.temperature.celsius() + shock)?;
+    effects.push(format!(
+        "Window proximity is now {}; temperature shifted to {:.1}°C",
+        target,
+        state.temperature.celsius()
+    ));
+    Ok(())
+}
+
+/// Add a microbe culture; the newcomers raise oxygen demand immediately
+fn inoculate_microbes(state: &mut EcosystemStateV2, count: f32, effects: &mut Vec<String>) -> EcosystemResult<()> {
+    introduce(state, Microbes, count, effects)?;
+    state.microbe_pop = state.microbe_pop.add(count.max(0.0))?;
+    state.air_o2 = state.air_o2.subtract(count.max(0.0) * 0.0005)?;
+    effects.push(format!("Microbes: {:.0}", state.microbe_pop.value()));
+    Ok(())
+}
+
+/// Bring a species the bottle started without into the simulation
+fn introduce(
+    state: &mut EcosystemStateV2,
+    organism: impl Organism + 'static,
+    count: f32,
+    effects: &mut Vec<String>,
+) -> EcosystemResult<()> {
+    if count > 0.0 && !state.species.contains(organism.name()) {
+        effects.push(format!("Introduced {} to the bottle", organism.name()));
+        state.species.register(organism)?;
+    }
+    Ok(())
+}
+
+/// Add worms; digging them in compacts the surface briefly
+fn add_worms(state: &mut EcosystemStateV2, count: f32, effects: &mut Vec<String>) -> EcosystemResult<()> {
+    introduce(state, Worms, count, effects)?;
+    state.worm_pop = state.worm_pop.add(count.max(0.0))?;
+    state.soil_aeration = Aeration::new((state.soil_aeration.value() - 0.1).max(0.0))?;
+    effects.push(format!("Worms: {:.0}", state.worm_pop.value()));
+    Ok(())
+}
+
+/// Add shrimp; handling them stresses the water column
+fn add_shrimp(state: &mut EcosystemStateV2, count: f32, effects: &mut Vec<String>) -> EcosystemResult<()> {
+    introduce(state, Shrimp, count, effects)?;
+    state.shrimp_pop = state.shrimp_pop.add(count.max(0.0))?;
+    state.water_o2 = state.water_o2.subtract(state.water_o2.percentage() * 0.05)?;
+    effects.push(format!("Shrimp: {:.0}", state.shrimp_pop.value()));
+    Ok(())
+}
+
+/// Cut back a fraction of the plants; the clippings left behind become detritus
+fn prune_plants(state: &mut EcosystemStateV2, fraction: f32, effects: &mut Vec<String>) -> EcosystemResult<()> {
+    let pruned = state.plant_biomass.value() * fraction.clamp(0.0, MAX_PRUNE);
+    state.plant_biomass = state.plant_biomass.subtract(pruned)?;
+    state.detritus = Detritus::new(state.detritus.value() + pruned * 0.3)?;
+    effects.push(format!(
+        "Plants: {:.2} kg; {:.2} kg of clippings fell into the detritus",
+        state.plant_biomass.value(),
+        pruned * 0.3
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn setup() -> (V2Config, EcosystemStateV2, InterventionBudget, StdRng) {
+        let config = V2Config::new();
+        let state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        (config, state, InterventionBudget::new(10), StdRng::seed_from_u64(7))
+    }
+
+    #[test]
+    fn test_open_bottle_restores_room_air() {
+        let (mut config, mut state, mut budget, mut rng) = setup();
+        state.air_o2 = Oxygen::new(8.0).unwrap();
+        state.air_co2 = CarbonDioxide::new(3.0).unwrap();
+
+        apply_intervention(&Intervention::OpenBottle, &mut config, &mut state, &mut budget, &mut rng).unwrap();
+
+        assert_eq!(state.air_o2.percentage(), ROOM_O2);
+        assert_eq!(state.air_co2.value(), ROOM_CO2);
+        assert_eq!(budget.remaining(), 8);
+    }
+
+    #[test]
+    fn test_budget_is_enforced() {
+        let (mut config, mut state, _, mut rng) = setup();
+        let mut budget = InterventionBudget::new(2);
+
+        let result = apply_intervention(&Intervention::AddShrimp(1.0), &mut config, &mut state, &mut budget, &mut rng);
+        assert!(result.is_err());
+        assert_eq!(budget.spent, 0);
+    }
+
+    #[test]
+    fn test_move_bottle_respects_limits() {
+        let (mut config, mut state, mut budget, mut rng) = setup();
+        state.window_proximity = 0;
+
+        assert!(apply_intervention(&Intervention::MoveTowardWindow, &mut config, &mut state, &mut budget, &mut rng).is_err());
+        assert_eq!(state.window_proximity, 0);
+        assert_eq!(budget.spent, 0);
+
+        apply_intervention(&Intervention::MoveAwayFromWindow, &mut config, &mut state, &mut budget, &mut rng).unwrap();
+        assert_eq!(state.window_proximity, 1);
+        assert_eq!(config.environment.window_proximity, 1);
+    }
+
+    #[test]
+    fn test_no_op_is_not_charged() {
+        let (mut config, mut state, mut budget, mut rng) = setup();
+        state.water_liters = WaterVolume::new(MAX_WATER_LITERS).unwrap();
+        let o2_before = state.air_o2.percentage();
+
+        assert!(apply_intervention(&Intervention::AddWater(0.5), &mut config, &mut state, &mut budget, &mut rng).is_err());
+        assert!(apply_intervention(&Intervention::AddWorms(0.0), &mut config, &mut state, &mut budget, &mut rng).is_err());
+        assert_eq!(budget.spent, 0);
+        assert_eq!(state.air_o2.percentage(), o2_before);
+    }
+
+    #[test]
+    fn test_failed_intervention_leaves_bottle_unchanged() {
+        let (mut config, mut state, mut budget, mut rng) = setup();
+        state.window_proximity = 2;
+        config.environment.window_proximity = 2;
+        // The thermal shock of the move would take the bottle past the hottest valid temperature
+        state.temperature = Temperature::new(60.0).unwrap();
+
+        assert!(apply_intervention(&Intervention::MoveTowardWindow, &mut config, &mut state, &mut budget, &mut rng).is_err());
+        assert_eq!(state.window_proximity, 2);
+        assert_eq!(config.environment.window_proximity, 2);
+        assert_eq!(budget.spent, 0);
+    }
+
+    #[test]
+    fn test_commands_round_trip() {
+        assert_eq!("add-water".parse::<Intervention>().unwrap(), Intervention::AddWater(0.25));
+        assert_eq!("prune-plants 30".parse::<Intervention>().unwrap(), Intervention::PrunePlants(0.3));
+        assert!("open-bottle 2".parse::<Intervention>().is_err());
+        assert!("add-rocks -1".parse::<Intervention>().is_err());
+        assert!("feed-shrimp".parse::<Intervention>().is_err());
+        assert!("prune-plants 90".parse::<Intervention>().is_ok());
+        assert!("prune-plants 150".parse::<Intervention>().is_err());
+
+        for command in ["open-bottle", "add-water 0.5", "add-rocks 3", "move-away-from-window", "add-shrimp 2", "prune-plants 30", "prune-plants 12.5"] {
+            let intervention: Intervention = command.parse().unwrap();
+            assert_eq!(String::from(intervention), command);
+        }
+    }
+}
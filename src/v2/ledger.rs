@@ -0,0 +1,282 @@
+// v2/ledger.rs
+// Mass-balance ledger for carbon, oxygen and nitrogen inside the closed bottle
+
+use crate::v2::config::parameters::MassBalanceParams;
+use crate::v2::climate::headspace_liters;
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Sub};
+
+/// Molar volume of an ideal gas near room temperature, L/mol
+pub const MOLAR_GAS_VOLUME: f64 = 24.0;
+
+/// Organic matter is counted as CH2O, with this much carbon (mol) in a unit of biomass or
+/// detritus. Its hydrogen and oxygen came from water, which is not counted, so fixing or
+/// respiring it trades one CO2 for one O2
+const CARBON_PER_UNIT_ORGANIC: f64 = 1.0 / 80.0;
+const ORGANIC_CARBON_TO_NITROGEN: f64 = 100.0;
+
+/// Organic matter held by one individual of each counted population, in biomass units
+pub const MICROBE_BIOMASS: f32 = 1e-5;
+pub const WORM_BIOMASS: f32 = 0.01;
+pub const SHRIMP_BIOMASS: f32 = 0.01;
+
+/// Soil nitrogen pools are kept in mmol of N
+const SOIL_NITROGEN_UNIT: f64 = 1e-3;
+
+/// Moles of each tracked element
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ElementTotals {
+    pub carbon: f64,
+    pub oxygen: f64,
+    pub nitrogen: f64,
+}
+
+impl ElementTotals {
+    pub fn new(carbon: f64, oxygen: f64, nitrogen: f64) -> Self {
+        Self { carbon, oxygen, nitrogen }
+    }
+}
+
+impl Add for ElementTotals {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.carbon + other.carbon, self.oxygen + other.oxygen, self.nitrogen + other.nitrogen)
+    }
+}
+
+impl Sub for ElementTotals {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.carbon - other.carbon, self.oxygen - other.oxygen, self.nitrogen - other.nitrogen)
+    }
+}
+
+/// Element content of every pool in the bottle; water itself is not counted
+pub fn pool_totals(state: &EcosystemStateV2) -> Vec<(&'static str, ElementTotals)> {
+    let air_moles = air_moles_per_percent(state);
+    let water = state.water_liters.value() as f64;
+    let co2 = state.air_co2.value() as f64 * air_moles;
+
+    vec![
+        ("plants", organic_totals(state.plant_biomass.value())),
+        ("detritus", organic_totals(state.detritus.value())),
+        ("microbes", organic_totals(state.microbe_pop.value() * MICROBE_BIOMASS)),
+        ("worms", organic_totals(state.worm_pop.value() * WORM_BIOMASS)),
+        ("shrimp", organic_totals(state.shrimp_pop.value() * SHRIMP_BIOMASS)),
+        ("soil", ElementTotals::new(0.0, 0.0, state.soil_nitrogen() as f64 * SOIL_NITROGEN_UNIT)),
+        // Dissolved carbon is counted as CO2 so gas exchange balances exactly
+        ("water", ElementTotals::new(
+            state.water_dic as f64 * water / 1000.0,
+            state.water_dic as f64 * water / 1000.0 * 2.0 + state.water_o2.percentage() as f64 * water / 16000.0,
+            0.0,
+        )),
+        ("air", ElementTotals::new(
+            co2,
+            2.0 * (state.air_o2.percentage() as f64 * air_moles + co2),
+            2.0 * state.air_n2.value() as f64 * air_moles,
+        )),
+    ]
+}
+
+/// Total moles of C, O and N across every pool
+pub fn element_totals(state: &EcosystemStateV2) -> ElementTotals {
+    pool_totals(state)
+        .into_iter()
+        .fold(ElementTotals::default(), |sum, (_, pool)| sum + pool)
+}
+
+/// Moles of gas in one percent of the headspace
+fn air_moles_per_percent(state: &EcosystemStateV2) -> f64 {
+    headspace_liters(state) as f64 / MOLAR_GAS_VOLUME / 100.0
+}
+
+/// C and N held by `amount` biomass units of organic matter
+fn organic_totals(amount: f32) -> ElementTotals {
+    let carbon = amount as f64 * CARBON_PER_UNIT_ORGANIC;
+    ElementTotals::new(carbon, 0.0, carbon / ORGANIC_CARBON_TO_NITROGEN)
+}
+
+/// Air N2 (in percent) holding the same nitrogen as `amount` of soil nitrogen
+pub fn soil_nitrogen_as_air_n2(state: &EcosystemStateV2, amount: f32) -> f32 {
+    (amount as f64 * SOIL_NITROGEN_UNIT / (2.0 * air_moles_per_percent(state))) as f32
+}
+
+/// Air CO2 (in percent) holding the same carbon as `amount` of organic matter
+pub fn organic_as_air_co2(state: &EcosystemStateV2, amount: f32) -> f32 {
+    (amount as f64 * CARBON_PER_UNIT_ORGANIC / air_moles_per_percent(state)) as f32
+}
+
+/// Organic matter holding the same carbon as `percent` of air CO2
+pub fn air_co2_as_organic(state: &EcosystemStateV2, percent: f32) -> f32 {
+    (percent as f64 * air_moles_per_percent(state) / CARBON_PER_UNIT_ORGANIC) as f32
+}
+
+/// Soil nitrogen held in `amount` of organic matter
+pub fn organic_nitrogen(amount: f32) -> f32 {
+    (amount as f64 * CARBON_PER_UNIT_ORGANIC / ORGANIC_CARBON_TO_NITROGEN / SOIL_NITROGEN_UNIT) as f32
+}
+
+/// Air O2 (in percent) holding the same oxygen as `amount` mg/L of dissolved O2
+pub fn water_o2_as_air_o2(state: &EcosystemStateV2, amount: f32) -> f32 {
+    let moles = amount as f64 * state.water_liters.value() as f64 / 32000.0;
+    (moles / air_moles_per_percent(state)) as f32
+}
+
+/// Elements in `amount` of soil nitrogen
+pub fn soil_nitrogen_totals(amount: f32) -> ElementTotals {
+    ElementTotals::new(0.0, 0.0, amount as f64 * SOIL_NITROGEN_UNIT)
+}
+
+/// Running account of what the bottle should contain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MassLedger {
+    expected: ElementTotals,
+    last_report: Option<MassBalanceReport>,
+}
+
+impl MassLedger {
+    pub fn new(totals: ElementTotals) -> Self {
+        Self { expected: totals, last_report: None }
+    }
+
+    /// Matter deliberately brought in from outside, such as dissolved limestone
+    pub fn record_inflow(&mut self, amount: ElementTotals) {
+        self.expected = self.expected + amount;
+    }
+
+    /// Matter locked away where the ledger no longer counts it, such as adsorbed ammonia
+    pub fn record_outflow(&mut self, amount: ElementTotals) {
+        self.expected = self.expected - amount;
+    }
+
+    pub fn last_report(&self) -> Option<&MassBalanceReport> {
+        self.last_report.as_ref()
+    }
+}
+
+/// Difference between what the bottle holds and what the ledger expects
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MassBalanceReport {
+    pub expected: ElementTotals,
+    pub actual: ElementTotals,
+    pub drift: ElementTotals,
+}
+
+impl MassBalanceReport {
+    /// Drift of each element as a fraction of the expected amount
+    pub fn relative_drift(&self) -> ElementTotals {
+        let relative = |drift: f64, expected: f64| if expected.abs() > f64::EPSILON { drift / expected } else { drift };
+        ElementTotals::new(
+            relative(self.drift.carbon, self.expected.carbon),
+            relative(self.drift.oxygen, self.expected.oxygen),
+            relative(self.drift.nitrogen, self.expected.nitrogen),
+        )
+    }
+
+    /// Largest relative drift of any element
+    pub fn worst_drift(&self) -> f64 {
+        let relative = self.relative_drift();
+        relative.carbon.abs().max(relative.oxygen.abs()).max(relative.nitrogen.abs())
+    }
+
+    pub fn is_within(&self, tolerance: f64) -> bool {
+        self.worst_drift() <= tolerance
+    }
+}
+
+impl fmt::Display for MassBalanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let relative = self.relative_drift();
+        write!(
+            f,
+            "C {:+.2}%, O {:+.2}%, N {:+.2}%",
+            relative.carbon * 100.0,
+            relative.oxygen * 100.0,
+            relative.nitrogen * 100.0
+        )
+    }
+}
+
+/// Compare the bottle against the ledger; strict mode turns drift into an error
+pub fn audit_mass_balance(
+    state: &mut EcosystemStateV2,
+    params: &MassBalanceParams,
+) -> EcosystemResult<MassBalanceReport> {
+    let actual = element_totals(state);
+    let expected = state.ledger.expected;
+    let report = MassBalanceReport { expected, actual, drift: actual - expected };
+    state.ledger.last_report = Some(report);
+
+    if params.strict && !report.is_within(params.tolerance as f64) {
+        return Err(EcosystemError::SimulationError {
+            message: format!("Mass balance violated beyond {:.2}%: {}", params.tolerance * 100.0, report),
+        });
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::config::V2Config;
+    use crate::v2::config::environment::SoilType;
+    use crate::v2::config::parameters::PhModel;
+    use crate::v2::simulation_refactored::update_ecosystem_v2;
+    use crate::v2::integrator::{integrate, Derivatives};
+
+    #[test]
+    fn test_gas_exchange_conserves_carbon() {
+        let mut config = V2Config::new();
+        config.parameters.ph_model = PhModel::Carbonate;
+        config.environment.rocks = 0;
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        state.air_co2 = crate::v2::types::CarbonDioxide::new(2.0).unwrap();
+        state.ledger = MassLedger::new(element_totals(&state));
+
+        let gas_exchange_only = |state: &EcosystemStateV2, config: &V2Config| {
+            let mut rates = Derivatives::default();
+            crate::v2::carbonate::carbonate_rates(state, &config.parameters.carbonate, &mut rates);
+            Ok(rates)
+        };
+        integrate(&mut state, &config, 1.0, gas_exchange_only).unwrap();
+
+        let report = audit_mass_balance(&mut state, &config.parameters.mass_balance).unwrap();
+        assert!(report.relative_drift().carbon.abs() < 1e-5, "carbon drift {}", report);
+    }
+
+    #[test]
+    fn test_default_day_conserves_mass() {
+        for soil in [SoilType::Balanced, SoilType::Clay, SoilType::Sand, SoilType::ActivatedCharcoal] {
+            let mut config = V2Config::new();
+            config.environment.soil_type = soil;
+            config.parameters.mass_balance.strict = true;
+            let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+
+            update_ecosystem_v2(&config, &mut state, true).unwrap();
+            update_ecosystem_v2(&config, &mut state, false).unwrap();
+
+            let report = state.ledger.last_report().unwrap();
+            assert!(report.worst_drift() < 1e-4, "{:?}: {}", soil, report);
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_reports_violation() {
+        let mut config = V2Config::new();
+        config.parameters.mass_balance.strict = true;
+        config.parameters.mass_balance.tolerance = 0.0;
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        state.plant_biomass = crate::v2::types::Biomass::new(5.0).unwrap();
+
+        let result = update_ecosystem_v2(&config, &mut state, true);
+        assert!(matches!(result, Err(EcosystemError::SimulationError { .. })));
+        assert!(state.ledger.last_report().is_some());
+    }
+}
+
@@ -3,6 +3,11 @@ pub mod types;
 pub mod errors;
 pub mod traits;
 pub mod environmental;
+pub mod climate;
+pub mod soil;
+pub mod carbonate;
+pub mod ledger;
+pub mod integrator;
 
 // Configuration
 pub mod config;
@@ -18,7 +23,19 @@ pub mod simulation_refactored;
 
 // Monte Carlo analysis
 pub mod montecarlo;
+pub mod sampling;
+pub mod export;
+pub mod sensitivity;
+pub mod survival;
+pub mod trajectory;
+pub mod sweep;
+pub mod optimize;
+
+// Player interventions
+pub mod interventions;
 
 // Game interface
 pub mod game;
 pub mod input;
+pub mod script;
+pub mod save;
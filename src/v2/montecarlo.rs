@@ -1,22 +1,48 @@
 // v2/montecarlo.rs
 // Monte Carlo simulation for v2 ecosystem
 
+use crate::v2::config::parameters::{CollapseCriteria, IntegrationParams};
+use crate::v2::organisms::OrganismRegistry;
 use crate::v2::config::V2Config;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::config::environment::SoilType;
 use crate::v2::state::EcosystemStateV2;
 use crate::v2::simulation_refactored::update_ecosystem_v2;
-use crate::v2::traits::{CollapseDetection, EcosystemValidation, EcosystemDisplay};
+use crate::v2::survival::{KaplanMeier, print_survival_curve};
+use crate::v2::sampling::{Dimension, SamplingPlan, SamplingStrategy, grid_size};
+use crate::v2::trajectory::{Trajectory, TrajectorySelection};
+use crate::v2::traits::{CollapseDetection, EcosystemDisplay};
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct MonteCarloConfig {
     pub num_runs: usize,
     pub day_cap: usize,
-    pub difficulty_range: (f32, f32),
+    /// How runs are spread over `dimensions`
+    pub sampling: SamplingStrategy,
+    /// Range and distribution of every sampled input, difficulty included
+    pub dimensions: Vec<Dimension>,
     pub randomize_environment: bool,
     pub randomize_organisms: bool,
     pub show_progress: bool,
+    pub integration: IntegrationParams,
+    pub species: OrganismRegistry,
+    pub collapse: CollapseCriteria,
+    /// Master seed every run's seed is derived from; drawn from entropy when unset
+    pub seed: Option<u64>,
+    /// Worker threads; defaults to the number of available cores
+    pub threads: Option<usize>,
+    /// Runs whose half-day metrics are kept in `SimulationResult::trajectory`
+    pub record_trajectories: TrajectorySelection,
+    /// Fields fixed after sampling, as paths accepted by `V2Config::set_field`
+    pub overrides: Vec<(String, f32)>,
+    /// Bottle every run uses instead of sampled settings; only the starting chemistry varies
+    pub base_config: Option<V2Config>,
 }
 
 impl Default for MonteCarloConfig {
@@ -24,55 +50,180 @@ impl Default for MonteCarloConfig {
         Self {
             num_runs: 1000,
             day_cap: 30,
-            difficulty_range: (0.3, 0.7),
+            sampling: SamplingStrategy::default(),
+            dimensions: Dimension::defaults(),
             randomize_environment: true,
             randomize_organisms: true,
             show_progress: true,
+            integration: IntegrationParams::default(),
+            species: OrganismRegistry::standard(),
+            collapse: CollapseCriteria::default(),
+            seed: None,
+            threads: None,
+            record_trajectories: TrajectorySelection::None,
+            overrides: Vec::new(),
+            base_config: None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SimulationResult {
     pub run_id: usize,
+    /// Seed this run was derived from; `derive_run_seed(master_seed, run_id)`
+    pub seed: u64,
     pub survived: bool,
     pub days_survived: usize,
+    #[serde(serialize_with = "crate::v2::export::serialize_reasons")]
     pub collapse_reasons: Vec<crate::v2::errors::CollapseReason>,
+    /// Simulation error the run stopped on; such runs neither survived nor collapsed
+    pub error: Option<String>,
     pub final_state: FinalStateSnapshot,
     pub config_snapshot: ConfigSnapshot,
+    /// Recorded only for runs picked by `MonteCarloConfig::record_trajectories`
+    #[serde(skip)]
+    pub trajectory: Option<Trajectory>,
 }
 
-#[derive(Debug, Clone)]
+/// Populations are `None` for species that were never in the bottle
+#[derive(Debug, Clone, Serialize)]
 pub struct FinalStateSnapshot {
-    pub plant_biomass: f32,
-    pub microbe_pop: f32,
-    pub worm_pop: f32,
-    pub shrimp_pop: f32,
+    pub plant_biomass: Option<f32>,
+    pub microbe_pop: Option<f32>,
+    pub worm_pop: Option<f32>,
+    pub shrimp_pop: Option<f32>,
     pub soil_ph: f32,
     pub air_o2: f32,
     pub temperature: f32,
     pub humidity: f32,
 }
 
-#[derive(Debug, Clone)]
+impl FinalStateSnapshot {
+    pub fn of(state: &EcosystemStateV2) -> Self {
+        let present = |species: &str, value: f32| state.species.contains(species).then_some(value);
+        Self {
+            plant_biomass: present("plants", state.plant_biomass.value()),
+            microbe_pop: present("microbes", state.microbe_pop.value()),
+            worm_pop: present("worms", state.worm_pop.value()),
+            shrimp_pop: present("shrimp", state.shrimp_pop.value()),
+            soil_ph: state.soil_ph.value(),
+            air_o2: state.air_o2.percentage(),
+            temperature: state.temperature.celsius(),
+            humidity: state.humidity.percentage(),
+        }
+    }
+}
+
+/// Everything sampled for one run, as it was simulated: the bottle and the initial state built from it
+#[derive(Debug, Clone, Serialize)]
 pub struct ConfigSnapshot {
     pub difficulty: f32,
+    pub species: Vec<&'static str>,
     pub microbe_count: usize,
     pub worm_count: usize,
     pub shrimp_count: usize,
+    pub plant_biomass: f32,
     pub water_volume: f32,
     pub rocks: usize,
     pub window_proximity: u8,
+    pub soil_type: SoilType,
+    pub room_temp: f32,
     pub initial_temp: f32,
     pub initial_humidity: f32,
+    pub initial_state: InitialStateSnapshot,
+}
+
+/// State the run actually started from; `new_with_seed` randomizes the soil and water
+/// chemistry on top of the configuration
+#[derive(Debug, Clone, Serialize)]
+pub struct InitialStateSnapshot {
+    pub microbe_pop: f32,
+    pub worm_pop: f32,
+    pub shrimp_pop: f32,
+    pub soil_ph: f32,
+    pub soil_ammonia: f32,
+    pub soil_nitrate: f32,
+    pub soil_moisture: f32,
+    pub soil_aeration: f32,
+    pub detritus: f32,
+    pub water_o2: f32,
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+/// Everything besides the master seed that decides a batch's runs, so any of them can be replayed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchSetup {
+    pub runs: usize,
+    pub days: usize,
+    pub sampling: SamplingStrategy,
+    pub dimensions: Vec<Dimension>,
+    pub integration: IntegrationParams,
+    pub species: OrganismRegistry,
+    pub collapse: CollapseCriteria,
+    /// Bottle every run used, if the batch had one
+    pub config: Option<V2Config>,
+}
+
+impl BatchSetup {
+    pub fn of(mc_config: &MonteCarloConfig) -> Self {
+        Self {
+            runs: mc_config.num_runs,
+            days: mc_config.day_cap,
+            sampling: mc_config.sampling,
+            dimensions: mc_config.dimensions.clone(),
+            integration: mc_config.integration.clone(),
+            species: mc_config.species.clone(),
+            collapse: mc_config.collapse,
+            config: mc_config.base_config.clone(),
+        }
+    }
+
+    /// The `replay` command for a run of this batch; a fixed bottle has to be passed with `--config`
+    pub fn replay_command(&self, master_seed: u64, run_id: &str) -> String {
+        let mut command = format!("replay {} {} --runs {} --days {}", master_seed, run_id, self.runs, self.days);
+        if self.config.is_some() {
+            command += " --config <the batch's bottle>";
+        } else {
+            command += &format!(" --sampling {}", String::from(self.sampling));
+            let defaults = Dimension::defaults();
+            for dimension in self.dimensions.iter().filter(|d| !defaults.contains(d)) {
+                command += &format!(" --dim {}", dimension);
+            }
+        }
+        let method = self.integration.method.to_string().to_lowercase();
+        command + &format!(" --integrator {} --sub-steps {}", method, self.integration.sub_steps)
+    }
+
+    /// A configuration that runs the batch again
+    pub fn monte_carlo_config(&self) -> MonteCarloConfig {
+        MonteCarloConfig {
+            num_runs: self.runs,
+            day_cap: self.days,
+            sampling: self.sampling,
+            dimensions: self.dimensions.clone(),
+            integration: self.integration.clone(),
+            species: self.species.clone(),
+            collapse: self.collapse,
+            base_config: self.config.clone(),
+            ..MonteCarloConfig::default()
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct MonteCarloResults {
+    pub master_seed: u64,
+    pub setup: BatchSetup,
     pub results: Vec<SimulationResult>,
+    /// Runs that stopped on a simulation error; left out of the rates and censored in the curve
+    pub failed_runs: usize,
     pub survival_rate: f32,
     pub average_days_survived: f32,
     pub survival_histogram: HashMap<usize, usize>,
+    /// Runs reaching the day cap are right-censored rather than counted as survivors
+    pub survival_curve: KaplanMeier,
     pub collapse_reasons_frequency: HashMap<String, usize>,
     pub survivor_analysis: SurvivorAnalysis,
 }
@@ -81,81 +232,240 @@ pub struct MonteCarloResults {
 pub struct SurvivorAnalysis {
     pub optimal_difficulty: Option<f32>,
     pub optimal_microbe_count: Option<usize>,
-    pub optimal_environment: Option<ConfigSnapshot>,
     pub success_factors: Vec<(String, f32)>,
 }
 
-pub fn run_monte_carlo_v2(mc_config: MonteCarloConfig) -> MonteCarloResults {
-    let mut results = Vec::with_capacity(mc_config.num_runs);
-    let mut rng = StdRng::from_entropy();
+pub fn run_monte_carlo_v2(mc_config: MonteCarloConfig) -> EcosystemResult<MonteCarloResults> {
+    let master_seed = mc_config.seed.unwrap_or_else(|| StdRng::from_entropy().r#gen());
+    let threads = worker_threads(&mc_config);
     
     println!("🧪 Running {} Monte Carlo simulations for v2 ecosystem", mc_config.num_runs);
     println!("📊 Configuration:");
     println!("   Days to survive: {}", mc_config.day_cap);
-    println!("   Difficulty range: {:.1}% - {:.1}%", 
-             mc_config.difficulty_range.0 * 100.0, 
-             mc_config.difficulty_range.1 * 100.0);
-    println!("   Randomize environment: {}", mc_config.randomize_environment);
-    println!("   Randomize organisms: {}", mc_config.randomize_organisms);
+    if let Some(base) = &mc_config.base_config {
+        println!("   Bottle: fixed configuration at {:.0}% difficulty; only the starting chemistry varies", base.difficulty.level * 100.0);
+    } else {
+        println!("   Sampling: {}", mc_config.sampling);
+        if let SamplingStrategy::Grid { levels } = mc_config.sampling {
+            let size = grid_size(&mc_config.dimensions, levels);
+            if size != mc_config.num_runs {
+                println!("   ⚠️  The grid has {} points; {} runs {}", size, mc_config.num_runs,
+                         if mc_config.num_runs < size { "leave part of it unvisited" } else { "revisit some points" });
+            }
+        }
+        for dimension in &mc_config.dimensions {
+            println!("      {:<17} {} – {} ({})", dimension.name, dimension.low, dimension.high, dimension.distribution);
+        }
+        println!("   Randomize environment: {}", mc_config.randomize_environment);
+        println!("   Randomize organisms: {}", mc_config.randomize_organisms);
+    }
+    println!("   Integrator: {} with {} sub-steps per half-day",
+             mc_config.integration.method,
+             mc_config.integration.sub_steps);
+    println!("   Species: {}", mc_config.species.names().join(", "));
+    println!("   Collapse when: {}", mc_config.collapse);
+    println!("   Master seed: {} ({} threads)", master_seed, threads);
+    if mc_config.record_trajectories != TrajectorySelection::None {
+        println!("   Recording trajectories of: {}", mc_config.record_trajectories);
+    }
     println!();
 
-    // Progress tracking
-    let progress_interval = (mc_config.num_runs / 20).max(1);
+    let started = Instant::now();
+    let results = run_parallel(&mc_config, master_seed, threads)?;
     
-    for run_id in 0..mc_config.num_runs {
-        if mc_config.show_progress && run_id % progress_interval == 0 {
-            let percent = (run_id as f32 / mc_config.num_runs as f32) * 100.0;
-            print!("\r🔄 Progress: [{:>3.0}%] Running simulation {}/{}", 
-                   percent, run_id + 1, mc_config.num_runs);
-            std::io::Write::flush(&mut std::io::stdout()).unwrap();
-        }
-
-        let result = run_single_simulation(run_id, &mc_config, &mut rng);
-        results.push(result);
+    if mc_config.show_progress {
+        let elapsed = started.elapsed().as_secs_f32();
+        println!("\r✅ Completed {} simulations in {:.1}s ({:.0} runs/s)                    ",
+                 mc_config.num_runs, elapsed, mc_config.num_runs as f32 / elapsed.max(1e-6));
     }
+
+    Ok(analyze_results(results, master_seed, BatchSetup::of(&mc_config)))
+}
+
+pub(crate) fn worker_threads(mc_config: &MonteCarloConfig) -> usize {
+    default_threads(mc_config.threads).min(mc_config.num_runs.max(1))
+}
+
+/// The requested worker count, or one per available core
+pub(crate) fn default_threads(requested: Option<usize>) -> usize {
+    requested
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        .max(1)
+}
+
+/// Re-run a single run of a batch with day-by-day output; `mc_config` must match the batch
+pub fn replay_run(mc_config: &MonteCarloConfig, master_seed: u64, run_id: usize) -> EcosystemResult<SimulationResult> {
+    println!("🔁 Replaying run {} of batch seed {} (run seed {})",
+             run_id, master_seed, derive_run_seed(master_seed, run_id));
+    println!("   As one of {} runs of {} days, {} sampling, {} with {} sub-steps",
+             mc_config.num_runs, mc_config.day_cap, mc_config.sampling,
+             mc_config.integration.method, mc_config.integration.sub_steps);
+    println!();
     
-    if mc_config.show_progress {
-        println!("\r✅ Completed {} simulations!                    ", mc_config.num_runs);
+    let plan = SamplingPlan::new(mc_config.sampling, &mc_config.dimensions, mc_config.num_runs.max(run_id + 1), master_seed);
+    let result = run_single_simulation(run_id, mc_config, &plan, master_seed, |day, is_day, state| {
+        println!("Day {:2} {}: {}", day, if is_day { "☀️ " } else { "🌙" }, state.display_status());
+    })?;
+    
+    let snapshot = &result.config_snapshot;
+    println!("\n📋 Run configuration:");
+    println!("   Species: {}", snapshot.species.join(", "));
+    let initial = &snapshot.initial_state;
+    println!("   Difficulty: {:.1}%", snapshot.difficulty * 100.0);
+    println!("   Starting life: {:.2} kg plants, {:.0} microbes, {:.0} worms, {:.0} shrimp",
+             snapshot.plant_biomass, initial.microbe_pop, initial.worm_pop, initial.shrimp_pop);
+    println!("   Soil: {}, pH {:.2}, NH3/NH4+ {:.2}, NO3- {:.2}, moisture {:.2}, aeration {:.2}, detritus {:.2}",
+             snapshot.soil_type, initial.soil_ph, initial.soil_ammonia, initial.soil_nitrate,
+             initial.soil_moisture, initial.soil_aeration, initial.detritus);
+    println!("   Environment: {:.1}L water ({:.1}% O2), {} rocks, window proximity {}, {:.1}°C in a {:.1}°C room, {:.1}% humidity",
+             snapshot.water_volume, initial.water_o2, snapshot.rocks, snapshot.window_proximity,
+             initial.temperature, snapshot.room_temp, initial.humidity);
+    
+    if result.survived {
+        println!("\n🎉 Survived all {} days", result.days_survived);
+    } else if let Some(error) = &result.error {
+        println!("\n⚠️  Simulation stopped after {} days: {}", result.days_survived, error);
+    } else if result.collapse_reasons.is_empty() {
+        println!("\n⚠️  Simulation stopped after {} days without a recorded collapse", result.days_survived);
+    } else {
+        println!("\n💀 Collapsed after {} days:", result.days_survived);
+        for reason in &result.collapse_reasons {
+            println!("   - {}", reason);
+        }
     }
+    
+    Ok(result)
+}
 
-    analyze_results(results, mc_config)
+/// Seed of one run, mixed from the master seed so neighbouring runs are unrelated (SplitMix64)
+pub fn derive_run_seed(master_seed: u64, run_id: usize) -> u64 {
+    let mut z = master_seed.wrapping_add((run_id as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
-fn run_single_simulation(
-    run_id: usize, 
-    mc_config: &MonteCarloConfig, 
-    rng: &mut StdRng
-) -> SimulationResult {
-    // Generate random configuration
-    let config = generate_random_config(mc_config, rng);
-    let config_snapshot = create_config_snapshot(&config, mc_config, rng);
+/// Hand runs out to worker threads one at a time; results come back in run order
+pub(crate) fn run_parallel(mc_config: &MonteCarloConfig, master_seed: u64, threads: usize) -> EcosystemResult<Vec<SimulationResult>> {
+    let plan = SamplingPlan::new(mc_config.sampling, &mc_config.dimensions, mc_config.num_runs, master_seed);
+    run_jobs(mc_config.num_runs, threads, mc_config.show_progress, |run_id| {
+        run_single_simulation(run_id, mc_config, &plan, master_seed, |_, _, _| {})
+    })
+}
+
+/// Run jobs `0..jobs` on worker threads, each taking the next job as it finishes one; results
+/// come back in job order. The first error or worker panic stops the batch
+pub(crate) fn run_jobs<R: Send>(
+    jobs: usize,
+    threads: usize,
+    show_progress: bool,
+    job: impl Fn(usize) -> EcosystemResult<R> + Sync,
+) -> EcosystemResult<Vec<R>> {
+    let next_job = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
     
-    // Create initial state
-    let seed = rng.r#gen::<u64>();
-    let mut state = match EcosystemStateV2::new_with_seed(&config, seed) {
-        Ok(state) => state,
-        Err(_) => {
-            // If seeded creation fails, use default
-            EcosystemStateV2::new(&config).unwrap_or_else(|_| {
-                // Last resort - create with default config
-                let default_config = V2Config::new();
-                EcosystemStateV2::new(&default_config).unwrap()
-            })
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, jobs.max(1)))
+            .map(|_| scope.spawn(|| {
+                let mut finished = Vec::new();
+                loop {
+                    let id = next_job.fetch_add(1, Ordering::Relaxed);
+                    if id >= jobs {
+                        break Ok(finished);
+                    }
+                    finished.push((id, job(id)?));
+                    completed.fetch_add(1, Ordering::Relaxed);
+                }
+            }))
+            .collect();
+        
+        if show_progress && jobs > 0 {
+            report_progress(&completed, jobs, || workers.iter().all(|w| w.is_finished()));
         }
-    };
+        
+        workers
+            .into_iter()
+            .map(|worker| worker.join().map_err(|panic| EcosystemError::SimulationError {
+                message: format!("A worker thread failed: {}", panic_message(panic.as_ref())),
+            })?)
+            .collect::<EcosystemResult<Vec<_>>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect();
+    
+    results.sort_by_key(|(id, _)| *id);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    panic.downcast_ref::<&str>().copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("panicked")
+}
+
+/// Redraw the progress line until every run is done or every worker has stopped
+fn report_progress(completed: &AtomicUsize, total: usize, workers_finished: impl Fn() -> bool) {
+    let started = Instant::now();
+    loop {
+        let done = completed.load(Ordering::Relaxed);
+        let elapsed = started.elapsed().as_secs_f32();
+        
+        // Throughput is meaningless until a few runs have had time to finish
+        let rate = if elapsed >= 0.2 && done > 0 {
+            let throughput = done as f32 / elapsed;
+            format!("{:.0} runs/s, ~{:.0}s left", throughput, (total - done) as f32 / throughput)
+        } else {
+            "measuring throughput".to_string()
+        };
+        
+        print!("\r🔄 Progress: [{:>3.0}%] {}/{} runs, {}   ",
+               done as f32 / total as f32 * 100.0, done, total, rate);
+        std::io::Write::flush(&mut std::io::stdout()).unwrap();
+        
+        if done >= total {
+            break;
+        }
+        if workers_finished() {
+            // A worker stopped early; leave the line for the error that follows
+            println!();
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// How a single run ended
+#[derive(Debug, Clone)]
+pub struct RunOutcome {
+    pub survived: bool,
+    pub days_survived: usize,
+    pub collapse_reasons: Vec<crate::v2::errors::CollapseReason>,
+    pub error: Option<String>,
+}
 
+/// Simulate until collapse or `day_cap` days; `observe` sees the state after every half-day
+pub fn run_to_collapse(
+    config: &V2Config,
+    state: &mut EcosystemStateV2,
+    day_cap: usize,
+    mut observe: impl FnMut(usize, bool, &EcosystemStateV2),
+) -> RunOutcome {
     let mut days_survived = 0;
     let mut survived = false;
     let mut collapse_reasons = Vec::new();
+    let mut error = None;
 
     // Run simulation
-    for day in 0..(mc_config.day_cap * 2) { // *2 for day/night cycles
+    for day in 0..(day_cap * 2) { // *2 for day/night cycles
         let is_day = day % 2 == 0;
         
         // Update ecosystem
-        if let Err(_) = update_ecosystem_v2(&config, &mut state, is_day) {
+        if let Err(e) = update_ecosystem_v2(config, state, is_day) {
+            error = Some(e.to_string());
             break;
         }
+        observe(day / 2 + 1, is_day, state);
 
         // Check for collapse
         if state.is_collapsed() {
@@ -168,51 +478,106 @@ fn run_single_simulation(
         }
 
         // Check if we've reached the goal
-        if days_survived >= mc_config.day_cap {
+        if days_survived >= day_cap {
             survived = true;
             break;
         }
     }
 
-    let final_state = FinalStateSnapshot {
-        plant_biomass: state.plant_biomass.value(),
-        microbe_pop: state.microbe_pop.value(),
-        worm_pop: state.worm_pop.value(),
-        shrimp_pop: state.shrimp_pop.value(),
-        soil_ph: state.soil_ph.value(),
-        air_o2: state.air_o2.percentage(),
-        temperature: state.temperature.celsius(),
-        humidity: state.humidity.percentage(),
+    RunOutcome { survived, days_survived, collapse_reasons, error }
+}
+
+/// Simulate one run from its seed; `observe` sees the state after every half-day
+fn run_single_simulation(
+    run_id: usize, 
+    mc_config: &MonteCarloConfig, 
+    plan: &SamplingPlan,
+    master_seed: u64,
+    mut observe: impl FnMut(usize, bool, &EcosystemStateV2),
+) -> EcosystemResult<SimulationResult> {
+    let run_seed = derive_run_seed(master_seed, run_id);
+    let mut rng = StdRng::seed_from_u64(run_seed);
+    
+    // Generate random configuration
+    let config = generate_random_config(mc_config, plan, run_id, &mut rng)?;
+    
+    // Create initial state
+    let seed = rng.r#gen::<u64>();
+    let mut state = match EcosystemStateV2::new_with_seed(&config, seed) {
+        Ok(state) => state,
+        Err(_) => {
+            // If seeded creation fails, use default
+            EcosystemStateV2::new(&config).unwrap_or_else(|_| {
+                // Last resort - create with default config
+                let default_config = V2Config::new();
+                EcosystemStateV2::new(&default_config).unwrap()
+            })
+        }
     };
 
-    SimulationResult {
+    let config_snapshot = create_config_snapshot(&config, &state);
+
+    let mut trajectory = mc_config.record_trajectories.records(run_seed).then(Trajectory::default);
+    if let Some(trajectory) = trajectory.as_mut() {
+        trajectory.record(&state);
+    }
+
+    let RunOutcome { survived, days_survived, collapse_reasons, error } =
+        run_to_collapse(&config, &mut state, mc_config.day_cap, |day, is_day, state| {
+            if let Some(trajectory) = trajectory.as_mut() {
+                trajectory.record(state);
+            }
+            observe(day, is_day, state);
+        });
+
+    let final_state = FinalStateSnapshot::of(&state);
+
+    let mut result = SimulationResult {
         run_id,
+        seed: run_seed,
         survived,
         days_survived,
         collapse_reasons,
+        error,
         final_state,
         config_snapshot,
+        trajectory: None,
+    };
+    if mc_config.record_trajectories.keeps(&result) {
+        result.trajectory = trajectory;
     }
+    Ok(result)
 }
 
-fn generate_random_config(mc_config: &MonteCarloConfig, rng: &mut StdRng) -> V2Config {
-    let difficulty = rng.gen_range(mc_config.difficulty_range.0..=mc_config.difficulty_range.1);
+fn generate_random_config(mc_config: &MonteCarloConfig, plan: &SamplingPlan, run_id: usize, rng: &mut StdRng) -> EcosystemResult<V2Config> {
+    let values = plan.sample(run_id, &mc_config.dimensions, rng);
+    let value = |name: &str| {
+        mc_config.dimensions.iter().position(|d| d.name == name).map(|i| values[i])
+    };
+    let difficulty = value("difficulty").unwrap_or(0.5);
     
-    let mut config = match V2Config::with_difficulty(difficulty) {
-        Ok(config) => config,
-        Err(_) => V2Config::new(), // Fallback to default
+    let mut config = match &mc_config.base_config {
+        Some(base) => base.clone(),
+        None => match V2Config::with_difficulty(difficulty) {
+            Ok(config) => config,
+            Err(_) => V2Config::new(), // Fallback to default
+        },
     };
+    config.parameters.integration = mc_config.integration.clone();
+    let sampled = mc_config.base_config.is_none();
 
-    if mc_config.randomize_environment {
-        // Randomize environment parameters
+    if sampled && mc_config.randomize_environment {
+        // Sampled environment, falling back to the difficulty's defaults for dimensions left out
         use crate::v2::config::environment::*;
-        use crate::v2::types::*;
         
-        let water_volume = rng.gen_range(0.2..=2.0);
-        let rocks = rng.gen_range(0..=5);
-        let window_proximity = rng.gen_range(1..=6);
-        let temp = rng.gen_range(15.0..=30.0);
-        let humidity = rng.gen_range(30.0..=90.0);
+        let environment = &config.environment;
+        let water_volume = value("water_volume").unwrap_or(environment.water_volume.value());
+        let rocks = value("rocks").map_or(environment.rocks, |v| v as usize);
+        let window_proximity = value("window_proximity").map_or(environment.window_proximity, |v| v as u8);
+        let temp = value("temperature").unwrap_or(environment.initial_temperature.celsius());
+        let humidity = value("humidity").unwrap_or(environment.initial_humidity.percentage());
+        let room_temp = value("room_temperature").unwrap_or(environment.room_temperature.celsius());
+        let soil_type = value("soil_type").map_or(environment.soil_type, |v| SoilType::ALL[(v as usize).min(SoilType::ALL.len() - 1)]);
         
         if let Ok(env_config) = EnvironmentConfig::new(
             water_volume,
@@ -220,20 +585,21 @@ fn generate_random_config(mc_config: &MonteCarloConfig, rng: &mut StdRng) -> V2C
             window_proximity,
             temp,
             humidity,
-            SoilType::Balanced,
-        ) {
+            soil_type,
+        ).and_then(|env| env.with_room_temperature(room_temp)) {
             config.environment = env_config;
         }
     }
 
-    if mc_config.randomize_organisms {
-        // Randomize organism counts
+    if sampled && mc_config.randomize_organisms {
+        // Sampled organism counts
         use crate::v2::config::organisms::*;
         
-        let microbe_count = rng.gen_range(100..=5000);
-        let worm_count = rng.gen_range(1..=15);
-        let shrimp_count = rng.gen_range(1..=8);
-        let plant_biomass = rng.gen_range(0.5..=3.0);
+        let organisms = &config.organisms;
+        let microbe_count = value("microbes").map_or(organisms.microbes.initial_count, |v| v as usize);
+        let worm_count = value("worms").map_or(organisms.worms.initial_count, |v| v as usize);
+        let shrimp_count = value("shrimp").map_or(organisms.shrimp.initial_count, |v| v as usize);
+        let plant_biomass = value("plant_biomass").unwrap_or(organisms.plants.initial_biomass);
         
         if let Ok(org_config) = OrganismConfig::new(
             microbe_count,
@@ -245,38 +611,75 @@ fn generate_random_config(mc_config: &MonteCarloConfig, rng: &mut StdRng) -> V2C
         }
     }
 
-    config
+    for (path, value) in &mc_config.overrides {
+        config.set_field(path, *value)?;
+    }
+
+    // Species left out of the study start with nothing
+    config.parameters.collapse = mc_config.collapse;
+    for species in config.species.names() {
+        if !mc_config.species.contains(species) {
+            config.remove_species(species);
+        }
+    }
+    config.species = mc_config.species.clone();
+
+    Ok(config)
 }
 
-fn create_config_snapshot(config: &V2Config, mc_config: &MonteCarloConfig, rng: &StdRng) -> ConfigSnapshot {
+fn create_config_snapshot(config: &V2Config, state: &EcosystemStateV2) -> ConfigSnapshot {
     ConfigSnapshot {
-        difficulty: mc_config.difficulty_range.0, // Approximation
-        microbe_count: config.organisms.microbes.initial_count,
-        worm_count: config.organisms.worms.initial_count,
-        shrimp_count: config.organisms.shrimp.initial_count,
-        water_volume: config.environment.water_volume.value(),
-        rocks: config.environment.rocks,
-        window_proximity: config.environment.window_proximity,
-        initial_temp: config.environment.initial_temperature.celsius(),
-        initial_humidity: config.environment.initial_humidity.percentage(),
+        difficulty: config.difficulty.level,
+        species: config.species.names(),
+        // Read back from the state, so species left out show as none
+        microbe_count: state.microbe_pop.value().round() as usize,
+        worm_count: state.worm_pop.value().round() as usize,
+        shrimp_count: state.shrimp_pop.value().round() as usize,
+        plant_biomass: state.plant_biomass.value(),
+        water_volume: state.water_liters.value(),
+        rocks: state.rocks,
+        window_proximity: state.window_proximity,
+        soil_type: state.soil_type,
+        room_temp: config.environment.room_temperature.celsius(),
+        initial_temp: state.temperature.celsius(),
+        initial_humidity: state.humidity.percentage(),
+        initial_state: InitialStateSnapshot {
+            microbe_pop: state.microbe_pop.value(),
+            worm_pop: state.worm_pop.value(),
+            shrimp_pop: state.shrimp_pop.value(),
+            soil_ph: state.soil_ph.value(),
+            soil_ammonia: state.soil_ammonia.value(),
+            soil_nitrate: state.soil_nitrate.value(),
+            soil_moisture: state.soil_moisture.value(),
+            soil_aeration: state.soil_aeration.value(),
+            detritus: state.detritus.value(),
+            water_o2: state.water_o2.percentage(),
+            temperature: state.temperature.celsius(),
+            humidity: state.humidity.percentage(),
+        },
     }
 }
 
-fn analyze_results(results: Vec<SimulationResult>, mc_config: MonteCarloConfig) -> MonteCarloResults {
-    let total_runs = results.len();
-    let survivors: Vec<_> = results.iter().filter(|r| r.survived).collect();
-    let survival_rate = survivors.len() as f32 / total_runs as f32;
+fn analyze_results(results: Vec<SimulationResult>, master_seed: u64, setup: BatchSetup) -> MonteCarloResults {
+    // A run that hit a simulation error has no outcome, as the Kaplan–Meier curve censors it
+    let finished: Vec<_> = results.iter().filter(|r| r.error.is_none()).collect();
+    let failed_runs = results.len() - finished.len();
+    let finished_runs = finished.len().max(1);
+    let survivors: Vec<_> = finished.iter().copied().filter(|r| r.survived).collect();
+    let survival_rate = survivors.len() as f32 / finished_runs as f32;
     
     // Calculate average days survived
-    let total_days: usize = results.iter().map(|r| r.days_survived).sum();
-    let average_days_survived = total_days as f32 / total_runs as f32;
+    let total_days: usize = finished.iter().map(|r| r.days_survived).sum();
+    let average_days_survived = total_days as f32 / finished_runs as f32;
     
     // Create survival histogram
     let mut survival_histogram = HashMap::new();
-    for result in &results {
+    for result in &finished {
         *survival_histogram.entry(result.days_survived).or_insert(0) += 1;
     }
     
+    let survival_curve = KaplanMeier::from_results(&results);
+    
     // Analyze collapse reasons
     let mut collapse_reasons_frequency = HashMap::new();
     for result in &results {
@@ -292,10 +695,14 @@ fn analyze_results(results: Vec<SimulationResult>, mc_config: MonteCarloConfig)
     let survivor_analysis = analyze_survivors(&survivors);
     
     MonteCarloResults {
+        master_seed,
+        setup,
         results,
+        failed_runs,
         survival_rate,
         average_days_survived,
         survival_histogram,
+        survival_curve,
         collapse_reasons_frequency,
         survivor_analysis,
     }
@@ -306,37 +713,36 @@ fn analyze_survivors(survivors: &[&SimulationResult]) -> SurvivorAnalysis {
         return SurvivorAnalysis {
             optimal_difficulty: None,
             optimal_microbe_count: None,
-            optimal_environment: None,
             success_factors: Vec::new(),
         };
     }
 
-    // Find most common successful configurations
-    let mut difficulty_sum = 0.0;
-    let mut microbe_sum = 0;
-    let mut success_factors = Vec::new();
-
-    for survivor in survivors {
-        difficulty_sum += survivor.config_snapshot.difficulty;
-        microbe_sum += survivor.config_snapshot.microbe_count;
-    }
-
-    let optimal_difficulty = Some(difficulty_sum / survivors.len() as f32);
-    let optimal_microbe_count = Some(microbe_sum / survivors.len());
-
-    // Calculate success factors (simplified)
-    let avg_microbes = survivors.iter().map(|s| s.config_snapshot.microbe_count as f32).sum::<f32>() / survivors.len() as f32;
-    let avg_water = survivors.iter().map(|s| s.config_snapshot.water_volume).sum::<f32>() / survivors.len() as f32;
-    let avg_rocks = survivors.iter().map(|s| s.config_snapshot.rocks as f32).sum::<f32>() / survivors.len() as f32;
+    // Average the inputs the survivors were actually simulated with
+    let average = |input: fn(&ConfigSnapshot) -> f32| {
+        survivors.iter().map(|s| input(&s.config_snapshot)).sum::<f32>() / survivors.len() as f32
+    };
 
-    success_factors.push(("Average Microbes".to_string(), avg_microbes));
-    success_factors.push(("Average Water Volume".to_string(), avg_water));
-    success_factors.push(("Average Rocks".to_string(), avg_rocks));
+    let optimal_difficulty = Some(average(|c| c.difficulty));
+    let avg_microbes = average(|c| c.initial_state.microbe_pop);
+    let optimal_microbe_count = Some(avg_microbes.round() as usize);
+
+    let success_factors = vec![
+        ("Average Microbes".to_string(), avg_microbes),
+        ("Average Worms".to_string(), average(|c| c.initial_state.worm_pop)),
+        ("Average Shrimp".to_string(), average(|c| c.initial_state.shrimp_pop)),
+        ("Average Plant Biomass".to_string(), average(|c| c.plant_biomass)),
+        ("Average Water Volume".to_string(), average(|c| c.water_volume)),
+        ("Average Rocks".to_string(), average(|c| c.rocks as f32)),
+        ("Average Window Proximity".to_string(), average(|c| c.window_proximity as f32)),
+        ("Average Initial Soil pH".to_string(), average(|c| c.initial_state.soil_ph)),
+        ("Average Initial Soil Nitrogen".to_string(),
+         average(|c| c.initial_state.soil_ammonia + c.initial_state.soil_nitrate)),
+        ("Average Initial Temperature".to_string(), average(|c| c.initial_state.temperature)),
+    ];
 
     SurvivorAnalysis {
         optimal_difficulty,
         optimal_microbe_count,
-        optimal_environment: survivors.first().map(|s| s.config_snapshot.clone()),
         success_factors,
     }
 }
@@ -346,8 +752,15 @@ pub fn print_monte_carlo_results(results: &MonteCarloResults) {
     println!("==========================================");
     println!("📈 Overall Statistics:");
     println!("   Total simulations: {}", results.results.len());
+    if results.failed_runs > 0 {
+        println!("   ⚠️  {} runs stopped on a simulation error and are left out of the rates below", results.failed_runs);
+        if let Some(failed) = results.results.iter().find(|r| r.error.is_some()) {
+            println!("      e.g. run {}: {}", failed.run_id, failed.error.as_deref().unwrap_or_default());
+        }
+    }
     println!("   Survival rate: {:.1}%", results.survival_rate * 100.0);
     println!("   Average days survived: {:.1}", results.average_days_survived);
+    println!("   Master seed: {}", results.master_seed);
     
     println!("\n📊 Survival Histogram:");
     let mut histogram_entries: Vec<_> = results.survival_histogram.iter().collect();
@@ -359,6 +772,8 @@ pub fn print_monte_carlo_results(results: &MonteCarloResults) {
         println!("   Day {:2}: {:4} runs {}", days, count, bar);
     }
     
+    print_survival_curve(&results.survival_curve);
+    
     println!("\n💀 Collapse Reasons:");
     let mut collapse_entries: Vec<_> = results.collapse_reasons_frequency.iter().collect();
     collapse_entries.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
@@ -368,6 +783,17 @@ pub fn print_monte_carlo_results(results: &MonteCarloResults) {
         println!("   {:25}: {:4} ({:5.1}%)", reason, count, percentage);
     }
     
+    let mut earliest: Vec<_> = results.results.iter().filter(|r| !r.collapse_reasons.is_empty()).collect();
+    earliest.sort_by_key(|r| (r.days_survived, r.run_id));
+    if !earliest.is_empty() {
+        println!("\n🔍 Earliest collapses:");
+        for result in earliest.iter().take(5) {
+            println!("   Run {:5} (run seed {}): collapsed after {} days", result.run_id, result.seed, result.days_survived);
+        }
+        println!("   Replay one with: {}", results.setup.replay_command(results.master_seed, "<run_id>"));
+        println!("   or from the batch's JSON export: replay {} <run_id> --from <results.json>", results.master_seed);
+    }
+    
     println!("\n🎯 Survivor Analysis:");
     if let Some(difficulty) = results.survivor_analysis.optimal_difficulty {
         println!("   Optimal difficulty: {:.1}%", difficulty * 100.0);
@@ -394,4 +820,111 @@ pub fn print_monte_carlo_results(results: &MonteCarloResults) {
     if let Some(optimal_microbes) = results.survivor_analysis.optimal_microbe_count {
         println!("   • Try starting with ~{} microbes for better success", optimal_microbes);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_do_not_depend_on_thread_count() {
+        let mc_config = |threads| MonteCarloConfig {
+            num_runs: 12,
+            day_cap: 5,
+            show_progress: false,
+            seed: Some(42),
+            threads: Some(threads),
+            ..MonteCarloConfig::default()
+        };
+        let single = run_parallel(&mc_config(1), 42, 1).unwrap();
+        let parallel = run_parallel(&mc_config(4), 42, 4).unwrap();
+
+        assert_eq!(single.len(), 12);
+        for (a, b) in single.iter().zip(&parallel) {
+            assert_eq!(a.run_id, b.run_id);
+            assert_eq!(a.seed, derive_run_seed(42, a.run_id));
+            assert_eq!(a.days_survived, b.days_survived);
+            assert_eq!(a.final_state.plant_biomass, b.final_state.plant_biomass);
+            assert_eq!(a.final_state.air_o2, b.final_state.air_o2);
+        }
+    }
+
+    #[test]
+    fn test_run_jobs_reports_errors_and_panics() {
+        assert_eq!(run_jobs(20, 4, false, |job| Ok(job * 2)).unwrap(), (0..20).map(|job| job * 2).collect::<Vec<_>>());
+
+        let failed = run_jobs(20, 4, false, |job| match job {
+            7 => Err(EcosystemError::SimulationError { message: "bad setup".to_string() }),
+            _ => Ok(job),
+        });
+        assert!(failed.unwrap_err().to_string().contains("bad setup"));
+
+        let panicked = run_jobs(20, 4, false, |job| if job == 3 { panic!("worker blew up") } else { Ok(job) });
+        assert!(panicked.unwrap_err().to_string().contains("worker blew up"));
+    }
+
+    #[test]
+    fn test_snapshot_records_sampled_inputs() {
+        let species = OrganismRegistry::from_names(&["plants", "microbes", "shrimp"]).unwrap();
+        let mc_config = MonteCarloConfig { num_runs: 10, day_cap: 1, show_progress: false, species, ..MonteCarloConfig::default() };
+        let results = run_parallel(&mc_config, 7, 1).unwrap();
+        let difficulty = &mc_config.dimensions[0];
+        let (low, high) = (difficulty.low, difficulty.high);
+
+        for result in &results {
+            let snapshot = &result.config_snapshot;
+            assert!((low..=high).contains(&snapshot.difficulty));
+            assert_eq!(snapshot.initial_state.microbe_pop, snapshot.microbe_count as f32);
+            assert_eq!(snapshot.initial_state.temperature, snapshot.initial_temp);
+            assert_eq!(snapshot.worm_count, 0);
+        }
+        assert!(results.iter().any(|r| r.config_snapshot.difficulty != low));
+    }
+
+    #[test]
+    fn test_sampled_stocking_is_simulated() {
+        let run = |shrimp: &str| {
+            let mut dimensions = Dimension::defaults();
+            dimensions.retain(|d| d.name != "shrimp");
+            dimensions.push(shrimp.parse().unwrap());
+            let mc_config = MonteCarloConfig { num_runs: 1, day_cap: 1, show_progress: false, dimensions, ..MonteCarloConfig::default() };
+            run_parallel(&mc_config, 3, 1).unwrap().remove(0).config_snapshot.initial_state
+        };
+        let (few, many) = (run("shrimp=1:1"), run("shrimp=8:8"));
+        assert_eq!(few.shrimp_pop, 1.0);
+        assert_eq!(many.shrimp_pop, 8.0);
+        // Only the stocking differs; the seeded chemistry is the same
+        assert_eq!(few.soil_ph, many.soil_ph);
+    }
+
+    #[test]
+    fn test_failed_runs_are_left_out_of_the_rates_and_histogram() {
+        let mc_config = MonteCarloConfig { num_runs: 4, day_cap: 2, show_progress: false, ..MonteCarloConfig::default() };
+        let mut results = run_parallel(&mc_config, 9, 1).unwrap();
+        assert!(results.iter().all(|r| r.survived && r.error.is_none()));
+        results[0].survived = false;
+        results[0].days_survived = 1;
+        results[0].error = Some("Integration produced a non-finite state".to_string());
+
+        let analysis = analyze_results(results, 9, BatchSetup::of(&mc_config));
+        assert_eq!(analysis.failed_runs, 1);
+        assert_eq!(analysis.survival_rate, 1.0);
+        assert_eq!(analysis.survival_histogram.get(&1), None);
+        assert_eq!(analysis.survival_histogram[&2], 3);
+        assert_eq!(analysis.survival_curve.collapses, 0);
+        assert_eq!(analysis.survival_curve.steps.last().unwrap().survival, 1.0);
+    }
+
+    #[test]
+    fn test_fixed_bottle_keeps_its_stocking_and_climate() {
+        let mut base = V2Config::new();
+        base.organisms.shrimp.initial_count = 18;
+        base.environment.initial_temperature = crate::v2::types::Temperature::new(26.0).unwrap();
+        let mc_config = MonteCarloConfig { num_runs: 4, day_cap: 1, show_progress: false, base_config: Some(base), ..MonteCarloConfig::default() };
+        for result in run_parallel(&mc_config, 5, 2).unwrap() {
+            let initial = &result.config_snapshot.initial_state;
+            assert_eq!(initial.shrimp_pop, 18.0);
+            assert_eq!(initial.temperature, 26.0);
+        }
+    }
+}
@@ -0,0 +1,368 @@
+// v2/optimize.rs
+// Genetic search for the bottle setup most likely to survive uncertain starting conditions
+
+use crate::v2::config::{field_path, V2Config};
+use crate::v2::errors::EcosystemResult;
+use crate::v2::montecarlo::{default_threads, run_jobs, run_to_collapse};
+use crate::v2::sampling::{Dimension, inverse_normal_cdf};
+use crate::v2::state::EcosystemStateV2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+
+/// Inputs the keeper does not choose: difficulty is fixed for the search and the starting
+/// temperature and humidity are the room's
+const UNCONTROLLED: [&str; 3] = ["difficulty", "temperature", "humidity"];
+
+#[derive(Debug, Clone)]
+pub struct OptimizerConfig {
+    pub difficulty: f32,
+    /// Candidates per generation
+    pub population: usize,
+    pub generations: usize,
+    /// Randomized starts every candidate is run from in each generation
+    pub runs_per_candidate: usize,
+    /// Fresh starts the finalists and the baseline are scored on after the search
+    pub validation_runs: usize,
+    /// Best candidates carried unchanged into the next generation
+    pub elites: usize,
+    /// Standard deviation of a mutation, as a fraction of the setting's range
+    pub mutation: f64,
+    pub finalists: usize,
+    pub day_cap: usize,
+    /// Ranges of the settings being searched
+    pub dimensions: Vec<Dimension>,
+    pub seed: Option<u64>,
+    pub threads: Option<usize>,
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        Self {
+            difficulty: 0.5,
+            population: 24,
+            generations: 15,
+            runs_per_candidate: 32,
+            validation_runs: 256,
+            elites: 2,
+            mutation: 0.1,
+            finalists: 5,
+            day_cap: 30,
+            dimensions: Dimension::defaults(),
+            seed: None,
+            threads: None,
+        }
+    }
+}
+
+/// Outcomes of one setup over a set of randomized starts
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    pub days_survived: Vec<usize>,
+    pub survivors: usize,
+}
+
+impl Evaluation {
+    fn record(&mut self, survived: bool, days: usize) {
+        self.days_survived.push(days);
+        self.survivors += survived as usize;
+    }
+
+    fn merge(&mut self, other: Evaluation) {
+        self.days_survived.extend(other.days_survived);
+        self.survivors += other.survivors;
+    }
+
+    pub fn runs(&self) -> usize {
+        self.days_survived.len()
+    }
+
+    pub fn survival_rate(&self) -> f64 {
+        self.survivors as f64 / self.runs().max(1) as f64
+    }
+
+    pub fn mean_days(&self) -> f64 {
+        self.days_survived.iter().sum::<usize>() as f64 / self.runs().max(1) as f64
+    }
+
+    /// 95% Wilson score interval of the survival rate
+    pub fn survival_interval(&self) -> (f64, f64) {
+        let n = self.runs() as f64;
+        if n == 0.0 {
+            return (0.0, 1.0);
+        }
+        let (p, z2) = (self.survival_rate(), 1.96f64 * 1.96);
+        let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half = 1.96 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        ((centre - half).max(0.0), (centre + half).min(1.0))
+    }
+
+    /// Days survived that all but the unluckiest `fraction` of runs reached
+    pub fn days_quantile(&self, fraction: f64) -> usize {
+        let mut days = self.days_survived.clone();
+        days.sort_unstable();
+        days.get(((days.len() as f64 * fraction) as usize).min(days.len().saturating_sub(1))).copied().unwrap_or(0)
+    }
+
+    /// Survival first, then days survived
+    fn compare(&self, other: &Evaluation) -> Ordering {
+        self.survival_rate()
+            .total_cmp(&other.survival_rate())
+            .then(self.mean_days().total_cmp(&other.mean_days()))
+    }
+}
+
+/// A setup as a point in the unit cube, one coordinate per searched setting
+#[derive(Debug, Clone)]
+struct Candidate {
+    genes: Vec<f64>,
+    /// Every run since the candidate was created; elites keep adding to it
+    evaluation: Evaluation,
+}
+
+impl Candidate {
+    fn new(genes: Vec<f64>) -> Self {
+        Self { genes, evaluation: Evaluation::default() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Finalist {
+    pub config: V2Config,
+    /// Every run during the search; flattered by having been selected on it
+    pub search: Evaluation,
+    /// Runs from starts no candidate was selected on
+    pub validation: Evaluation,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationSummary {
+    pub best_survival: f64,
+    pub mean_survival: f64,
+}
+
+#[derive(Debug)]
+pub struct OptimizerResults {
+    pub difficulty: f32,
+    pub seed: u64,
+    pub history: Vec<GenerationSummary>,
+    /// Best validated survival first
+    pub finalists: Vec<Finalist>,
+    /// The difficulty's default setup on the same validation starts
+    pub baseline: Evaluation,
+    pub evaluations: usize,
+}
+
+pub fn run_optimizer(opt_config: &OptimizerConfig) -> EcosystemResult<OptimizerResults> {
+    let seed = opt_config.seed.unwrap_or_else(|| StdRng::from_entropy().r#gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let dimensions: Vec<Dimension> = opt_config
+        .dimensions
+        .iter()
+        .filter(|d| !UNCONTROLLED.contains(&d.name))
+        .cloned()
+        .collect();
+    // Settings map monotonically onto their ranges, so valid corners mean every candidate is valid
+    for corner in [0.0, 1.0] {
+        build_config(opt_config.difficulty, &dimensions, &vec![corner; dimensions.len()])?;
+    }
+    let threads = default_threads(opt_config.threads);
+    let population_size = opt_config.population.max(2);
+    let runs = opt_config.runs_per_candidate.max(1);
+
+    println!("🧬 Searching for the most robust bottle at {:.0}% difficulty", opt_config.difficulty * 100.0);
+    println!("   Settings: {}", dimensions.iter().map(|d| d.name).collect::<Vec<_>>().join(", "));
+    println!("   {} candidates × {} generations, {} randomized starts each ({} days)",
+             population_size, opt_config.generations, runs, opt_config.day_cap);
+    println!("   Seed: {} ({} threads)", seed, threads);
+
+    let mut population: Vec<Candidate> = (0..population_size)
+        .map(|_| Candidate::new((0..dimensions.len()).map(|_| rng.r#gen()).collect()))
+        .collect();
+    let mut history = Vec::with_capacity(opt_config.generations);
+    let mut evaluations = 0;
+
+    for generation in 0..opt_config.generations.max(1) {
+        // Every candidate in a generation faces the same starts, so they are compared like for like
+        let seeds: Vec<u64> = (0..runs).map(|_| rng.r#gen()).collect();
+        let configs = population
+            .iter()
+            .map(|c| build_config(opt_config.difficulty, &dimensions, &c.genes))
+            .collect::<EcosystemResult<Vec<_>>>()?;
+        for (candidate, evaluation) in population.iter_mut().zip(evaluate(&configs, &seeds, opt_config.day_cap, threads)?) {
+            candidate.evaluation.merge(evaluation);
+        }
+        evaluations += configs.len() * runs;
+
+        population.sort_by(|a, b| b.evaluation.compare(&a.evaluation));
+        let summary = GenerationSummary {
+            best_survival: population[0].evaluation.survival_rate(),
+            mean_survival: population.iter().map(|c| c.evaluation.survival_rate()).sum::<f64>() / population.len() as f64,
+        };
+        println!("   Generation {:>3}/{}: best {:>5.1}%, mean {:>5.1}%", generation + 1, opt_config.generations.max(1),
+                 summary.best_survival * 100.0, summary.mean_survival * 100.0);
+        history.push(summary);
+
+        if generation + 1 < opt_config.generations {
+            population = next_generation(&population, opt_config.elites, opt_config.mutation, &mut rng);
+        }
+    }
+
+    // Scores from the search favour whoever got lucky starts, so the finalists are rescored on new ones
+    let finalists: Vec<Candidate> = population.into_iter().take(opt_config.finalists.max(1)).collect();
+    let baseline = V2Config::with_difficulty(opt_config.difficulty)?;
+    let mut configs = finalists
+        .iter()
+        .map(|c| build_config(opt_config.difficulty, &dimensions, &c.genes))
+        .collect::<EcosystemResult<Vec<_>>>()?;
+    configs.push(baseline);
+    let seeds: Vec<u64> = (0..opt_config.validation_runs.max(1)).map(|_| rng.r#gen()).collect();
+    let mut validation = evaluate(&configs, &seeds, opt_config.day_cap, threads)?;
+    evaluations += configs.len() * seeds.len();
+    let baseline = validation.pop().unwrap_or_default();
+
+    let mut finalists: Vec<Finalist> = finalists
+        .into_iter()
+        .zip(configs)
+        .zip(validation)
+        .map(|((candidate, config), validation)| Finalist {
+            config,
+            search: candidate.evaluation,
+            validation,
+        })
+        .collect();
+    finalists.sort_by(|a, b| b.validation.compare(&a.validation));
+
+    Ok(OptimizerResults { difficulty: opt_config.difficulty, seed, history, finalists, baseline, evaluations })
+}
+
+/// The difficulty's configuration with every searched setting taken from `genes`
+fn build_config(difficulty: f32, dimensions: &[Dimension], genes: &[f64]) -> EcosystemResult<V2Config> {
+    let mut config = V2Config::with_difficulty(difficulty)?;
+    for (dimension, &unit) in dimensions.iter().zip(genes) {
+        config.set_field(field_path(dimension.name), dimension.value(unit))?;
+    }
+    Ok(config)
+}
+
+/// Run every configuration from every seed, spread over worker threads. A setup that can't
+/// be built is an error, not a bottle that died on day 0
+fn evaluate(configs: &[V2Config], seeds: &[u64], day_cap: usize, threads: usize) -> EcosystemResult<Vec<Evaluation>> {
+    let outcomes = run_jobs(configs.len() * seeds.len(), threads, false, |job| {
+        let config = &configs[job / seeds.len()];
+        let mut state = EcosystemStateV2::new_with_seed(config, seeds[job % seeds.len()])?;
+        Ok(run_to_collapse(config, &mut state, day_cap, |_, _, _| {}))
+    })?;
+
+    let mut evaluations = vec![Evaluation::default(); configs.len()];
+    for (job, outcome) in outcomes.into_iter().enumerate() {
+        evaluations[job / seeds.len()].record(outcome.survived, outcome.days_survived);
+    }
+    Ok(evaluations)
+}
+
+/// Elites carry over with their runs so far; the rest are bred from tournament winners
+fn next_generation(ranked: &[Candidate], elites: usize, mutation: f64, rng: &mut StdRng) -> Vec<Candidate> {
+    let mut next: Vec<Candidate> = ranked.iter().take(elites).cloned().collect();
+    while next.len() < ranked.len() {
+        let genes = crossover(&tournament(ranked, rng).genes, &tournament(ranked, rng).genes, mutation, rng);
+        next.push(Candidate::new(genes));
+    }
+    next
+}
+
+/// Best of three drawn at random; `ranked` is sorted best first
+fn tournament<'a>(ranked: &'a [Candidate], rng: &mut StdRng) -> &'a Candidate {
+    let winner = (0..3).map(|_| rng.gen_range(0..ranked.len())).min().unwrap_or(0);
+    &ranked[winner]
+}
+
+/// Blend crossover (BLX-0.5), then a Gaussian mutation of about one setting per child
+fn crossover(a: &[f64], b: &[f64], mutation: f64, rng: &mut StdRng) -> Vec<f64> {
+    let rate = 1.0 / a.len().max(1) as f64;
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| {
+            let (low, high) = (x.min(y), x.max(y));
+            let spread = 0.5 * (high - low);
+            let mut gene = rng.gen_range(low - spread..=high + spread);
+            if rng.r#gen::<f64>() < rate {
+                gene += mutation * inverse_normal_cdf(rng.gen_range(1e-9..1.0 - 1e-9));
+            }
+            gene.clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+fn format_evaluation(evaluation: &Evaluation) -> String {
+    let (low, high) = evaluation.survival_interval();
+    format!("{:.1}% survival (95% CI {:.1}–{:.1}%), mean {:.1} days",
+            evaluation.survival_rate() * 100.0, low * 100.0, high * 100.0, evaluation.mean_days())
+}
+
+pub fn print_optimizer_results(results: &OptimizerResults) {
+    let validation_runs = results.baseline.runs();
+    println!("\n🏆 MOST ROBUST BOTTLES AT {:.0}% DIFFICULTY ({} runs, seed {})",
+             results.difficulty * 100.0, results.evaluations, results.seed);
+    println!("==========================================");
+    println!("Scored on {} fresh randomized starts each:", validation_runs);
+    println!("   Default setup: {}", format_evaluation(&results.baseline));
+    let progress: Vec<String> = results.history.iter().map(|g| format!("{:.0}%", g.best_survival * 100.0)).collect();
+    println!("   Best survival by generation: {}", progress.join(" → "));
+
+    for (rank, finalist) in results.finalists.iter().enumerate() {
+        println!("\n#{} {}", rank + 1, format_evaluation(&finalist.validation));
+        println!("   90% of starts lasted at least {} days; {:.1}% survival during the search ({} runs)",
+                 finalist.validation.days_quantile(0.1), finalist.search.survival_rate() * 100.0, finalist.search.runs());
+        let (environment, organisms) = (&finalist.config.environment, &finalist.config.organisms);
+        println!("   Bottle: {:.2} L of water, {} rocks, {} soil, window proximity {}, room at {:.1}°C",
+                 environment.water_volume.value(), environment.rocks, environment.soil_type,
+                 environment.window_proximity, environment.room_temperature.celsius());
+        println!("   Stocking: {} microbes, {} worms, {} shrimp, {:.2} plant biomass",
+                 organisms.microbes.initial_count, organisms.worms.initial_count,
+                 organisms.shrimp.initial_count, organisms.plants.initial_biomass);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossover_stays_in_unit_cube() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let child = crossover(&[0.0, 0.95, 0.5], &[0.1, 1.0, 0.5], 0.5, &mut rng);
+            assert!(child.iter().all(|g| (0.0..=1.0).contains(g)));
+        }
+    }
+
+    #[test]
+    fn test_optimizer_reports_validated_finalists() {
+        let opt_config = OptimizerConfig {
+            population: 4,
+            generations: 2,
+            runs_per_candidate: 2,
+            validation_runs: 3,
+            finalists: 2,
+            day_cap: 2,
+            seed: Some(9),
+            threads: Some(2),
+            ..OptimizerConfig::default()
+        };
+        let results = run_optimizer(&opt_config).unwrap();
+
+        assert_eq!(results.history.len(), 2);
+        assert_eq!(results.finalists.len(), 2);
+        assert_eq!(results.baseline.runs(), 3);
+        for finalist in &results.finalists {
+            assert_eq!(finalist.validation.runs(), 3);
+            assert!(finalist.search.runs() >= 2);
+            let (low, high) = finalist.validation.survival_interval();
+            assert!(low <= finalist.validation.survival_rate() && finalist.validation.survival_rate() <= high);
+            assert!(finalist.config.environment.rocks <= 5);
+            assert!((1..=15).contains(&finalist.config.organisms.worms.initial_count));
+        }
+        assert!(results.finalists[0].validation.compare(&results.finalists[1].validation) != Ordering::Less);
+    }
+}
@@ -1,71 +1,161 @@
 // v2/organisms/microbes.rs
 // Microbe simulation logic
 
+use crate::v2::ledger::{air_co2_as_organic, soil_nitrogen_as_air_n2, MICROBE_BIOMASS};
 use crate::v2::config::parameters::SimulationParameters;
 use crate::v2::state::EcosystemStateV2;
 use crate::v2::environmental::*;
 use crate::v2::errors::EcosystemResult;
+use crate::v2::integrator::Derivatives;
+use crate::v2::organisms::{mineralise, surviving, Organism, TrophicRole};
+use crate::v2::errors::CollapseReason;
 use crate::v2::organisms::plants::{GasOps, BiomassOps};
+use crate::v2::soil::aeration_efficiency;
 
-/// Update microbe population and associated processes
-pub fn update_microbes(
-    state: &mut EcosystemStateV2,
+/// Decomposers and nitrogen cyclers living in the soil
+pub struct Microbes;
+
+impl Organism for Microbes {
+    fn name(&self) -> &'static str {
+        "microbes"
+    }
+
+    fn role(&self) -> TrophicRole {
+        TrophicRole::Decomposer
+    }
+
+    fn rates(&self, state: &EcosystemStateV2, params: &SimulationParameters, _is_day: bool, rates: &mut Derivatives) {
+        microbe_rates(state, params, rates);
+    }
+
+    fn stress_rates(&self, state: &EcosystemStateV2, rates: &mut Derivatives) {
+        let penalty = ph_penalty_factor(state.soil_ph) + oxygen_penalty_factor(state.air_o2);
+        let died = 0.15 * penalty * surviving(state.microbe_pop);
+        rates.microbe_pop -= died;
+        rates.detritus += died * MICROBE_BIOMASS;
+    }
+
+    fn is_collapsed(&self, state: &EcosystemStateV2) -> bool {
+        are_microbes_collapsed(state)
+    }
+
+    fn collapse_reason(&self) -> CollapseReason {
+        CollapseReason::MicrobesDied
+    }
+
+    fn collapse_risk(&self, state: &EcosystemStateV2) -> f32 {
+        if state.microbe_pop.value() < 100.0 { 0.9 } else { 0.0 }
+    }
+}
+
+/// Add the rates of microbial nitrogen cycling, growth and respiration
+pub fn microbe_rates(
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     // Nitrogen fixation
-    nitrogen_fixation(state, params, dt)?;
+    nitrogen_fixation(state, params, rates);
+    
+    // Nitrification of ammonia to nitrite and nitrate
+    nitrification(state, params, rates);
+    
+    // Denitrification of nitrate back to N2 in airless soil
+    denitrification(state, params, rates);
     
     // Microbe population growth and death
-    microbe_population_dynamics(state, params, dt)?;
+    microbe_population_dynamics(state, params, rates);
     
     // Microbe respiration
-    microbe_respiration(state, params, dt)?;
-    
-    Ok(())
+    microbe_respiration(state, params, rates);
 }
 
-/// Calculate nitrogen fixation by microbes
+/// Atmospheric nitrogen fixed into soil ammonia
 fn nitrogen_fixation(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     let oxygen_factor = oxygen_efficiency(state.air_o2);
     let moisture_factor = moisture_efficiency(state.soil_moisture);
+    // Fixers switch off nitrogenase when mineral nitrogen is already plentiful
+    let available = state.soil_ammonia.value() + state.soil_nitrate.value();
+    let inhibition_factor = 1.0 / (1.0 + available / params.nitrogen.fixation_inhibition);
     
     let fixation_rate = params.microbial.nitrogen_fixation_rate
         * state.microbe_pop.value()
         * oxygen_factor
-        * moisture_factor;
+        * moisture_factor
+        * inhibition_factor;
     
-    let nitrogen_fixed = fixation_rate * dt;
-    state.soil_nitrogen = state.soil_nitrogen.add(nitrogen_fixed)?;
+    // Fixed nitrogen is drawn from the air and enters the soil as ammonia
+    rates.soil_ammonia += fixation_rate;
+    rates.air_n2 -= soil_nitrogen_as_air_n2(state, fixation_rate);
+}
+
+/// Share of the microbial community doing nitrification, limited by oxygen, temperature and pH
+fn nitrifier_activity(state: &EcosystemStateV2, params: &SimulationParameters) -> f32 {
+    let population = state.microbe_pop.value();
+    let saturation = population / (population + params.nitrogen.nitrifier_half_saturation);
     
-    // Consume some atmospheric nitrogen
-    let n2_consumed = nitrogen_fixed * 0.1; // Small amount from atmosphere
-    let new_air_n2 = (state.air_n2.value() - n2_consumed).max(0.0);
-    state.air_n2 = crate::v2::types::Nitrogen::new(new_air_n2)?;
+    saturation
+        * oxygen_efficiency(state.air_o2)
+        * aeration_efficiency(state.soil_aeration)
+        * temperature_efficiency(state.temperature)
+        * ph_efficiency(state.soil_ph)
+}
+
+/// Oxidise ammonia to nitrite, then nitrite to nitrate
+fn nitrification(
+    state: &EcosystemStateV2,
+    params: &SimulationParameters,
+    rates: &mut Derivatives,
+) {
+    let activity = nitrifier_activity(state, params);
     
-    Ok(())
+    let ammonia_oxidised = params.nitrogen.nitritation_rate * activity * state.soil_ammonia.value();
+    let nitrite_oxidised = params.nitrogen.nitratation_rate * activity * state.soil_nitrite.value();
+    
+    rates.soil_ammonia -= ammonia_oxidised;
+    rates.soil_nitrite += ammonia_oxidised - nitrite_oxidised;
+    rates.soil_nitrate += nitrite_oxidised;
 }
 
-/// Calculate microbe population growth and death
+/// Reduce nitrate back to atmospheric N2 where the soil has gone airless
+fn denitrification(
+    state: &EcosystemStateV2,
+    params: &SimulationParameters,
+    rates: &mut Derivatives,
+) {
+    let anoxia = 1.0 - state.soil_aeration.value().min(1.0);
+    let denitrified = state.soil_nitrate.value()
+        * params.nitrogen.denitrification_rate
+        * anoxia;
+    
+    rates.soil_nitrate -= denitrified;
+    rates.air_n2 += soil_nitrogen_as_air_n2(state, denitrified);
+}
+
+/// Microbe population growth and death
 fn microbe_population_dynamics(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let nutrient_factor = nutrient_efficiency(state.soil_nitrogen);
+    rates: &mut Derivatives,
+) {
+    let nutrient_factor = nutrient_efficiency(available_nitrogen(state));
     let moisture_factor = moisture_efficiency(state.soil_moisture);
     let temperature_factor = temperature_efficiency(state.temperature);
+    let aeration_factor = aeration_efficiency(state.soil_aeration);
     
-    // Growth
+    // Growth, built from the detritus they feed on
     let growth_rate = params.microbial.growth_rate
+        * state.soil_type.microbial_growth_modifier()
         * state.microbe_pop.value()
         * nutrient_factor
         * moisture_factor
-        * temperature_factor;
+        * temperature_factor
+        * aeration_factor
+        * food_limitation(state.detritus);
     
     // Death
     let ph_factor = ph_efficiency(state.soil_ph);
@@ -75,29 +165,25 @@ fn microbe_population_dynamics(
         * (1.0 - ph_factor)  // Higher death when pH is not optimal
         * (1.0 - oxygen_factor); // Higher death when oxygen is low
     
-    let net_growth = (growth_rate - death_rate) * dt;
-    let new_population = (state.microbe_pop.value() + net_growth).max(0.0);
-    
-    state.microbe_pop = crate::v2::types::Population::new(new_population)?;
-    
-    Ok(())
+    rates.microbe_pop += growth_rate - death_rate;
+    rates.detritus += (death_rate - growth_rate) * MICROBE_BIOMASS;
 }
 
-/// Calculate microbe respiration
+/// Detritus respired by the microbes, using oxygen and releasing CO2 and ammonia
 fn microbe_respiration(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let respiration_rate = params.microbial.respiration_rate * state.microbe_pop.value();
-    
-    let oxygen_consumption = respiration_rate * dt;
-    let co2_production = oxygen_consumption * params.microbial.respiration_co2_ratio;
-    
-    state.air_o2 = state.air_o2.subtract(oxygen_consumption)?;
-    state.air_co2 = state.air_co2.add(co2_production)?;
+    rates: &mut Derivatives,
+) {
+    let respiration_rate = params.microbial.respiration_rate
+        * params.microbial.respiration_co2_ratio
+        * state.microbe_pop.value()
+        * aerobic_limitation(state.air_o2)
+        * food_limitation(state.detritus);
     
-    Ok(())
+    let respired = air_co2_as_organic(state, respiration_rate);
+    rates.detritus -= respired;
+    mineralise(state, respired, rates);
 }
 
 /// Check if microbes have collapsed
@@ -108,7 +194,7 @@ pub fn are_microbes_collapsed(state: &EcosystemStateV2) -> bool {
 /// Get current microbe health status
 pub fn microbe_health_status(state: &EcosystemStateV2) -> MicrobeHealthStatus {
     let population = state.microbe_pop.value();
-    let nutrient_factor = nutrient_efficiency(state.soil_nitrogen);
+    let nutrient_factor = nutrient_efficiency(available_nitrogen(state));
     let moisture_factor = moisture_efficiency(state.soil_moisture);
     let temperature_factor = temperature_efficiency(state.temperature);
     let ph_factor = ph_efficiency(state.soil_ph);
@@ -127,6 +213,11 @@ pub fn microbe_health_status(state: &EcosystemStateV2) -> MicrobeHealthStatus {
     }
 }
 
+/// Microbes feed on both ammonium and nitrate
+fn available_nitrogen(state: &EcosystemStateV2) -> crate::v2::types::Nitrogen {
+    state.soil_ammonia.add(state.soil_nitrate.value()).unwrap_or(state.soil_ammonia)
+}
+
 fn calculate_nitrogen_fixation_rate(state: &EcosystemStateV2) -> f32 {
     let oxygen_factor = oxygen_efficiency(state.air_o2);
     let moisture_factor = moisture_efficiency(state.soil_moisture);
@@ -179,4 +270,37 @@ impl PopulationOps for crate::v2::types::Population {
         let new_value = (self.value() * factor).max(0.01);
         Ok(crate::v2::types::Population::new(new_value)?)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::config::V2Config;
+    use crate::v2::types::Nitrogen;
+
+    #[test]
+    fn test_ammonia_is_nitrified_through_nitrite() {
+        let config = V2Config::new();
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        state.soil_ammonia = Nitrogen::new(2.0).unwrap();
+        state.soil_nitrite = Nitrogen::new(0.0).unwrap();
+        state.soil_nitrate = Nitrogen::new(0.0).unwrap();
+        let total = state.soil_nitrogen();
+
+        let mut peak_nitrite: f32 = 0.0;
+        for half_day in 0..40 {
+            let mut rates = Derivatives::default();
+            nitrification(&state, &config.parameters, &mut rates);
+            if half_day == 0 {
+                // Nothing reaches nitrate until there is nitrite to oxidise
+                assert!(rates.soil_nitrite > 0.0);
+                assert_eq!(rates.soil_nitrate, 0.0);
+            }
+            Derivatives::from_state(&state).scaled_add(1.0, &rates).write_to(&mut state).unwrap();
+            peak_nitrite = peak_nitrite.max(state.soil_nitrite.value());
+        }
+
+        assert!(peak_nitrite > 2.0 * state.soil_nitrite.value());
+        assert!(state.soil_nitrate.value() > 0.9 * total);
+        assert!((state.soil_nitrogen() - total).abs() < 1e-4);
+    }
+}
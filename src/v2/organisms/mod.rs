@@ -6,76 +6,79 @@ pub mod microbes;
 pub mod worms;
 pub mod shrimp;
 
+pub mod registry;
+
+pub use registry::OrganismRegistry;
+
 use crate::v2::config::parameters::SimulationParameters;
 use crate::v2::state::EcosystemStateV2;
-use crate::v2::environmental::*;
-use crate::v2::errors::EcosystemResult;
+use crate::v2::errors::CollapseReason;
+use crate::v2::integrator::Derivatives;
+use crate::v2::ledger::{organic_as_air_co2, organic_nitrogen};
+use crate::v2::types::Population;
 
-/// Trait for organisms that can be simulated
-pub trait Organism {
-    fn update(&mut self, state: &EcosystemStateV2, params: &SimulationParameters, is_day: bool, dt: f32) -> EcosystemResult<()>;
-    fn is_collapsed(&self) -> bool;
-    fn population_size(&self) -> f32;
+/// Part a species plays in the bottle's food web
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrophicRole {
+    /// Fixes carbon from light
+    Producer,
+    /// Breaks dead matter back down into nutrients
+    Decomposer,
+    /// Feeds on what the others produce
+    Consumer,
 }
 
-/// Update all organisms in the ecosystem
-pub fn update_all_organisms(
-    state: &mut EcosystemStateV2,
-    params: &SimulationParameters,
-    is_day: bool,
-    dt: f32,
-) -> EcosystemResult<()> {
-    // Update plants
-    plants::update_plants(state, params, is_day, dt)?;
-    
-    // Update microbes
-    microbes::update_microbes(state, params, dt)?;
-    
-    // Update worms
-    worms::update_worms(state, params, dt)?;
-    
-    // Update shrimp
-    shrimp::update_shrimp(state, params, dt)?;
-    
-    Ok(())
+/// A species living in the bottle. Implementations read the state and add their
+/// contribution to the rates; the integrator is the only thing that moves the state
+pub trait Organism: Send + Sync {
+    /// Unique name the registry knows the species by
+    fn name(&self) -> &'static str;
+
+    fn role(&self) -> TrophicRole;
+
+    /// Growth, feeding, respiration and everything else the species does
+    fn rates(&self, state: &EcosystemStateV2, params: &SimulationParameters, is_day: bool, rates: &mut Derivatives);
+
+    /// Die-off caused by poor pH, low oxygen and other environmental stress
+    fn stress_rates(&self, _state: &EcosystemStateV2, _rates: &mut Derivatives) {}
+
+    fn is_collapsed(&self, state: &EcosystemStateV2) -> bool;
+    fn collapse_reason(&self) -> CollapseReason;
+
+    /// 0.0 = thriving, 1.0 = about to die out
+    fn collapse_risk(&self, state: &EcosystemStateV2) -> f32;
 }
 
-/// Calculate environmental penalties and apply them to populations
-pub fn apply_environmental_penalties(state: &mut EcosystemStateV2) -> EcosystemResult<()> {
-    use crate::v2::types::*;
-    
-    // pH penalties
-    let ph_penalty = ph_penalty_factor(state.soil_ph);
-    if ph_penalty > 0.0 {
-        let new_plant_biomass = (state.plant_biomass.value() * (1.0 - 0.10 * ph_penalty)).max(0.0);
-        state.plant_biomass = Biomass::new(new_plant_biomass)?;
-        
-        let new_microbe_pop = (state.microbe_pop.value() * (1.0 - 0.15 * ph_penalty)).max(0.01);
-        state.microbe_pop = Population::new(new_microbe_pop)?;
-        
-        let new_shrimp_pop = (state.shrimp_pop.value() * (1.0 - 0.20 * ph_penalty)).max(0.01);
-        state.shrimp_pop = Population::new(new_shrimp_pop)?;
+/// Add the rates of every registered organism; all of them see the same state,
+/// so registry order doesn't matter
+pub fn organism_rates(
+    state: &EcosystemStateV2,
+    params: &SimulationParameters,
+    is_day: bool,
+    rates: &mut Derivatives,
+) {
+    for organism in state.species.iter() {
+        organism.rates(state, params, is_day, rates);
     }
+}
 
-    // Air oxygen penalties
-    let oxygen_penalty = oxygen_penalty_factor(state.air_o2);
-    if oxygen_penalty > 0.0 {
-        let new_plant_biomass = (state.plant_biomass.value() * (1.0 - 0.10 * oxygen_penalty)).max(0.0);
-        state.plant_biomass = Biomass::new(new_plant_biomass)?;
-        
-        let new_microbe_pop = (state.microbe_pop.value() * (1.0 - 0.15 * oxygen_penalty)).max(0.01);
-        state.microbe_pop = Population::new(new_microbe_pop)?;
-        
-        let new_worm_pop = (state.worm_pop.value() * (1.0 - 0.20 * oxygen_penalty)).max(0.01);
-        state.worm_pop = Population::new(new_worm_pop)?;
+/// Add the die-off of every registered organism caused by poor pH and low oxygen
+pub fn environmental_penalty_rates(state: &EcosystemStateV2, rates: &mut Derivatives) {
+    for organism in state.species.iter() {
+        organism.stress_rates(state, rates);
     }
+}
 
-    // Water oxygen penalties for shrimp
-    let water_oxygen_penalty = water_oxygen_penalty_factor(state.water_o2);
-    if water_oxygen_penalty > 0.0 {
-        let new_shrimp_pop = (state.shrimp_pop.value() * (1.0 - 0.20 * water_oxygen_penalty)).max(0.01);
-        state.shrimp_pop = Population::new(new_shrimp_pop)?;
-    }
+/// Populations already at the collapse threshold have nothing left to lose
+pub(crate) fn surviving(population: Population) -> f32 {
+    if population.is_collapsed() { 0.0 } else { population.value() }
+}
 
-    Ok(())
-}
\ No newline at end of file
+/// Respire `amount` of organic matter: each CO2 given off takes one O2 from the air and
+/// its nitrogen is released as ammonia. The caller takes `amount` from where it came
+pub(crate) fn mineralise(state: &EcosystemStateV2, amount: f32, rates: &mut Derivatives) {
+    let carbon = organic_as_air_co2(state, amount);
+    rates.air_o2 -= carbon;
+    rates.air_co2 += carbon;
+    rates.soil_ammonia += organic_nitrogen(amount);
+}
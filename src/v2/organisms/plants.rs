@@ -5,111 +5,108 @@ use crate::v2::config::parameters::SimulationParameters;
 use crate::v2::state::EcosystemStateV2;
 use crate::v2::environmental::*;
 use crate::v2::errors::EcosystemResult;
+use crate::v2::integrator::Derivatives;
+use crate::v2::ledger::{air_co2_as_organic, organic_nitrogen};
+use crate::v2::organisms::{mineralise, Organism, TrophicRole};
+use crate::v2::errors::CollapseReason;
 
-/// Update plant biomass and associated processes
-pub fn update_plants(
-    state: &mut EcosystemStateV2,
+/// Producers: fix carbon by day and respire at night
+pub struct Plants;
+
+impl Organism for Plants {
+    fn name(&self) -> &'static str {
+        "plants"
+    }
+
+    fn role(&self) -> TrophicRole {
+        TrophicRole::Producer
+    }
+
+    fn rates(&self, state: &EcosystemStateV2, params: &SimulationParameters, is_day: bool, rates: &mut Derivatives) {
+        plant_rates(state, params, is_day, rates);
+    }
+
+    fn stress_rates(&self, state: &EcosystemStateV2, rates: &mut Derivatives) {
+        let penalty = ph_penalty_factor(state.soil_ph) + oxygen_penalty_factor(state.air_o2);
+        let died = 0.10 * penalty * state.plant_biomass.value();
+        rates.plant_biomass -= died;
+        rates.detritus += died;
+    }
+
+    fn is_collapsed(&self, state: &EcosystemStateV2) -> bool {
+        are_plants_collapsed(state)
+    }
+
+    fn collapse_reason(&self) -> CollapseReason {
+        CollapseReason::PlantsDied
+    }
+
+    fn collapse_risk(&self, state: &EcosystemStateV2) -> f32 {
+        if state.plant_biomass.value() < 0.1 { 0.8 } else { 0.0 }
+    }
+}
+
+/// Add the rates of plant growth and gas exchange
+pub fn plant_rates(
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
     is_day: bool,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     if is_day {
         // Photosynthesis during day
-        photosynthesis(state, params, dt)?;
-        
-        // Plant growth
-        plant_growth(state, params, dt)?;
-        
-        // Nitrogen uptake
-        nitrogen_uptake(state, params, dt)?;
+        photosynthesis(state, params, rates);
     } else {
         // Respiration at night
-        plant_respiration(state, params, dt)?;
+        plant_respiration(state, params, rates);
     }
-    
-    Ok(())
 }
 
-/// Calculate photosynthesis rate and update oxygen/CO2
+/// CO2 fixed into new growth, releasing its oxygen and taking up nitrate for it
 fn photosynthesis(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     let light_level = state.light_level();
+    let nutrient_factor = nutrient_efficiency(state.soil_nitrate);
     let humidity_factor = humidity_efficiency(state.humidity);
+    let temperature_factor = temperature_efficiency(state.temperature);
+    let competition_factor = competition_factor(state.plant_biomass);
     let co2_factor = (state.air_co2.value() / 0.04).min(2.0); // CO2 can enhance photosynthesis
     
-    let photosynthesis_rate = params.photosynthesis.base_rate 
+    let fixed = params.photosynthesis.base_rate
+        * params.photosynthesis.co2_efficiency
         * state.plant_biomass.value()
         * light_efficiency(light_level)
+        * nutrient_factor
         * humidity_factor
+        * temperature_factor
+        * competition_factor
         * co2_factor;
     
-    let oxygen_production = photosynthesis_rate * dt;
-    let co2_consumption = oxygen_production * params.photosynthesis.co2_efficiency;
-    
-    state.air_o2 = state.air_o2.add(oxygen_production)?;
-    state.air_co2 = state.air_co2.subtract(co2_consumption)?;
+    rates.air_co2 -= fixed;
+    rates.air_o2 += fixed;
     
-    Ok(())
+    let growth = air_co2_as_organic(state, fixed);
+    rates.plant_biomass += growth;
+    rates.soil_nitrate -= organic_nitrogen(growth);
 }
 
-/// Calculate plant respiration and update oxygen/CO2
+/// Biomass burnt at night for the plants' upkeep
 fn plant_respiration(
-    state: &mut EcosystemStateV2,
-    params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let respiration_rate = params.respiration.base_rate * state.plant_biomass.value();
-    
-    let oxygen_consumption = respiration_rate * dt;
-    let co2_production = oxygen_consumption * params.respiration.co2_production;
-    
-    state.air_o2 = state.air_o2.subtract(oxygen_consumption)?;
-    state.air_co2 = state.air_co2.add(co2_production)?;
-    
-    Ok(())
-}
-
-/// Calculate plant growth based on environmental conditions
-fn plant_growth(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let light_level = state.light_level();
-    let light_factor = light_efficiency(light_level);
-    let nutrient_factor = nutrient_efficiency(state.soil_nitrogen);
-    let humidity_factor = humidity_efficiency(state.humidity);
-    let competition_factor = competition_factor(state.plant_biomass);
-    
-    let growth_rate = params.photosynthesis.base_rate * 0.3 // Growth is slower than photosynthesis
+    rates: &mut Derivatives,
+) {
+    let respiration_rate = params.respiration.base_rate
+        * params.respiration.co2_production
         * state.plant_biomass.value()
-        * light_factor
-        * nutrient_factor
-        * humidity_factor
-        * competition_factor;
-    
-    let biomass_increase = growth_rate * dt;
-    state.plant_biomass = state.plant_biomass.add(biomass_increase)?;
+        * aerobic_limitation(state.air_o2);
     
-    Ok(())
-}
-
-/// Calculate nitrogen uptake by plants
-fn nitrogen_uptake(
-    state: &mut EcosystemStateV2,
-    params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let uptake_rate = params.environmental.plant_nitrogen_uptake 
-        * state.plant_biomass.value();
-    
-    let nitrogen_consumed = uptake_rate * dt;
-    state.soil_nitrogen = state.soil_nitrogen.subtract(nitrogen_consumed)?;
-    
-    Ok(())
+    let burnt = air_co2_as_organic(state, respiration_rate);
+    rates.plant_biomass -= burnt;
+    mineralise(state, burnt, rates);
 }
 
 /// Check if plants have collapsed
@@ -121,19 +118,20 @@ pub fn are_plants_collapsed(state: &EcosystemStateV2) -> bool {
 pub fn plant_health_status(state: &EcosystemStateV2) -> PlantHealthStatus {
     let biomass = state.plant_biomass.value();
     let light_level = state.light_level();
-    let nutrient_level = state.soil_nitrogen.value();
     
     let light_factor = light_efficiency(light_level);
-    let nutrient_factor = nutrient_efficiency(state.soil_nitrogen);
+    let nutrient_factor = nutrient_efficiency(state.soil_nitrate);
     let humidity_factor = humidity_efficiency(state.humidity);
+    let temperature_factor = temperature_efficiency(state.temperature);
     
     PlantHealthStatus {
         biomass,
         light_adequacy: light_factor,
         nutrient_adequacy: nutrient_factor,
         humidity_adequacy: humidity_factor,
+        temperature_adequacy: temperature_factor,
         is_growing: biomass > 0.01 && light_factor > 0.3 && nutrient_factor > 0.1,
-        is_stressed: light_factor < 0.5 || nutrient_factor < 0.2 || humidity_factor < 0.4,
+        is_stressed: light_factor < 0.5 || nutrient_factor < 0.2 || humidity_factor < 0.4 || temperature_factor < 0.3,
     }
 }
 
@@ -143,6 +141,7 @@ pub struct PlantHealthStatus {
     pub light_adequacy: f32,
     pub nutrient_adequacy: f32,
     pub humidity_adequacy: f32,
+    pub temperature_adequacy: f32,
     pub is_growing: bool,
     pub is_stressed: bool,
 }
@@ -213,4 +212,31 @@ impl GasOps for crate::v2::types::Nitrogen {
         let new_value = (self.value() - amount).max(0.0);
         Ok(crate::v2::types::Nitrogen::new(new_value)?)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::config::V2Config;
+    use crate::v2::types::{DayPhase, Nitrogen};
+
+    fn daytime_growth(nitrate: f32) -> Derivatives {
+        let config = V2Config::new();
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        state.day_phase = DayPhase::Noon;
+        state.soil_nitrate = Nitrogen::new(nitrate).unwrap();
+        let mut rates = Derivatives::default();
+        photosynthesis(&state, &config.parameters, &mut rates);
+        rates
+    }
+
+    #[test]
+    fn test_growth_takes_up_nitrate() {
+        let fed = daytime_growth(2.0);
+        assert!(fed.plant_biomass > 0.0);
+        assert_eq!(fed.soil_nitrate, -organic_nitrogen(fed.plant_biomass));
+
+        let starved = daytime_growth(0.0);
+        assert_eq!(starved.plant_biomass, 0.0);
+        assert_eq!(starved.soil_nitrate, 0.0);
+    }
+}
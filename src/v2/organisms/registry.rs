@@ -0,0 +1,177 @@
+// v2/organisms/registry.rs
+// Registry of the species simulated in a bottle
+
+use crate::v2::config::parameters::CollapseCriteria;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::organisms::{Organism, TrophicRole};
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::organisms::{microbes::Microbes, plants::Plants, shrimp::Shrimp, worms::Worms};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::sync::Arc;
+
+/// Ordered set of species; the engine simulates whatever is registered here
+#[derive(Clone)]
+pub struct OrganismRegistry {
+    organisms: Vec<Arc<dyn Organism>>,
+}
+
+impl OrganismRegistry {
+    /// A bottle with nothing living in it
+    pub fn empty() -> Self {
+        Self { organisms: Vec::new() }
+    }
+
+    /// Plants, microbes, worms and shrimp
+    pub fn standard() -> Self {
+        let mut registry = Self::empty();
+        registry.organisms.push(Arc::new(Plants));
+        registry.organisms.push(Arc::new(Microbes));
+        registry.organisms.push(Arc::new(Worms));
+        registry.organisms.push(Arc::new(Shrimp));
+        registry
+    }
+
+    /// The standard species named, in the order given
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> EcosystemResult<Self> {
+        let standard = Self::standard();
+        let mut registry = Self::empty();
+        for name in names {
+            let position = standard.position(name.as_ref())?;
+            if registry.contains(name.as_ref()) {
+                return Err(EcosystemError::ConfigurationError {
+                    message: format!("Species '{}' is listed twice", name.as_ref()),
+                });
+            }
+            registry.organisms.push(standard.organisms[position].clone());
+        }
+        Ok(registry)
+    }
+
+    /// Add a species at the end of the registry
+    pub fn register(&mut self, organism: impl Organism + 'static) -> EcosystemResult<()> {
+        if self.contains(organism.name()) {
+            return Err(EcosystemError::ConfigurationError {
+                message: format!("Species '{}' is already registered", organism.name()),
+            });
+        }
+        self.organisms.push(Arc::new(organism));
+        Ok(())
+    }
+
+    /// Take a species out of the bottle; returns false if it wasn't there
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.organisms.len();
+        self.organisms.retain(|organism| organism.name() != name);
+        self.organisms.len() != before
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.organisms.iter().any(|organism| organism.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Organism> {
+        self.organisms.iter().map(|organism| organism.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.iter().map(|organism| organism.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.organisms.is_empty()
+    }
+
+    /// Species whose loss ends the run under the given collapse rule
+    pub fn failed_species(&self, state: &EcosystemStateV2, criteria: CollapseCriteria) -> Vec<&dyn Organism> {
+        let dead = |organism: &&dyn Organism| organism.is_collapsed(state);
+        match criteria {
+            CollapseCriteria::AnySpecies => self.iter().filter(dead).collect(),
+            CollapseCriteria::ProducersOrDecomposers => [TrophicRole::Producer, TrophicRole::Decomposer]
+                .into_iter()
+                .flat_map(|role| self.extinct_group(|organism| organism.role() == role, state))
+                .collect(),
+            CollapseCriteria::AllSpecies => self.extinct_group(|_| true, state),
+        }
+    }
+
+    /// Every member of the group, if the group has members and all of them are gone
+    fn extinct_group(&self, member: impl Fn(&dyn Organism) -> bool, state: &EcosystemStateV2) -> Vec<&dyn Organism> {
+        let group: Vec<&dyn Organism> = self.iter().filter(|organism| member(*organism)).collect();
+        if !group.is_empty() && group.iter().all(|organism| organism.is_collapsed(state)) {
+            group
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn position(&self, name: &str) -> EcosystemResult<usize> {
+        self.organisms
+            .iter()
+            .position(|organism| organism.name() == name)
+            .ok_or_else(|| EcosystemError::ConfigurationError {
+                message: format!("Unknown species '{}'", name),
+            })
+    }
+}
+
+impl Default for OrganismRegistry {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Written as the list of species names
+impl Serialize for OrganismRegistry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.names().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OrganismRegistry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        Self::from_names(&names).map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Debug for OrganismRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_remove() {
+        let mut registry = OrganismRegistry::standard();
+        assert_eq!(registry.names(), vec!["plants", "microbes", "worms", "shrimp"]);
+
+        assert!(registry.register(Shrimp).is_err());
+        assert!(registry.remove("shrimp"));
+        assert!(!registry.remove("shrimp"));
+        assert_eq!(registry.names(), vec!["plants", "microbes", "worms"]);
+    }
+
+    #[test]
+    fn test_producers_or_decomposers_rule() {
+        use crate::v2::config::V2Config;
+        use crate::v2::types::Population;
+
+        let config = V2Config::new();
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        let registry = OrganismRegistry::standard();
+        state.worm_pop = Population::new(0.0).unwrap();
+
+        // Microbes are still decomposing, so losing the worms is survivable
+        assert!(registry.failed_species(&state, CollapseCriteria::ProducersOrDecomposers).is_empty());
+        assert_eq!(registry.failed_species(&state, CollapseCriteria::AnySpecies).len(), 1);
+
+        state.microbe_pop = Population::new(0.0).unwrap();
+        let failed = registry.failed_species(&state, CollapseCriteria::ProducersOrDecomposers);
+        assert_eq!(failed.iter().map(|organism| organism.name()).collect::<Vec<_>>(), vec!["microbes", "worms"]);
+    }
+}
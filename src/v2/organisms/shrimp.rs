@@ -4,88 +4,100 @@
 use crate::v2::config::parameters::SimulationParameters;
 use crate::v2::state::EcosystemStateV2;
 use crate::v2::environmental::*;
-use crate::v2::errors::EcosystemResult;
-use crate::v2::organisms::microbes::PopulationOps;
+use crate::v2::integrator::Derivatives;
+use crate::v2::ledger::SHRIMP_BIOMASS;
+use crate::v2::organisms::{mineralise, surviving, Organism, TrophicRole};
+use crate::v2::errors::CollapseReason;
 
-/// Update shrimp population and associated processes
-pub fn update_shrimp(
-    state: &mut EcosystemStateV2,
+/// Shrimp graze detritus in the water
+pub struct Shrimp;
+
+impl Organism for Shrimp {
+    fn name(&self) -> &'static str {
+        "shrimp"
+    }
+
+    fn role(&self) -> TrophicRole {
+        TrophicRole::Consumer
+    }
+
+    fn rates(&self, state: &EcosystemStateV2, params: &SimulationParameters, _is_day: bool, rates: &mut Derivatives) {
+        shrimp_rates(state, params, rates);
+    }
+
+    fn stress_rates(&self, state: &EcosystemStateV2, rates: &mut Derivatives) {
+        let penalty = ph_penalty_factor(state.water_ph) + water_oxygen_penalty_factor(state.water_o2);
+        let died = 0.20 * penalty * surviving(state.shrimp_pop);
+        rates.shrimp_pop -= died;
+        rates.detritus += died * SHRIMP_BIOMASS;
+    }
+
+    fn is_collapsed(&self, state: &EcosystemStateV2) -> bool {
+        are_shrimp_collapsed(state)
+    }
+
+    fn collapse_reason(&self) -> CollapseReason {
+        CollapseReason::ShrimpDied
+    }
+
+    fn collapse_risk(&self, state: &EcosystemStateV2) -> f32 {
+        if state.shrimp_pop.value() < 1.0 { 0.5 } else { 0.0 }
+    }
+}
+
+/// Add the rates of shrimp feeding, excretion and population change
+pub fn shrimp_rates(
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     // Detritus consumption by shrimp
-    detritus_consumption(state, params, dt)?;
-    
-    // Waste production by shrimp
-    waste_production(state, params, dt)?;
+    detritus_consumption(state, params, rates);
     
     // Shrimp population dynamics
-    shrimp_population_dynamics(state, params, dt)?;
-    
-    Ok(())
+    shrimp_population_dynamics(state, params, rates);
 }
 
-/// Calculate detritus consumption by shrimp
+/// Detritus grazed by shrimp, respired to CO2 with its nitrogen excreted as ammonia
 fn detritus_consumption(
-    state: &mut EcosystemStateV2,
-    params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let consumption_rate = params.shrimp.detritus_consumption_rate * state.shrimp_pop.value();
-    let detritus_consumed = consumption_rate * dt;
-    
-    // Consume detritus
-    let new_detritus = (state.detritus.value() - detritus_consumed).max(0.0);
-    state.detritus = crate::v2::types::Detritus::new(new_detritus)?;
-    
-    Ok(())
-}
-
-/// Calculate waste production by shrimp
-fn waste_production(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let waste_rate = params.shrimp.waste_production_rate * state.shrimp_pop.value();
-    let waste_produced = waste_rate * dt;
-    
-    // Add waste as soil nitrogen (shrimp waste is nutrient-rich)
-    let new_nitrogen = state.soil_nitrogen.value() + waste_produced;
-    state.soil_nitrogen = crate::v2::types::Nitrogen::new(new_nitrogen)?;
+    rates: &mut Derivatives,
+) {
+    let grazed = params.shrimp.detritus_consumption_rate
+        * state.shrimp_pop.value()
+        * food_limitation(state.detritus)
+        * aerobic_limitation(state.air_o2);
     
-    Ok(())
+    rates.detritus -= grazed;
+    mineralise(state, grazed, rates);
 }
 
-/// Calculate shrimp population growth and death
+/// Shrimp population growth and death
 fn shrimp_population_dynamics(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     let detritus_factor = detritus_availability(state.detritus);
     let water_oxygen_factor = water_oxygen_efficiency(state.water_o2);
     let temperature_factor = temperature_efficiency(state.temperature);
     
-    // Growth
+    // Growth, built from the detritus they feed on
     let growth_rate = params.shrimp.growth_rate
         * state.shrimp_pop.value()
         * detritus_factor
         * water_oxygen_factor
         * temperature_factor;
     
-    // Death (including toxicity effects when implemented)
-    let toxicity_factor = toxicity_factor(0.0); // Placeholder
+    // Death, raised by ammonia and nitrite in the water
+    let toxicity_factor = toxicity_factor(state.toxicity());
     let death_rate = params.shrimp.death_rate
         * state.shrimp_pop.value()
         * (1.0 + toxicity_factor);
     
-    let net_growth = (growth_rate - death_rate) * dt;
-    let new_population = (state.shrimp_pop.value() + net_growth).max(0.0);
-    
-    state.shrimp_pop = crate::v2::types::Population::new(new_population)?;
-    
-    Ok(())
+    rates.shrimp_pop += growth_rate - death_rate;
+    rates.detritus += (death_rate - growth_rate) * SHRIMP_BIOMASS;
 }
 
 /// Check if shrimp have collapsed
@@ -106,7 +118,7 @@ pub fn shrimp_health_status(state: &EcosystemStateV2) -> ShrimpHealthStatus {
         water_oxygen_adequacy: water_oxygen_factor,
         temperature_adequacy: temperature_factor,
         is_growing: population > 0.01 && detritus_factor > 0.1 && water_oxygen_factor > 0.3,
-        is_stressed: water_oxygen_factor < 0.3 || temperature_factor < 0.3,
+        is_stressed: water_oxygen_factor < 0.3 || temperature_factor < 0.3 || state.toxicity() > STRESSFUL_TOXICITY,
         detritus_consumption_rate: calculate_detritus_consumption_rate(state),
         waste_production_rate: calculate_waste_production_rate(state),
     }
@@ -160,20 +172,32 @@ pub fn shrimp_oxygen_consumption(state: &EcosystemStateV2) -> f32 {
     base_consumption * state.shrimp_pop.value()
 }
 
-/// Update water oxygen levels based on shrimp activity
-pub fn update_water_oxygen(
-    state: &mut EcosystemStateV2,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let oxygen_consumed = shrimp_oxygen_consumption(state) * dt;
-    let new_water_oxygen = (state.water_o2.percentage() - oxygen_consumed).max(0.0);
-    state.water_o2 = crate::v2::types::Oxygen::new(new_water_oxygen)?;
+/// Water oxygen used by shrimp and regained from the air
+pub fn water_oxygen_rates(state: &EcosystemStateV2, rates: &mut Derivatives) {
+    rates.water_o2 -= shrimp_oxygen_consumption(state);
     
     // Water can also gain oxygen from air through surface exchange
     let surface_exchange_rate = 0.01; // Slow oxygen exchange with air
-    let oxygen_gained = surface_exchange_rate * (state.air_o2.percentage() - state.water_o2.percentage()).max(0.0) * dt;
-    let final_water_oxygen = state.water_o2.percentage() + oxygen_gained;
-    state.water_o2 = crate::v2::types::Oxygen::new(final_water_oxygen)?;
-    
-    Ok(())
-}
\ No newline at end of file
+    rates.water_o2 += surface_exchange_rate * (state.air_o2.percentage() - state.water_o2.percentage()).max(0.0);
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::config::V2Config;
+    use crate::v2::types::Ph;
+
+    #[test]
+    fn test_stress_follows_water_ph() {
+        let mut state = EcosystemStateV2::new(&V2Config::new()).expect("Failed to create state");
+        state.soil_ph = Ph::new(4.0).unwrap();
+        state.water_ph = Ph::new(7.0).unwrap();
+        let mut rates = Derivatives::default();
+        Shrimp.stress_rates(&state, &mut rates);
+        let neutral_water = rates.shrimp_pop;
+
+        state.water_ph = Ph::new(4.0).unwrap();
+        let mut rates = Derivatives::default();
+        Shrimp.stress_rates(&state, &mut rates);
+        assert!(rates.shrimp_pop < neutral_water);
+    }
+}
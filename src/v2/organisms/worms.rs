@@ -4,91 +4,119 @@
 use crate::v2::config::parameters::SimulationParameters;
 use crate::v2::state::EcosystemStateV2;
 use crate::v2::environmental::*;
-use crate::v2::errors::EcosystemResult;
-use crate::v2::organisms::microbes::PopulationOps;
+use crate::v2::integrator::Derivatives;
+use crate::v2::ledger::WORM_BIOMASS;
+use crate::v2::organisms::{mineralise, surviving, Organism, TrophicRole};
+use crate::v2::errors::CollapseReason;
 
-/// Update worm population and associated processes
-pub fn update_worms(
-    state: &mut EcosystemStateV2,
+/// Share of the detritus passing through a worm that it digests; the rest is left as castings
+const DIGESTED_SHARE: f32 = 0.3;
+
+/// Worms break down detritus and keep the soil open
+pub struct Worms;
+
+impl Organism for Worms {
+    fn name(&self) -> &'static str {
+        "worms"
+    }
+
+    fn role(&self) -> TrophicRole {
+        TrophicRole::Decomposer
+    }
+
+    fn rates(&self, state: &EcosystemStateV2, params: &SimulationParameters, _is_day: bool, rates: &mut Derivatives) {
+        worm_rates(state, params, rates);
+    }
+
+    fn stress_rates(&self, state: &EcosystemStateV2, rates: &mut Derivatives) {
+        let penalty = oxygen_penalty_factor(state.air_o2);
+        let died = 0.20 * penalty * surviving(state.worm_pop);
+        rates.worm_pop -= died;
+        rates.detritus += died * WORM_BIOMASS;
+    }
+
+    fn is_collapsed(&self, state: &EcosystemStateV2) -> bool {
+        are_worms_collapsed(state)
+    }
+
+    fn collapse_reason(&self) -> CollapseReason {
+        CollapseReason::WormsDied
+    }
+
+    fn collapse_risk(&self, state: &EcosystemStateV2) -> f32 {
+        if state.worm_pop.value() < 1.0 { 0.6 } else { 0.0 }
+    }
+}
+
+/// Add the rates of worm aeration, decomposition and population change
+pub fn worm_rates(
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     // Soil aeration by worms
-    soil_aeration(state, params, dt)?;
+    soil_aeration(state, params, rates);
     
     // Decomposition of organic matter
-    decomposition(state, params, dt)?;
+    decomposition(state, params, rates);
     
     // Worm population dynamics
-    worm_population_dynamics(state, params, dt)?;
-    
-    Ok(())
+    worm_population_dynamics(state, params, rates);
 }
 
-/// Calculate soil aeration by worms
+/// Soil loosened by burrowing worms
 fn soil_aeration(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let aeration_increase = params.worm.aeration_rate * state.worm_pop.value() * dt;
-    
-    let new_aeration = state.soil_aeration.value() + aeration_increase;
-    state.soil_aeration = crate::v2::types::Aeration::new(new_aeration)?;
-    
-    Ok(())
+    rates: &mut Derivatives,
+) {
+    rates.soil_aeration += params.worm.aeration_rate
+        * state.soil_type.aeration_modifier()
+        * state.worm_pop.value();
 }
 
-/// Calculate decomposition of organic matter by worms
+/// Decomposition of organic matter by worms
 fn decomposition(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
-    let decomposition_rate = params.worm.decomposition_rate * state.worm_pop.value();
-    let detritus_consumed = decomposition_rate * dt;
-    
-    // Consume detritus
-    let new_detritus = (state.detritus.value() - detritus_consumed).max(0.0);
-    state.detritus = crate::v2::types::Detritus::new(new_detritus)?;
-    
-    // Convert some detritus back to soil nutrients
-    let nutrients_released = detritus_consumed * 0.3; // 30% conversion efficiency
-    let new_nitrogen = state.soil_nitrogen.value() + nutrients_released;
-    state.soil_nitrogen = crate::v2::types::Nitrogen::new(new_nitrogen)?;
+    rates: &mut Derivatives,
+) {
+    let digested = params.worm.decomposition_rate
+        * state.worm_pop.value()
+        * food_limitation(state.detritus)
+        * aerobic_limitation(state.air_o2)
+        * DIGESTED_SHARE;
     
-    Ok(())
+    // Digested detritus is mineralised back to CO2 and soil ammonia
+    rates.detritus -= digested;
+    mineralise(state, digested, rates);
 }
 
-/// Calculate worm population growth and death
+/// Worm population growth and death
 fn worm_population_dynamics(
-    state: &mut EcosystemStateV2,
+    state: &EcosystemStateV2,
     params: &SimulationParameters,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     let detritus_factor = detritus_availability(state.detritus);
     let moisture_factor = moisture_efficiency(state.soil_moisture);
     let temperature_factor = temperature_efficiency(state.temperature);
     
-    // Growth
+    // Growth, built from the detritus they feed on
     let growth_rate = params.worm.growth_rate
         * state.worm_pop.value()
         * detritus_factor
         * moisture_factor
         * temperature_factor;
     
-    // Death (including toxicity effects when implemented)
-    let toxicity_factor = toxicity_factor(0.0); // Placeholder
+    // Death, raised by ammonia and nitrite; worms tolerate them better than shrimp
+    let toxicity_factor = toxicity_factor(state.toxicity() * 0.5);
     let death_rate = params.worm.death_rate
         * state.worm_pop.value()
         * (1.0 + toxicity_factor);
     
-    let net_growth = (growth_rate - death_rate) * dt;
-    let new_population = (state.worm_pop.value() + net_growth).max(0.0);
-    
-    state.worm_pop = crate::v2::types::Population::new(new_population)?;
-    
-    Ok(())
+    rates.worm_pop += growth_rate - death_rate;
+    rates.detritus += (death_rate - growth_rate) * WORM_BIOMASS;
 }
 
 /// Check if worms have collapsed
@@ -116,7 +144,7 @@ pub fn worm_health_status(state: &EcosystemStateV2) -> WormHealthStatus {
 }
 
 fn calculate_aeration_contribution(state: &EcosystemStateV2) -> f32 {
-    0.01 * state.worm_pop.value()
+    0.01 * state.soil_type.aeration_modifier() * state.worm_pop.value()
 }
 
 fn calculate_decomposition_rate(state: &EcosystemStateV2) -> f32 {
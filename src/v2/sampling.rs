@@ -0,0 +1,601 @@
+// v2/sampling.rs
+// Sampling strategies and per-dimension distributions for Monte Carlo inputs
+
+use crate::v2::config::{field_path, V2Config};
+use crate::v2::config::difficulty::DifficultyConfig;
+use crate::v2::config::environment::SoilType;
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the runs of a batch are spread over the input space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SamplingStrategy {
+    /// Independent uniform draws for every run
+    #[default]
+    Uniform,
+    /// Every dimension split into one stratum per run, each stratum used once
+    LatinHypercube,
+    /// Digitally shifted Sobol sequence; best with a power-of-two number of runs
+    Sobol,
+    /// Randomly rotated Halton sequence
+    Halton,
+    /// Full-factorial grid with this many levels per varied dimension
+    Grid { levels: usize },
+}
+
+impl FromStr for SamplingStrategy {
+    type Err = EcosystemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EcosystemError::ConfigurationError {
+            message: format!("Unknown sampling strategy '{}' (expected uniform, lhs, sobol, halton or grid:<levels>)", s),
+        };
+        match s.to_lowercase().as_str() {
+            "uniform" | "random" => Ok(SamplingStrategy::Uniform),
+            "lhs" | "latin-hypercube" => Ok(SamplingStrategy::LatinHypercube),
+            "sobol" => Ok(SamplingStrategy::Sobol),
+            "halton" => Ok(SamplingStrategy::Halton),
+            "grid" => Ok(SamplingStrategy::Grid { levels: 3 }),
+            other => {
+                let levels: usize = other.strip_prefix("grid:").ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+                if levels == 0 {
+                    return Err(invalid());
+                }
+                Ok(SamplingStrategy::Grid { levels })
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for SamplingStrategy {
+    type Error = EcosystemError;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        spec.parse()
+    }
+}
+
+/// Written as `--sampling` takes it
+impl From<SamplingStrategy> for String {
+    fn from(strategy: SamplingStrategy) -> Self {
+        match strategy {
+            SamplingStrategy::Uniform => "uniform".to_string(),
+            SamplingStrategy::LatinHypercube => "lhs".to_string(),
+            SamplingStrategy::Sobol => "sobol".to_string(),
+            SamplingStrategy::Halton => "halton".to_string(),
+            SamplingStrategy::Grid { levels } => format!("grid:{}", levels),
+        }
+    }
+}
+
+impl fmt::Display for SamplingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SamplingStrategy::Uniform => write!(f, "uniform random"),
+            SamplingStrategy::LatinHypercube => write!(f, "Latin hypercube"),
+            SamplingStrategy::Sobol => write!(f, "Sobol"),
+            SamplingStrategy::Halton => write!(f, "Halton"),
+            SamplingStrategy::Grid { levels } => write!(f, "grid with {} levels", levels),
+        }
+    }
+}
+
+/// Shape of a dimension inside its range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Uniform,
+    /// Normal truncated to the range
+    Normal { mean: f32, std_dev: f32 },
+    /// Uniform in the logarithm; the range must be positive
+    LogUniform,
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Distribution::Uniform => write!(f, "uniform"),
+            Distribution::Normal { mean, std_dev } => write!(f, "normal(μ={}, σ={})", mean, std_dev),
+            Distribution::LogUniform => write!(f, "log-uniform"),
+        }
+    }
+}
+
+/// One sampled input: its name, range and distribution
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dimension {
+    pub name: &'static str,
+    pub low: f32,
+    pub high: f32,
+    pub distribution: Distribution,
+    /// Rounded to whole numbers: counts, rocks, window proximity and soil types
+    pub discrete: bool,
+}
+
+impl Dimension {
+    pub const NAMES: [&'static str; 12] = [
+        "difficulty",
+        "water_volume",
+        "rocks",
+        "window_proximity",
+        "temperature",
+        "humidity",
+        "room_temperature",
+        "soil_type",
+        "microbes",
+        "worms",
+        "shrimp",
+        "plant_biomass",
+    ];
+
+    pub(crate) fn continuous(name: &'static str, low: f32, high: f32) -> Self {
+        Self { name, low, high, distribution: Distribution::Uniform, discrete: false }
+    }
+
+    pub(crate) fn discrete(name: &'static str, low: f32, high: f32) -> Self {
+        Self { discrete: true, ..Self::continuous(name, low, high) }
+    }
+
+    /// Ranges the Monte Carlo has always drawn from
+    pub fn defaults() -> Vec<Dimension> {
+        vec![
+            Dimension::continuous("difficulty", 0.3, 0.7),
+            Dimension::continuous("water_volume", 0.2, 2.0),
+            Dimension::discrete("rocks", 0.0, 5.0),
+            Dimension::discrete("window_proximity", 1.0, 6.0),
+            Dimension::continuous("temperature", 15.0, 30.0),
+            Dimension::continuous("humidity", 30.0, 90.0),
+            Dimension::continuous("room_temperature", 18.0, 26.0),
+            Dimension::discrete("soil_type", 0.0, (SoilType::ALL.len() - 1) as f32),
+            Dimension::discrete("microbes", 100.0, 5000.0),
+            Dimension::discrete("worms", 1.0, 15.0),
+            Dimension::discrete("shrimp", 1.0, 8.0),
+            Dimension::continuous("plant_biomass", 0.5, 3.0),
+        ]
+    }
+
+    /// Map a point of the unit interval to a value through the inverse CDF
+    pub fn value(&self, unit: f64) -> f32 {
+        let unit = unit.clamp(0.0, 1.0);
+        // Whole numbers get equal-width cells centred on each value
+        let (low, high) = if self.discrete {
+            (self.low as f64 - 0.5, self.high as f64 + 0.5)
+        } else {
+            (self.low as f64, self.high as f64)
+        };
+
+        let value = match self.distribution {
+            Distribution::Uniform => low + unit * (high - low),
+            Distribution::LogUniform => (low.ln() + unit * (high.ln() - low.ln())).exp(),
+            Distribution::Normal { mean, std_dev } => {
+                let (mean, std_dev) = (mean as f64, (std_dev as f64).max(f64::EPSILON));
+                let (a, b) = (normal_cdf((low - mean) / std_dev), normal_cdf((high - mean) / std_dev));
+                mean + std_dev * inverse_normal_cdf(a + unit * (b - a))
+            }
+        };
+        let value = value.clamp(low, high) as f32;
+
+        if self.discrete {
+            value.round().clamp(self.low, self.high)
+        } else {
+            value
+        }
+    }
+
+    /// Number of grid levels; fixed dimensions have one and whole numbers no more than they hold
+    fn grid_levels(&self, levels: usize) -> usize {
+        if self.high <= self.low {
+            1
+        } else if self.discrete {
+            levels.min((self.high - self.low) as usize + 1)
+        } else {
+            levels
+        }
+    }
+
+    fn validate(&self) -> EcosystemResult<()> {
+        let invalid = |message: String| Err(EcosystemError::ConfigurationError { message });
+        if !Self::NAMES.contains(&self.name) {
+            return invalid(format!("Unknown dimension '{}' (expected one of {})", self.name, Self::NAMES.join(", ")));
+        }
+        if self.low.is_nan() || self.high.is_nan() || self.low > self.high {
+            return invalid(format!("Dimension '{}' has low {} above high {}", self.name, self.low, self.high));
+        }
+        // Both ends have to be values a configuration accepts; counts would otherwise round up to 0
+        if self.discrete && self.low < 0.0 {
+            return invalid(format!("Dimension '{}' cannot go below 0", self.name));
+        }
+        for value in [self.low, self.high] {
+            let accepted = match self.name {
+                "difficulty" => DifficultyConfig::new(value).map(|_| ()),
+                name => V2Config::check_field(field_path(name), value),
+            };
+            if let Err(e) = accepted {
+                let reason = match e {
+                    EcosystemError::ConfigurationError { message } => message,
+                    e => e.to_string(),
+                };
+                return invalid(format!("Dimension '{}' goes outside what the field accepts: {}", self.name, reason));
+            }
+        }
+        match self.distribution {
+            Distribution::LogUniform if self.low <= 0.0 || self.discrete && self.low - 0.5 <= 0.0 => {
+                invalid(format!("Dimension '{}' needs a positive range to be log-uniform", self.name))
+            }
+            Distribution::Normal { std_dev, .. } if std_dev <= 0.0 => {
+                invalid(format!("Dimension '{}' needs a positive standard deviation", self.name))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `name=low:high[:uniform|normal|normal:<std_dev>|log-uniform]`; normal defaults to the
+/// range's midpoint and a quarter of its width
+impl FromStr for Dimension {
+    type Err = EcosystemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EcosystemError::ConfigurationError {
+            message: format!("Cannot read dimension '{}' (expected name=low:high[:uniform|normal[:std_dev]|log-uniform])", s),
+        };
+        let (name, spec) = s.split_once('=').ok_or_else(invalid)?;
+        let name = *Dimension::NAMES.iter().find(|n| **n == name.trim()).ok_or_else(|| EcosystemError::ConfigurationError {
+            message: format!("Unknown dimension '{}' (expected one of {})", name.trim(), Dimension::NAMES.join(", ")),
+        })?;
+        let mut parts = spec.split(':');
+        let low: f32 = parts.next().and_then(|v| v.trim().parse().ok()).ok_or_else(invalid)?;
+        let high: f32 = parts.next().and_then(|v| v.trim().parse().ok()).ok_or_else(invalid)?;
+
+        let distribution = match parts.next().map(str::trim) {
+            None | Some("uniform") => Distribution::Uniform,
+            Some("log-uniform") | Some("loguniform") => Distribution::LogUniform,
+            Some("normal") => {
+                let std_dev = match parts.next() {
+                    Some(v) => v.trim().parse().map_err(|_| invalid())?,
+                    None => (high - low) / 4.0,
+                };
+                Distribution::Normal { mean: (low + high) / 2.0, std_dev }
+            }
+            Some(_) => return Err(invalid()),
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        let discrete = Dimension::defaults().iter().any(|d| d.name == name && d.discrete);
+        let dimension = Dimension { name, low, high, distribution, discrete };
+        dimension.validate()?;
+        Ok(dimension)
+    }
+}
+
+/// Written as `--dim` takes it; a normal distribution is always centred on its range
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}:{}:", self.name, self.low, self.high)?;
+        match self.distribution {
+            Distribution::Uniform => write!(f, "uniform"),
+            Distribution::Normal { std_dev, .. } => write!(f, "normal:{}", std_dev),
+            Distribution::LogUniform => write!(f, "log-uniform"),
+        }
+    }
+}
+
+impl Serialize for Dimension {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Dimension {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Replace the default dimension of the same name
+pub fn override_dimension(dimensions: &mut [Dimension], dimension: Dimension) {
+    if let Some(existing) = dimensions.iter_mut().find(|d| d.name == dimension.name) {
+        *existing = dimension;
+    }
+}
+
+/// Points in the unit hypercube for every run, built once per batch from the master seed
+#[derive(Debug, Clone)]
+pub struct SamplingPlan {
+    /// `None` for uniform sampling, where each run draws from its own generator
+    points: Option<Vec<Vec<f64>>>,
+}
+
+impl SamplingPlan {
+    pub fn new(strategy: SamplingStrategy, dimensions: &[Dimension], runs: usize, master_seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(master_seed ^ 0x5A4D_504C_494E_4721);
+        let k = dimensions.len();
+        let points = match strategy {
+            SamplingStrategy::Uniform => None,
+            SamplingStrategy::LatinHypercube => Some(latin_hypercube(runs, k, &mut rng)),
+            SamplingStrategy::Sobol => Some(sobol(runs, k, &mut rng)),
+            SamplingStrategy::Halton => Some(halton(runs, k, &mut rng)),
+            SamplingStrategy::Grid { levels } => {
+                let levels: Vec<usize> = dimensions.iter().map(|d| d.grid_levels(levels)).collect();
+                Some(grid(runs, &levels))
+            }
+        };
+        Self { points }
+    }
+
+    /// Values of every dimension for one run
+    pub fn sample(&self, run_id: usize, dimensions: &[Dimension], rng: &mut StdRng) -> Vec<f32> {
+        match &self.points {
+            Some(points) => dimensions.iter().zip(&points[run_id]).map(|(d, &u)| d.value(u)).collect(),
+            None => dimensions.iter().map(|d| d.value(rng.r#gen())).collect(),
+        }
+    }
+}
+
+/// Runs needed to visit every point of the grid once
+pub fn grid_size(dimensions: &[Dimension], levels: usize) -> usize {
+    dimensions.iter().map(|d| d.grid_levels(levels)).product()
+}
+
+fn latin_hypercube(runs: usize, k: usize, rng: &mut StdRng) -> Vec<Vec<f64>> {
+    let mut points = vec![vec![0.0; k]; runs];
+    for dimension in 0..k {
+        let mut strata: Vec<usize> = (0..runs).collect();
+        strata.shuffle(rng);
+        for (point, stratum) in points.iter_mut().zip(strata) {
+            point[dimension] = (stratum as f64 + rng.r#gen::<f64>()) / runs as f64;
+        }
+    }
+    points
+}
+
+/// Direction numbers for dimensions 2 to 16 (Joe & Kuo): degree s, coefficients a, initial m
+const SOBOL_DIRECTIONS: [(u32, u32, &[u32]); 15] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+];
+
+const SOBOL_BITS: u32 = 32;
+
+fn sobol_direction_vectors(dimension: usize) -> [u32; SOBOL_BITS as usize] {
+    let mut v = [0u32; SOBOL_BITS as usize];
+    if dimension == 0 {
+        for (i, value) in v.iter_mut().enumerate() {
+            *value = 1 << (SOBOL_BITS - 1 - i as u32);
+        }
+        return v;
+    }
+
+    let (s, a, m) = SOBOL_DIRECTIONS[dimension - 1];
+    let s = s as usize;
+    for i in 0..s.min(SOBOL_BITS as usize) {
+        v[i] = m[i] << (SOBOL_BITS - 1 - i as u32);
+    }
+    for i in s..SOBOL_BITS as usize {
+        let mut value = v[i - s] ^ (v[i - s] >> s);
+        for k in 1..s {
+            if (a >> (s - 1 - k)) & 1 == 1 {
+                value ^= v[i - k];
+            }
+        }
+        v[i] = value;
+    }
+    v
+}
+
+/// Gray-code Sobol points with a random digital shift per dimension; falls back to Halton
+/// past the dimensions the direction table covers
+fn sobol(runs: usize, k: usize, rng: &mut StdRng) -> Vec<Vec<f64>> {
+    if k > SOBOL_DIRECTIONS.len() + 1 {
+        return halton(runs, k, rng);
+    }
+    let directions: Vec<_> = (0..k).map(sobol_direction_vectors).collect();
+    let shifts: Vec<u32> = (0..k).map(|_| rng.r#gen()).collect();
+    let scale = 1.0 / (1u64 << SOBOL_BITS) as f64;
+
+    let mut x = vec![0u32; k];
+    let mut points = Vec::with_capacity(runs);
+    for index in 0..runs {
+        if index > 0 {
+            // The bit that changes between consecutive Gray codes
+            let bit = (index - 1).trailing_ones() as usize;
+            for (value, direction) in x.iter_mut().zip(&directions) {
+                *value ^= direction[bit.min(SOBOL_BITS as usize - 1)];
+            }
+        }
+        points.push(x.iter().zip(&shifts).map(|(value, shift)| (value ^ shift) as f64 * scale).collect());
+    }
+    points
+}
+
+fn halton(runs: usize, k: usize, rng: &mut StdRng) -> Vec<Vec<f64>> {
+    let primes = first_primes(k);
+    let rotations: Vec<f64> = (0..k).map(|_| rng.r#gen()).collect();
+    (0..runs)
+        .map(|index| {
+            primes
+                .iter()
+                .zip(&rotations)
+                .map(|(&base, rotation)| (radical_inverse(index + 1, base) + rotation).fract())
+                .collect()
+        })
+        .collect()
+}
+
+fn radical_inverse(mut index: usize, base: usize) -> f64 {
+    let mut result = 0.0;
+    let mut fraction = 1.0 / base as f64;
+    while index > 0 {
+        result += (index % base) as f64 * fraction;
+        index /= base;
+        fraction /= base as f64;
+    }
+    result
+}
+
+fn first_primes(count: usize) -> Vec<usize> {
+    let mut primes = Vec::with_capacity(count);
+    let mut candidate = 2;
+    while primes.len() < count {
+        if primes.iter().all(|p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// Cell centres of the grid in mixed-radix order; runs past the grid's size wrap around
+fn grid(runs: usize, levels: &[usize]) -> Vec<Vec<f64>> {
+    let size: usize = levels.iter().product::<usize>().max(1);
+    (0..runs)
+        .map(|run| {
+            let mut index = run % size;
+            levels
+                .iter()
+                .map(|&level| {
+                    let digit = index % level;
+                    index /= level;
+                    if level == 1 { 0.5 } else { digit as f64 / (level - 1) as f64 }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Standard normal CDF (Abramowitz & Stegun 7.1.26)
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+/// Inverse standard normal CDF (Acklam's rational approximation)
+pub(crate) fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2, 1.38357751867269e2, -3.066479806614716e1, 2.506628277459239];
+    const B: [f64; 5] = [-5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2, 6.680131188771972e1, -1.328068155288572e1];
+    const C: [f64; 6] = [-7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838, -2.549732539343734, 4.374664141464968, 2.938163982698783];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+
+    let p = p.clamp(1e-12, 1.0 - 1e-12);
+    let tail = |q: f64| (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0);
+    if p < 0.02425 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.02425 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each of `runs` equal strata of every dimension holds exactly one point
+    fn stratified(points: &[Vec<f64>], k: usize) -> bool {
+        let n = points.len();
+        (0..k).all(|dimension| {
+            let mut seen = vec![false; n];
+            points.iter().all(|p| {
+                let stratum = ((p[dimension] * n as f64) as usize).min(n - 1);
+                !std::mem::replace(&mut seen[stratum], true)
+            })
+        })
+    }
+
+    #[test]
+    fn test_designs_fill_every_stratum() {
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(stratified(&latin_hypercube(50, 12, &mut rng), 12));
+        // The first 2^m Sobol points are a net: one point per 1/2^m interval in every dimension
+        assert!(stratified(&sobol(64, 12, &mut rng), 12));
+        assert!(!stratified(&(0..64).map(|_| (0..12).map(|_| rng.r#gen()).collect()).collect::<Vec<_>>(), 12));
+
+        let points = halton(27, 2, &mut rng);
+        assert!(points.iter().all(|p| p.iter().all(|u| (0.0..1.0).contains(u))));
+    }
+
+    #[test]
+    fn test_grid_visits_every_combination() {
+        let mut dimensions = Dimension::defaults();
+        for dimension in dimensions.iter_mut().skip(2) {
+            dimension.high = dimension.low;
+        }
+        assert_eq!(grid_size(&dimensions, 3), 9);
+
+        let plan = SamplingPlan::new(SamplingStrategy::Grid { levels: 3 }, &dimensions, 9, 1);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut samples: Vec<(f32, f32)> = (0..9)
+            .map(|run| {
+                let values = plan.sample(run, &dimensions, &mut rng);
+                (values[0], values[1])
+            })
+            .collect();
+        samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        samples.dedup();
+        assert_eq!(samples.len(), 9);
+        assert!(samples.contains(&(0.3, 0.2)) && samples.contains(&(0.7, 2.0)));
+    }
+
+    #[test]
+    fn test_distributions_map_into_range() {
+        let log: Dimension = "microbes=100:10000:log-uniform".parse().unwrap();
+        assert!(log.discrete);
+        assert_eq!(log.value(0.0), 100.0);
+        assert!((log.value(0.5) - 1000.0).abs() < 15.0);
+
+        let normal: Dimension = "temperature=15:25:normal:2".parse().unwrap();
+        assert!((normal.value(0.5) - 20.0).abs() < 1e-3);
+        assert!((normal.value(0.8413) - 22.0).abs() < 0.05);
+        assert!(normal.value(0.0) >= 15.0 && normal.value(1.0) <= 25.0);
+
+        let rocks: Dimension = "rocks=0:5".parse().unwrap();
+        let counts = (0..600).fold([0; 6], |mut counts, i| {
+            counts[rocks.value((i as f64 + 0.5) / 600.0) as usize] += 1;
+            counts
+        });
+        assert_eq!(counts, [100; 6]);
+
+        assert!("rocks=0:5:log-uniform".parse::<Dimension>().is_err());
+        assert!("colour=0:1".parse::<Dimension>().is_err());
+    }
+
+    #[test]
+    fn test_ranges_stay_inside_what_the_config_accepts() {
+        for dimension in Dimension::defaults() {
+            assert!(dimension.validate().is_ok(), "{}", dimension.name);
+        }
+        let error = |s: &str| s.parse::<Dimension>().unwrap_err().to_string();
+        assert!(error("water_volume=0.05:0.15").contains("environment.water_volume"));
+        assert!(error("humidity=50:120").contains("humidity"));
+        assert!(error("shrimp=1:30").contains("organisms.shrimp.initial_count"));
+        assert!(error("worms=-2:5").contains("below 0"));
+        assert!(error("difficulty=0.5:1.5").contains("difficulty"));
+    }
+}
@@ -0,0 +1,226 @@
+// v2/script.rs
+// Headless v2 games: the setup and a day-by-day plan of interventions come from a file
+
+use crate::v2::config::{ConfigFormat, V2Config};
+use crate::v2::errors::{CollapseReason, EcosystemError, EcosystemResult};
+use crate::v2::game::{GameSession, SetupChoices, GOAL_DAYS};
+use crate::v2::interventions::Intervention;
+use crate::v2::montecarlo::FinalStateSnapshot;
+use crate::v2::traits::CollapseDetection;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A game decided in advance
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameScript {
+    /// Seeds the starting state and any randomness in interventions; unseeded games start from the configuration
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default = "default_goal_days")]
+    pub goal_days: usize,
+    /// Configuration file to play instead of `setup`, relative to the script
+    #[serde(default)]
+    pub config: Option<String>,
+    /// Answers to the setup prompts
+    #[serde(default)]
+    pub setup: Option<SetupChoices>,
+    /// Carried out in order after the daytime half of their day, where the game prompts
+    #[serde(default)]
+    pub actions: Vec<PlannedAction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlannedAction {
+    pub day: usize,
+    /// Intervention command, e.g. `add-water 0.5`
+    #[serde(rename = "do")]
+    pub intervention: Intervention,
+}
+
+fn default_goal_days() -> usize {
+    GOAL_DAYS
+}
+
+fn invalid(message: String) -> EcosystemError {
+    EcosystemError::ConfigurationError { message }
+}
+
+impl GameScript {
+    /// A TOML or JSON script, with `config` resolved against the script's directory
+    pub fn load(path: &str) -> EcosystemResult<Self> {
+        let format = ConfigFormat::from_path(path)?;
+        let text = std::fs::read_to_string(path).map_err(|e| invalid(format!("Could not read {}: {}", path, e)))?;
+        let mut script = Self::parse(&text, format).map_err(|e| invalid(format!("{}: {}", path, e)))?;
+        if let Some(config) = &script.config {
+            let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+            script.config = Some(directory.join(config).to_string_lossy().into_owned());
+        }
+        Ok(script)
+    }
+
+    pub fn parse(text: &str, format: ConfigFormat) -> EcosystemResult<Self> {
+        let value: serde_json::Value = match format {
+            ConfigFormat::Toml => toml::from_str(text).map_err(|e| invalid(e.to_string().trim_end().to_string()))?,
+            ConfigFormat::Json => serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?,
+        };
+        let script: GameScript = serde_path_to_error::deserialize(value)
+            .map_err(|e| invalid(format!("{}: {}", e.path(), e.inner())))?;
+        script.validate()?;
+        Ok(script)
+    }
+
+    fn validate(&self) -> EcosystemResult<()> {
+        if self.goal_days == 0 {
+            return Err(invalid("goal_days must be at least 1".to_string()));
+        }
+        if self.config.is_some() && self.setup.is_some() {
+            return Err(invalid("Give either `config` or `setup`, not both".to_string()));
+        }
+        if let Some(action) = self.actions.iter().find(|a| a.day == 0 || a.day > self.goal_days) {
+            return Err(invalid(format!("Action '{}' is planned for day {}, outside days 1 to {}",
+                                       String::from(action.intervention.clone()), action.day, self.goal_days)));
+        }
+        Ok(())
+    }
+
+    /// The bottle the script plays: its configuration file, its setup answers or the defaults
+    pub fn configuration(&self) -> EcosystemResult<V2Config> {
+        match (&self.config, &self.setup) {
+            (Some(path), _) => V2Config::load(path),
+            (None, Some(setup)) => setup.configure(),
+            (None, None) => SetupChoices::default().configure(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameResult {
+    Survived,
+    Collapsed,
+    /// The simulation itself failed; see `ScriptOutcome::error`
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionRecord {
+    pub day: usize,
+    #[serde(rename = "do")]
+    pub intervention: Intervention,
+    pub cost: u32,
+    pub effects: Vec<String>,
+    /// Why the action was not carried out, e.g. too few points left
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Everything a pipeline needs to judge a scripted game
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptOutcome {
+    pub result: GameResult,
+    pub seed: Option<u64>,
+    pub difficulty: f32,
+    pub goal_days: usize,
+    pub days_survived: usize,
+    pub half_days: usize,
+    #[serde(serialize_with = "crate::v2::export::serialize_reasons")]
+    pub collapse_reasons: Vec<CollapseReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub actions: Vec<ActionRecord>,
+    pub points_left: u32,
+    pub final_state: FinalStateSnapshot,
+}
+
+/// Play `script` on `config` to the goal or a collapse, without prompts, pauses or output
+pub fn run_script(script: &GameScript, config: V2Config) -> EcosystemResult<ScriptOutcome> {
+    let mut game = GameSession::new(config, script.seed, script.goal_days)?;
+    let mut actions = Vec::new();
+    let mut error = None;
+
+    let result = loop {
+        if let Err(e) = game.advance() {
+            error = Some(e.to_string());
+            break GameResult::Failed;
+        }
+        if game.state.is_collapsed() {
+            break GameResult::Collapsed;
+        }
+        if game.is_day() {
+            let day = game.day();
+            for planned in script.actions.iter().filter(|a| a.day == day) {
+                let record = match game.intervene(&planned.intervention) {
+                    Ok(outcome) => ActionRecord { day, intervention: outcome.intervention, cost: outcome.cost, effects: outcome.effects, error: None },
+                    Err(e) => ActionRecord { day, intervention: planned.intervention.clone(), cost: 0, effects: Vec::new(), error: Some(e.to_string()) },
+                };
+                actions.push(record);
+            }
+        }
+        if game.is_won() {
+            break GameResult::Survived;
+        }
+    };
+
+    Ok(ScriptOutcome {
+        result,
+        seed: script.seed,
+        difficulty: game.config.difficulty.level,
+        goal_days: script.goal_days,
+        days_survived: game.half_day / 2,
+        half_days: game.half_day,
+        collapse_reasons: if result == GameResult::Collapsed { game.state.collapse_reasons() } else { Vec::new() },
+        error,
+        actions,
+        points_left: game.budget.remaining(),
+        final_state: FinalStateSnapshot::of(&game.state),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+seed = 7
+goal_days = 4
+
+[setup]
+difficulty = 0.2
+soil = "Peat"
+leave_out = ["shrimp"]
+
+[[actions]]
+day = 1
+do = "add-water 0.1"
+
+[[actions]]
+day = 2
+do = "open-bottle"
+"#;
+
+    #[test]
+    fn test_scripted_game_is_repeatable() {
+        let script = GameScript::parse(SCRIPT, ConfigFormat::Toml).unwrap();
+        let config = script.configuration().unwrap();
+        assert!(!config.species.contains("shrimp"));
+
+        let first = run_script(&script, config.clone()).unwrap();
+        let second = run_script(&script, config).unwrap();
+        assert_eq!(serde_json::to_string(&first).unwrap(), serde_json::to_string(&second).unwrap());
+        // A gentle Peat bottle on this seed lasts the four days, so both actions are taken
+        assert_eq!(first.result, GameResult::Survived);
+        assert_eq!(first.half_days, 8);
+        assert_eq!(first.actions.len(), 2);
+        assert!(first.actions.iter().all(|a| a.error.is_none() && a.cost > 0));
+    }
+
+    #[test]
+    fn test_script_errors() {
+        let error = |text: &str| GameScript::parse(text, ConfigFormat::Toml).unwrap_err().to_string();
+        assert!(error("[[actions]]\nday = 1\ndo = \"feed-shrimp\"\n").contains("actions[0].do"));
+        assert!(error("goal_days = 3\n[[actions]]\nday = 5\ndo = \"open-bottle\"\n").contains("day 5"));
+        assert!(error("[setup]\ndifficulty = 0.5\ncolour = \"red\"\n").contains("setup"));
+    }
+}
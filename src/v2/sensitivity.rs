@@ -0,0 +1,363 @@
+// v2/sensitivity.rs
+// Global sensitivity analysis of survival to the simulation, environment and organism settings
+
+use crate::v2::config::{field_path, rate, V2Config, RATE_FIELDS};
+use crate::v2::errors::EcosystemResult;
+use crate::v2::montecarlo::{default_threads, run_jobs, run_to_collapse};
+use crate::v2::sampling::Dimension;
+use crate::v2::state::EcosystemStateV2;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+/// Outputs every index is computed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Survival,
+    DaysSurvived,
+}
+
+impl Output {
+    pub const ALL: [Output; 2] = [Output::Survival, Output::DaysSurvived];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Output::Survival => "survival probability",
+            Output::DaysSurvived => "days survived",
+        }
+    }
+}
+
+type Outputs = [f64; 2];
+
+#[derive(Debug, Clone)]
+pub struct SensitivityConfig {
+    /// Base sample size N of the Sobol design; costs N * (factors + 2) runs
+    pub samples: usize,
+    /// Morris trajectories; costs trajectories * (factors + 1) runs
+    pub trajectories: usize,
+    pub day_cap: usize,
+    /// Difficulty of the baseline the rate constants are varied around
+    pub difficulty: f32,
+    /// Rate constants vary by this fraction either side of the baseline
+    pub spread: f32,
+    pub seed: Option<u64>,
+    pub threads: Option<usize>,
+    /// Rows printed per table
+    pub show_top: usize,
+}
+
+impl Default for SensitivityConfig {
+    fn default() -> Self {
+        Self {
+            samples: 256,
+            trajectories: 20,
+            day_cap: 30,
+            difficulty: 0.5,
+            spread: 0.5,
+            seed: None,
+            threads: None,
+            show_top: 15,
+        }
+    }
+}
+
+/// The rate constants of `SimulationParameters`, each varied by `spread` either side of its
+/// baseline, then every setting the Monte Carlo samples over its usual range. Integration,
+/// mass-balance and collapse settings are left out: they change how the run is computed or
+/// judged, not what is being simulated
+pub fn factors(baseline: &V2Config, spread: f32) -> Vec<Dimension> {
+    let rates = RATE_FIELDS
+        .iter()
+        .filter(|path| path.starts_with("parameters."))
+        .filter(|path| !path.starts_with("parameters.integration.") && !path.starts_with("parameters.mass_balance."))
+        .filter_map(|&path| {
+            let value = rate(baseline, path)?;
+            Some(Dimension::continuous(path, value * (1.0 - spread), value * (1.0 + spread)))
+        });
+    let settings = Dimension::defaults().into_iter().filter(|dimension| dimension.name != "difficulty");
+
+    rates
+        .chain([Dimension::discrete("parameters.ph_model", 0.0, 1.0)])
+        .chain(settings)
+        .collect()
+}
+
+/// Morris elementary-effect statistics for one factor
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MorrisIndex {
+    /// Mean absolute effect: overall importance
+    pub mu_star: f64,
+    pub mu: f64,
+    /// Spread of the effects: interactions and non-linearity
+    pub sigma: f64,
+}
+
+/// Sobol variance-based indices for one factor
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SobolIndex {
+    pub first_order: f64,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FactorSensitivity {
+    pub name: &'static str,
+    pub low: f32,
+    pub high: f32,
+    /// Indexed like `Output::ALL`
+    pub morris: [MorrisIndex; 2],
+    pub sobol: [SobolIndex; 2],
+}
+
+#[derive(Debug)]
+pub struct SensitivityResults {
+    pub factors: Vec<FactorSensitivity>,
+    pub evaluations: usize,
+    pub seed: u64,
+}
+
+pub fn run_sensitivity_analysis(sa_config: &SensitivityConfig) -> EcosystemResult<SensitivityResults> {
+    let seed = sa_config.seed.unwrap_or_else(|| StdRng::from_entropy().r#gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let baseline = V2Config::with_difficulty(sa_config.difficulty)?;
+    let factors = factors(&baseline, sa_config.spread);
+    let threads = default_threads(sa_config.threads);
+
+    println!("🔬 Sensitivity analysis of {} factors at {:.0}% difficulty", factors.len(), sa_config.difficulty * 100.0);
+    println!("   Morris: {} trajectories ({} runs)", sa_config.trajectories, sa_config.trajectories * (factors.len() + 1));
+    println!("   Sobol: N = {} ({} runs)", sa_config.samples, sa_config.samples * (factors.len() + 2));
+    println!("   Seed: {} ({} threads)", seed, threads);
+
+    let model = |unit: &[f64]| simulate_point(&baseline, &factors, unit, sa_config.day_cap);
+    let morris = morris_indices(factors.len(), sa_config.trajectories, &mut rng, |points| evaluate(points, &model, threads))?;
+    let sobol = sobol_indices(factors.len(), sa_config.samples, &mut rng, |points| evaluate(points, &model, threads))?;
+
+    let evaluations = sa_config.trajectories * (factors.len() + 1) + sa_config.samples * (factors.len() + 2);
+    let factors = factors
+        .iter()
+        .enumerate()
+        .map(|(i, factor)| FactorSensitivity {
+            name: field_path(factor.name),
+            low: factor.low,
+            high: factor.high,
+            morris: morris[i],
+            sobol: sobol[i],
+        })
+        .collect();
+
+    Ok(SensitivityResults { factors, evaluations, seed })
+}
+
+/// Run the bottle with every factor set from a point in the unit hypercube. A point the
+/// bottle can't be built from is an error rather than a day-0 collapse, which would skew
+/// every index
+fn simulate_point(baseline: &V2Config, factors: &[Dimension], unit: &[f64], day_cap: usize) -> EcosystemResult<Outputs> {
+    let mut config = baseline.clone();
+    for (factor, &u) in factors.iter().zip(unit) {
+        config.set_field(field_path(factor.name), factor.value(u))?;
+    }
+
+    let mut state = EcosystemStateV2::new(&config)?;
+    let outcome = run_to_collapse(&config, &mut state, day_cap, |_, _, _| {});
+    Ok([if outcome.survived { 1.0 } else { 0.0 }, outcome.days_survived as f64])
+}
+
+/// Evaluate the model at every point, spread over worker threads
+fn evaluate(
+    points: &[Vec<f64>],
+    model: &(impl Fn(&[f64]) -> EcosystemResult<Outputs> + Sync),
+    threads: usize,
+) -> EcosystemResult<Vec<Outputs>> {
+    run_jobs(points.len(), threads, false, |point| model(&points[point]))
+}
+
+/// Morris screening with `levels` grid levels; each trajectory moves every factor once by Δ
+fn morris_indices(
+    k: usize,
+    trajectories: usize,
+    rng: &mut StdRng,
+    evaluate: impl Fn(&[Vec<f64>]) -> EcosystemResult<Vec<Outputs>>,
+) -> EcosystemResult<Vec<[MorrisIndex; 2]>> {
+    const LEVELS: usize = 4;
+    let delta = LEVELS as f64 / (2.0 * (LEVELS - 1) as f64);
+
+    // Build every trajectory first so the runs can be evaluated in one parallel batch
+    let mut points = Vec::with_capacity(trajectories * (k + 1));
+    let mut orders = Vec::with_capacity(trajectories);
+    for _ in 0..trajectories {
+        let mut x: Vec<f64> = (0..k)
+            .map(|_| rng.gen_range(0..LEVELS / 2) as f64 / (LEVELS - 1) as f64)
+            .collect();
+        let mut order: Vec<usize> = (0..k).collect();
+        order.shuffle(rng);
+
+        points.push(x.clone());
+        for &factor in &order {
+            x[factor] += delta;
+            points.push(x.clone());
+        }
+        orders.push(order);
+    }
+    let outputs = evaluate(&points)?;
+
+    let mut effects = vec![[Vec::with_capacity(trajectories), Vec::with_capacity(trajectories)]; k];
+    for (t, order) in orders.iter().enumerate() {
+        let start = t * (k + 1);
+        for (step, &factor) in order.iter().enumerate() {
+            for output in 0..2 {
+                let effect = (outputs[start + step + 1][output] - outputs[start + step][output]) / delta;
+                effects[factor][output].push(effect);
+            }
+        }
+    }
+
+    Ok(effects
+        .iter()
+        .map(|per_output| {
+            let summarise = |effects: &[f64]| {
+                let n = effects.len().max(1) as f64;
+                let mu = effects.iter().sum::<f64>() / n;
+                let mu_star = effects.iter().map(|e| e.abs()).sum::<f64>() / n;
+                let variance = effects.iter().map(|e| (e - mu).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+                MorrisIndex { mu_star, mu, sigma: variance.sqrt() }
+            };
+            [summarise(&per_output[0]), summarise(&per_output[1])]
+        })
+        .collect())
+}
+
+/// Saltelli sampling with the Saltelli (2010) first-order and Jansen total-effect estimators
+fn sobol_indices(
+    k: usize,
+    samples: usize,
+    rng: &mut StdRng,
+    evaluate: impl Fn(&[Vec<f64>]) -> EcosystemResult<Vec<Outputs>>,
+) -> EcosystemResult<Vec<[SobolIndex; 2]>> {
+    let mut draw = || -> Vec<f64> { (0..k).map(|_| rng.r#gen::<f64>()).collect() };
+    let a: Vec<Vec<f64>> = (0..samples).map(|_| draw()).collect();
+    let b: Vec<Vec<f64>> = (0..samples).map(|_| draw()).collect();
+
+    // A, B, then A with column i taken from B for every factor i
+    let mut points = Vec::with_capacity(samples * (k + 2));
+    points.extend(a.iter().cloned());
+    points.extend(b.iter().cloned());
+    for i in 0..k {
+        points.extend(a.iter().zip(&b).map(|(row_a, row_b)| {
+            let mut row = row_a.clone();
+            row[i] = row_b[i];
+            row
+        }));
+    }
+    let outputs = evaluate(&points)?;
+    let (f_a, rest) = outputs.split_at(samples);
+    let (f_b, f_ab) = rest.split_at(samples);
+
+    let n = samples.max(1) as f64;
+    let variance = |output: usize| {
+        let all = f_a.iter().chain(f_b).map(|f| f[output]);
+        let mean = all.clone().sum::<f64>() / (2.0 * n);
+        all.map(|f| (f - mean).powi(2)).sum::<f64>() / (2.0 * n - 1.0).max(1.0)
+    };
+    let variances = [variance(0), variance(1)];
+
+    Ok((0..k)
+        .map(|i| {
+            let f_abi = &f_ab[i * samples..(i + 1) * samples];
+            let index = |output: usize| {
+                if variances[output] <= f64::EPSILON {
+                    return SobolIndex::default();
+                }
+                let mut first = 0.0;
+                let mut total = 0.0;
+                for j in 0..samples {
+                    first += f_b[j][output] * (f_abi[j][output] - f_a[j][output]);
+                    total += (f_a[j][output] - f_abi[j][output]).powi(2);
+                }
+                SobolIndex {
+                    first_order: first / n / variances[output],
+                    total: total / (2.0 * n) / variances[output],
+                }
+            };
+            [index(0), index(1)]
+        })
+        .collect())
+}
+
+pub fn print_sensitivity_results(results: &SensitivityResults, show_top: usize) {
+    println!("\n🔬 SENSITIVITY ANALYSIS ({} runs, seed {})", results.evaluations, results.seed);
+    println!("==========================================");
+
+    for (o, output) in Output::ALL.iter().enumerate() {
+        let mut ranked: Vec<&FactorSensitivity> = results.factors.iter().collect();
+        ranked.sort_by(|a, b| b.morris[o].mu_star.total_cmp(&a.morris[o].mu_star));
+        println!("\n📐 Morris elementary effects on {}:", output.label());
+        println!("   {:>4}  {:<48} {:>21} {:>10} {:>10} {:>10}", "Rank", "Parameter", "Range", "μ*", "μ", "σ");
+        for (rank, factor) in ranked.iter().take(show_top).enumerate() {
+            let index = factor.morris[o];
+            let range = format!("{:.4}–{:.4}", factor.low, factor.high);
+            println!("   {:>4}  {:<48} {:>21} {:>10.3} {:>10.3} {:>10.3}", rank + 1, factor.name, range, index.mu_star, index.mu, index.sigma);
+        }
+
+        ranked.sort_by(|a, b| b.sobol[o].total.total_cmp(&a.sobol[o].total));
+        println!("\n📊 Sobol indices for {}:", output.label());
+        println!("   {:>4}  {:<48} {:>10} {:>10}", "Rank", "Parameter", "S1", "ST");
+        for (rank, factor) in ranked.iter().take(show_top).enumerate() {
+            let index = factor.sobol[o];
+            println!("   {:>4}  {:<48} {:>10.3} {:>10.3}", rank + 1, factor.name, index.first_order, index.total);
+        }
+    }
+
+    if results.factors.len() > show_top {
+        println!("\n   ({} more factors with smaller effects not shown)", results.factors.len() - show_top);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::config::environment::SoilType;
+    use crate::v2::config::parameters::PhModel;
+
+    /// y = x0 + 2 x1 on uniform inputs: S1 = ST = 0.2 and 0.8, and x2 does nothing
+    fn linear(points: &[Vec<f64>]) -> EcosystemResult<Vec<Outputs>> {
+        Ok(points.iter().map(|x| { let y = x[0] + 2.0 * x[1]; [y, y] }).collect())
+    }
+
+    #[test]
+    fn test_sobol_indices_of_linear_model() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let indices = sobol_indices(3, 4096, &mut rng, linear).unwrap();
+
+        assert!((indices[0][0].first_order - 0.2).abs() < 0.05);
+        assert!((indices[1][0].first_order - 0.8).abs() < 0.05);
+        assert!((indices[1][0].total - 0.8).abs() < 0.05);
+        assert!(indices[2][0].total.abs() < 0.01);
+    }
+
+    #[test]
+    fn test_morris_ranks_linear_model() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let indices = morris_indices(3, 10, &mut rng, linear).unwrap();
+
+        assert!((indices[0][0].mu_star - 1.0).abs() < 1e-9);
+        assert!((indices[1][0].mu_star - 2.0).abs() < 1e-9);
+        assert_eq!(indices[2][0].mu_star, 0.0);
+    }
+
+    #[test]
+    fn test_factors_cover_every_setting() {
+        let baseline = V2Config::new();
+        let factors = factors(&baseline, 0.5);
+        let mut config = baseline.clone();
+        for factor in &factors {
+            assert!(factor.low <= factor.high, "{}", factor.name);
+            config.set_field(field_path(factor.name), factor.value(1.0)).unwrap();
+        }
+        assert!(factors.iter().any(|factor| factor.name == "parameters.carbonate.soil_water_coupling"));
+        assert!(!factors.iter().any(|factor| factor.name.starts_with("parameters.integration.")));
+        assert_eq!(config.environment.soil_type, SoilType::ActivatedCharcoal);
+        assert_eq!(config.parameters.ph_model, PhModel::Carbonate);
+        assert_eq!(config.organisms.shrimp.initial_count, 8);
+    }
+}
@@ -4,84 +4,118 @@
 use crate::v2::config::V2Config;
 use crate::v2::state::EcosystemStateV2;
 use crate::v2::organisms;
-use crate::v2::environmental::*;
+use crate::v2::climate;
+use crate::v2::soil;
+use crate::v2::carbonate;
+use crate::v2::ledger;
+use crate::v2::integrator::{self, Derivatives};
+use crate::v2::config::parameters::PhModel;
 use crate::v2::errors::EcosystemResult;
+use crate::v2::types::DayPhase;
 
-/// Main simulation update function - now much cleaner and more modular
+/// Main simulation update function - advances the ecosystem by one half-day
 pub fn update_ecosystem_v2(
     config: &V2Config,
     state: &mut EcosystemStateV2,
     is_day: bool,
 ) -> EcosystemResult<()> {
-    let dt = 1.0; // Time step
+    // Daytime is stepped through dawn, noon and dusk so light follows the sun
+    for &phase in DayPhase::half_day(is_day) {
+        update_phase(config, state, phase)?;
+    }
     
-    // Update all organisms using the modular system
-    organisms::update_all_organisms(state, &config.parameters, is_day, dt)?;
+    // Check that no carbon, oxygen or nitrogen appeared or vanished
+    ledger::audit_mass_balance(state, &config.parameters.mass_balance)?;
     
-    // Update environmental parameters
-    update_environmental_parameters(state, config, dt)?;
+    Ok(())
+}
+
+/// Advance the ecosystem through a single phase of the day
+fn update_phase(
+    config: &V2Config,
+    state: &mut EcosystemStateV2,
+    phase: DayPhase,
+) -> EcosystemResult<()> {
+    state.day_phase = phase;
     
-    // Apply environmental penalties
-    organisms::apply_environmental_penalties(state)?;
+    // Integrate every process together over the phase, in half-days
+    integrator::integrate(state, config, phase.duration(), ecosystem_rates)
+}
+
+/// Rates of change of the whole ecosystem at the given state
+pub fn ecosystem_rates(state: &EcosystemStateV2, config: &V2Config) -> EcosystemResult<Derivatives> {
+    let mut rates = Derivatives::default();
     
-    // Clamp all values to valid ranges
-    state.clamp_values()?;
+    // Organisms
+    organisms::organism_rates(state, &config.parameters, state.day_phase.is_daylight(), &mut rates);
     
-    Ok(())
+    // Environmental parameters
+    environmental_rates(state, config, &mut rates);
+    
+    // Environmental penalties
+    organisms::environmental_penalty_rates(state, &mut rates);
+    
+    Ok(rates)
 }
 
-/// Update environmental parameters like pH buffering
-fn update_environmental_parameters(
-    state: &mut EcosystemStateV2,
+/// Rates of environmental parameters like climate and pH buffering
+fn environmental_rates(
+    state: &EcosystemStateV2,
     config: &V2Config,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
+    // Temperature, humidity, evaporation and condensation
+    climate::microclimate_rates(state, config, rates);
+    
+    // Soil moisture, structure and leaching
+    soil::soil_rates(state, rates);
+    
     // pH changes
-    update_ph(state, config, dt)?;
+    ph_rates(state, config, rates);
     
     // Water oxygen exchange with air
-    update_water_oxygen_exchange(state, dt)?;
-    
-    Ok(())
+    water_oxygen_exchange(state, rates);
 }
 
-/// Update soil pH based on various factors
-fn update_ph(
-    state: &mut EcosystemStateV2,
+/// Soil and water pH change under the configured pH model
+fn ph_rates(
+    state: &EcosystemStateV2,
     config: &V2Config,
-    dt: f32,
-) -> EcosystemResult<()> {
+    rates: &mut Derivatives,
+) {
     let acidification = config.parameters.environmental.ph_acidification_rate 
         * state.microbe_pop.value();
     
-    let rock_buffering = config.parameters.environmental.rock_buffer_rate 
-        * state.rocks as f32;
+    let soil_buffering = soil::ph_buffering(state);
     
-    let water_buffering = config.parameters.environmental.water_buffer_rate 
-        * state.water_liters.value();
+    let external_buffering = match config.parameters.ph_model {
+        PhModel::Linear => {
+            let rock_buffering = config.parameters.environmental.rock_buffer_rate 
+                * state.rocks as f32;
+            
+            let water_buffering = config.parameters.environmental.water_buffer_rate 
+                * state.water_liters.value();
+            
+            rock_buffering + water_buffering
+        }
+        PhModel::Carbonate => {
+            // Rocks and dissolved CO2 act on the water; the soil only drifts toward it
+            carbonate::carbonate_rates(state, &config.parameters.carbonate, rates);
+            config.parameters.carbonate.soil_water_coupling
+                * (state.water_ph.value() - state.soil_ph.value())
+        }
+    };
     
-    let ph_change = (-acidification + rock_buffering + water_buffering) * dt;
-    let new_ph = (state.soil_ph.value() + ph_change).clamp(0.0, 14.0);
-    
-    state.soil_ph = crate::v2::types::Ph::new(new_ph)?;
-    
-    Ok(())
+    rates.soil_ph += -acidification + external_buffering + soil_buffering;
 }
 
-/// Update water oxygen through surface exchange with air
-fn update_water_oxygen_exchange(
-    state: &mut EcosystemStateV2,
-    dt: f32,
-) -> EcosystemResult<()> {
-    // Oxygen exchange between air and water
+/// Water oxygen through surface exchange with air
+fn water_oxygen_exchange(state: &EcosystemStateV2, rates: &mut Derivatives) {
     let exchange_rate = 0.01; // Surface exchange rate
     let oxygen_gradient = state.air_o2.percentage() - state.water_o2.percentage();
-    let oxygen_transfer = exchange_rate * oxygen_gradient * dt;
-    
-    let new_water_oxygen = (state.water_o2.percentage() + oxygen_transfer).max(0.0);
-    state.water_o2 = crate::v2::types::Oxygen::new(new_water_oxygen)?;
-    
-    Ok(())
+    let dissolved = exchange_rate * oxygen_gradient;
+    rates.water_o2 += dissolved;
+    rates.air_o2 -= ledger::water_o2_as_air_o2(state, dissolved);
 }
 
 /// Returns true if any critical population is at or below collapse threshold
@@ -157,6 +191,23 @@ mod tests {
         // Test that update doesn't panic
         let result = update_ecosystem_v2(&config, &mut state, true);
         assert!(result.is_ok());
+        assert_eq!(state.day_phase, DayPhase::Dusk);
+    }
+    
+    #[test]
+    fn test_window_placement_drives_growth() {
+        let mut near = V2Config::new();
+        near.environment.window_proximity = 1;
+        let mut far = V2Config::new();
+        far.environment.window_proximity = 5;
+        
+        let mut near_state = EcosystemStateV2::new(&near).expect("Failed to create state");
+        let mut far_state = EcosystemStateV2::new(&far).expect("Failed to create state");
+        update_ecosystem_v2(&near, &mut near_state, true).unwrap();
+        update_ecosystem_v2(&far, &mut far_state, true).unwrap();
+        
+        assert!(near_state.plant_biomass.value() > far_state.plant_biomass.value());
+        assert!(near_state.air_o2.percentage() > far_state.air_o2.percentage());
     }
     
     #[test]
@@ -173,4 +224,51 @@ mod tests {
         
         assert!(is_ecosystem_collapsed(&state));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_ammonia_spike_is_toxic_buildup() {
+        use crate::v2::errors::CollapseReason;
+        use crate::v2::traits::CollapseDetection;
+        use crate::v2::types::Nitrogen;
+        
+        let config = V2Config::new();
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        assert!(!state.collapse_reasons().contains(&CollapseReason::ToxicBuildup));
+        
+        // A dose of ammonia far beyond what the nitrifiers can clear; nitrite backs up behind it
+        state.soil_ammonia = Nitrogen::new(12.0).unwrap();
+        let poisoned = (0..4).any(|half_day| {
+            update_ecosystem_v2(&config, &mut state, half_day % 2 == 0).unwrap();
+            state.collapse_reasons().contains(&CollapseReason::ToxicBuildup)
+        });
+        
+        assert!(poisoned);
+        assert!(is_ecosystem_collapsed(&state));
+    }
+    
+    #[test]
+    fn test_plants_and_microbes_only_bottle() {
+        use crate::v2::errors::CollapseReason;
+        use crate::v2::traits::{CollapseDetection, EcosystemDisplay};
+        
+        let mut config = V2Config::new();
+        config.organisms.worms.initial_count = 0;
+        assert!(config.validate().is_err());
+        
+        assert!(config.remove_species("worms"));
+        assert!(config.remove_species("shrimp"));
+        assert!(config.validate().is_ok());
+        
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        assert_eq!(state.shrimp_pop.value(), 0.0);
+        for _ in 0..3 {
+            update_ecosystem_v2(&config, &mut state, true).unwrap();
+            update_ecosystem_v2(&config, &mut state, false).unwrap();
+        }
+        
+        let reasons = state.collapse_reasons();
+        assert!(!reasons.contains(&CollapseReason::WormsDied));
+        assert!(!reasons.contains(&CollapseReason::ShrimpDied));
+        assert!(state.display_status().contains("Shrimp: absent"));
+    }
+}
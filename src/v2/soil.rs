@@ -0,0 +1,114 @@
+// v2/soil.rs
+// Soil moisture, structure and nutrient retention driven by the soil type
+
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::integrator::Derivatives;
+use crate::v2::ledger::soil_nitrogen_as_air_n2;
+use crate::v2::types::*;
+
+/// Moisture a balanced soil holds when the reservoir is well stocked
+const BASE_FIELD_CAPACITY: f32 = 1.5;
+
+/// Nitrogen lost per half-day from a soil with no retention at all
+const BASE_LEACHING_RATE: f32 = 0.1;
+
+/// Rate at which soil structure settles back to its natural aeration
+const COMPACTION_RATE: f32 = 0.05;
+
+/// Add the rates of soil moisture, aeration, leaching and ammonia adsorption
+pub fn soil_rates(state: &EcosystemStateV2, rates: &mut Derivatives) {
+    moisture_rate(state, rates);
+    aeration_rate(state, rates);
+    leaching_rate(state, rates);
+    adsorption_rate(state, rates);
+}
+
+/// Moisture the soil settles at for the current water reservoir
+pub fn field_capacity(state: &EcosystemStateV2) -> f32 {
+    let water = state.water_liters.value();
+    let wicking = water / (water + 0.5); // A dry reservoir can't keep the soil wet
+    BASE_FIELD_CAPACITY * state.soil_type.water_retention_modifier() * wicking
+}
+
+/// Wet soils soak up toward field capacity and drain anything above it
+fn moisture_rate(state: &EcosystemStateV2, rates: &mut Derivatives) {
+    let target = field_capacity(state);
+    rates.soil_moisture += state.soil_type.drainage_rate() * (target - state.soil_moisture.value());
+}
+
+/// Soil slowly compacts back to its natural structure, faster when waterlogged
+fn aeration_rate(state: &EcosystemStateV2, rates: &mut Derivatives) {
+    let natural = state.soil_type.aeration_modifier();
+    let waterlogging = (state.soil_moisture.value() / field_capacity(state).max(0.01) - 1.0).max(0.0);
+    let rate = COMPACTION_RATE * (1.0 + waterlogging);
+    rates.soil_aeration -= (state.soil_aeration.value() - natural) * rate;
+}
+
+/// Water percolating through poorly retaining soils carries nitrate and nitrite down into
+/// the waterlogged drainage layer, where it is denitrified; ammonium is held on soil particles
+fn leaching_rate(state: &EcosystemStateV2, rates: &mut Derivatives) {
+    let retention = state.soil_type.nitrogen_retention();
+    let flow = (state.soil_moisture.value() / BASE_FIELD_CAPACITY).min(1.5);
+    let loss = (1.0 - retention) * BASE_LEACHING_RATE * flow;
+    let nitrate = state.soil_nitrate.value() * loss;
+    let nitrite = state.soil_nitrite.value() * loss;
+    rates.soil_nitrate -= nitrate;
+    rates.soil_nitrite -= nitrite;
+    rates.air_n2 += soil_nitrogen_as_air_n2(state, nitrate + nitrite);
+}
+
+/// Adsorbent soils lock dissolved ammonia away where it can't poison anything
+fn adsorption_rate(state: &EcosystemStateV2, rates: &mut Derivatives) {
+    let adsorbed = state.soil_ammonia.value() * state.soil_type.ammonia_adsorption();
+    rates.soil_ammonia -= adsorbed;
+    rates.adsorbed_ammonia += adsorbed;
+}
+
+/// Pull applied to soil pH by the soil's own buffering capacity
+pub fn ph_buffering(state: &EcosystemStateV2) -> f32 {
+    state.soil_type.ph_buffering() * (state.soil_type.native_ph() - state.soil_ph.value())
+}
+
+/// Microbes and aeration: an airless soil suffocates them
+pub fn aeration_efficiency(aeration: Aeration) -> f32 {
+    0.5 + 0.5 * aeration.value().min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::config::V2Config;
+    use crate::v2::config::environment::SoilType;
+
+    fn run_soil(soil_type: SoilType, half_days: usize) -> EcosystemStateV2 {
+        let mut config = V2Config::new();
+        config.environment.soil_type = soil_type;
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        for _ in 0..half_days {
+            let mut rates = Derivatives::default();
+            soil_rates(&state, &mut rates);
+            Derivatives::from_state(&state).scaled_add(1.0, &rates).write_to(&mut state).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_sand_drains_and_leaches_more_than_clay() {
+        let sand = run_soil(SoilType::Sand, 10);
+        let clay = run_soil(SoilType::Clay, 10);
+
+        assert!(sand.soil_moisture.value() < clay.soil_moisture.value());
+        assert!(sand.soil_nitrate.value() < clay.soil_nitrate.value());
+    }
+
+    #[test]
+    fn test_peat_buffers_toward_acidic() {
+        let config = V2Config::new();
+        let mut state = EcosystemStateV2::new(&config).expect("Failed to create state");
+        state.soil_type = SoilType::Peat;
+        assert!(ph_buffering(&state) < 0.0);
+
+        state.soil_type = SoilType::ActivatedCharcoal;
+        assert!(ph_buffering(&state) > 0.0);
+    }
+}
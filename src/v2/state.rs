@@ -4,37 +4,64 @@
 use crate::v2::types::*;
 use crate::v2::traits::*;
 use crate::v2::errors::{EcosystemResult, CollapseReason};
-use crate::v2::config::environment::EnvironmentConfig;
+use crate::v2::environmental::{nitrogen_toxicity, LETHAL_TOXICITY, STRESSFUL_TOXICITY};
+use crate::v2::config::environment::SoilType;
+use crate::v2::config::parameters::{CollapseCriteria, PhModel};
+use crate::v2::carbonate::{equilibrium_water, solve_ph};
+use crate::v2::ledger::{element_totals, MassLedger};
+use crate::v2::organisms::OrganismRegistry;
+use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EcosystemStateV2 {
     pub plant_biomass: Biomass,
     pub microbe_pop: Population,
     pub worm_pop: Population,
     pub shrimp_pop: Population,
-    pub soil_nitrogen: Nitrogen,
+    pub soil_ammonia: Nitrogen,
+    pub soil_nitrite: Nitrogen,
+    pub soil_nitrate: Nitrogen,
     pub soil_ph: Ph,
     pub soil_moisture: Moisture,
     pub soil_aeration: Aeration,
     pub detritus: Detritus,
     pub water_liters: WaterVolume,
     pub water_o2: Oxygen,
+    pub water_ph: Ph,
+    pub water_dic: f32,        // Dissolved inorganic carbon, mmol/L
+    pub water_alkalinity: f32, // meq/L
     pub air_n2: Nitrogen,
     pub air_o2: Oxygen,
     pub air_co2: CarbonDioxide,
     pub temperature: Temperature,
     pub humidity: Humidity,
     pub rocks: usize,
+    pub soil_type: SoilType,
+    pub window_proximity: u8,
+    pub day_phase: DayPhase,
+    pub ledger: MassLedger,
+    pub species: OrganismRegistry,
+    pub collapse_criteria: CollapseCriteria,
 }
 
 impl EcosystemStateV2 {
     #[allow(dead_code)]
     pub fn new(config: &crate::v2::config::V2Config) -> EcosystemResult<Self> {
+        Self::configured(config)?
+            .without_absent_species()?
+            .with_equilibrium_water(config)
+    }
+
+    /// The bottle exactly as configured, before absent species and the water are settled
+    fn configured(config: &crate::v2::config::V2Config) -> EcosystemResult<Self> {
         Ok(Self {
             plant_biomass: Biomass::new(config.organisms.plants.initial_biomass)?,
             microbe_pop: Population::new(config.organisms.microbes.initial_count as f32)?,
             worm_pop: Population::new(config.organisms.worms.initial_count as f32)?,
             shrimp_pop: Population::new(config.organisms.shrimp.initial_count as f32)?,
-            soil_nitrogen: Nitrogen::new(1.0)?,
+            soil_ammonia: Nitrogen::new(0.1)?,
+            soil_nitrite: Nitrogen::new(0.0)?,
+            soil_nitrate: Nitrogen::new(0.9)?,
             soil_ph: Ph::new(7.0)?,
             soil_moisture: Moisture::new(config.environment.water_volume.value())?,
             soil_aeration: Aeration::new(1.0)?,
@@ -47,60 +74,121 @@ impl EcosystemStateV2 {
             temperature: config.environment.initial_temperature,
             humidity: config.environment.initial_humidity,
             rocks: config.environment.rocks,
+            soil_type: config.environment.soil_type,
+            window_proximity: config.environment.window_proximity,
+            day_phase: DayPhase::Dawn,
+            water_ph: Ph::new(7.0)?,
+            water_dic: 0.0,
+            water_alkalinity: 0.0,
+            ledger: MassLedger::default(),
+            species: config.species.clone(),
+            collapse_criteria: config.parameters.collapse,
         })
     }
     
+    /// The configured bottle with randomized starting chemistry; stocking and climate come from `config`
     pub fn new_with_seed(config: &crate::v2::config::V2Config, seed: u64) -> EcosystemResult<Self> {
         use rand::{Rng, SeedableRng};
         use rand::rngs::StdRng;
         let mut rng = StdRng::seed_from_u64(seed);
-        Ok(Self {
-            plant_biomass: Biomass::new(config.organisms.plants.initial_biomass)?,
-            microbe_pop: Population::new(rng.gen_range(500.0..=2000.0))?,
-            worm_pop: Population::new(rng.gen_range(1.0..=10.0))?,
-            shrimp_pop: Population::new(rng.gen_range(1.0..=5.0))?,
-            soil_nitrogen: Nitrogen::new(rng.gen_range(0.5..=2.0))?,
+        let water = config.environment.water_volume.value();
+        let soil_nitrogen = rng.gen_range(0.5..=2.0);
+        Self {
+            soil_ammonia: Nitrogen::new(soil_nitrogen * 0.1)?,
+            soil_nitrite: Nitrogen::new(0.0)?,
+            soil_nitrate: Nitrogen::new(soil_nitrogen * 0.9)?,
             soil_ph: Ph::new(rng.gen_range(5.5..=8.5))?,
-            soil_moisture: Moisture::new(rng.gen_range(0.2..=config.environment.water_volume.value()))?,
+            soil_moisture: Moisture::new(rng.gen_range(water.min(0.2)..=water))?,
             soil_aeration: Aeration::new(rng.gen_range(0.5..=2.0))?,
             detritus: Detritus::new(rng.gen_range(0.1..=2.0))?,
-            water_liters: config.environment.water_volume,
             water_o2: Oxygen::new(rng.gen_range(6.0..=10.0))?,
-            air_n2: Nitrogen::new(78.0)?,
-            air_o2: Oxygen::new(21.0)?,
-            air_co2: CarbonDioxide::new(0.04)?,
-            temperature: Temperature::new(rng.gen_range(18.0..=28.0))?,
-            humidity: Humidity::new(rng.gen_range(40.0..=80.0))?,
-            rocks: config.environment.rocks,
-        })
+            ..Self::configured(config)?
+        }
+        .without_absent_species()?
+        .with_equilibrium_water(config)
     }
     
-    pub fn light_level(&self) -> f32 {
-        // This should be calculated based on window proximity from config
-        // For now, default to a medium light level
-        4.0
+    /// Species left out of the bottle start with nothing
+    fn without_absent_species(mut self) -> EcosystemResult<Self> {
+        if !self.species.contains("plants") {
+            self.plant_biomass = Biomass::new(0.0)?;
+        }
+        if !self.species.contains("microbes") {
+            self.microbe_pop = Population::new(0.0)?;
+        }
+        if !self.species.contains("worms") {
+            self.worm_pop = Population::new(0.0)?;
+        }
+        if !self.species.contains("shrimp") {
+            self.shrimp_pop = Population::new(0.0)?;
+        }
+        Ok(self)
     }
     
-    pub fn clamp_values(&mut self) -> EcosystemResult<()> {
-        // Ensure all values are within valid ranges
-        // Most clamping is handled by the type system now
-        // Just update air composition
-        let total_air = self.air_o2.percentage() + self.air_co2.value();
-        let remaining_n2 = (100.0 - total_air).max(0.0);
-        self.air_n2 = Nitrogen::new(remaining_n2)?;
+    /// Population of a species for display, or "absent" if it isn't in the bottle
+    fn describe_population(&self, species: &str, value: f32, precision: usize, unit: &str) -> String {
+        if self.species.contains(species) {
+            format!("{:.*}{}", precision, value, unit)
+        } else {
+            "absent".to_string()
+        }
+    }
+    
+    /// Start the water column settled against the bottle air
+    fn with_equilibrium_water(mut self, config: &crate::v2::config::V2Config) -> EcosystemResult<Self> {
+        let water = equilibrium_water(
+            config.parameters.carbonate.initial_alkalinity,
+            self.air_co2,
+            self.temperature.celsius(),
+        );
+        self.water_dic = water.dic;
+        self.water_alkalinity = water.alkalinity;
+        self.water_ph = match config.parameters.ph_model {
+            PhModel::Linear => self.soil_ph,
+            PhModel::Carbonate => Ph::new(water.ph)?,
+        };
+        self.ledger = MassLedger::new(element_totals(&self));
+        Ok(self)
+    }
+    
+    /// Recompute water pH, which follows from the other state rather than being integrated
+    pub fn refresh_water_ph(&mut self, config: &crate::v2::config::V2Config) -> EcosystemResult<()> {
+        self.water_ph = match config.parameters.ph_model {
+            PhModel::Linear => self.soil_ph,
+            PhModel::Carbonate if self.water_liters.value() > 0.01 => {
+                Ph::new(solve_ph(self.water_dic, self.water_alkalinity, self.temperature.celsius()))?
+            }
+            PhModel::Carbonate => self.water_ph,
+        };
         Ok(())
     }
+    
+    /// Total mineral nitrogen across the ammonia, nitrite and nitrate pools
+    pub fn soil_nitrogen(&self) -> f32 {
+        self.soil_ammonia.value() + self.soil_nitrite.value() + self.soil_nitrate.value()
+    }
+    
+    /// Combined ammonia and nitrite toxicity index; free ammonia follows the water pH
+    pub fn toxicity(&self) -> f32 {
+        nitrogen_toxicity(self.soil_ammonia, self.soil_nitrite, self.water_ph, self.temperature)
+    }
+    
+    /// Light reaching the plants given the bottle's placement and the time of day
+    pub fn light_level(&self) -> f32 {
+        crate::v2::environmental::light_level(self.window_proximity, self.day_phase)
+    }
+
 }
 
 // Implement traits for EcosystemStateV2
 impl EcosystemDisplay for EcosystemStateV2 {
     fn display_status(&self) -> String {
         format!(
-            "Plants: {:.1} | Microbes: {:.0} | Worms: {:.0} | Shrimp: {:.0} | pH: {:.1} | O2: {:.1}% | Temp: {:.1}°C",
-            self.plant_biomass.value(),
-            self.microbe_pop.value(),
-            self.worm_pop.value(),
-            self.shrimp_pop.value(),
+            "Plants: {} | Microbes: {} | Worms: {} | Shrimp: {} | pH: {:.1} | O2: {:.1}% | Temp: {:.1}°C",
+            self.describe_population("plants", self.plant_biomass.value(), 1, ""),
+            self.describe_population("microbes", self.microbe_pop.value(), 0, ""),
+            self.describe_population("worms", self.worm_pop.value(), 0, ""),
+            self.describe_population("shrimp", self.shrimp_pop.value(), 0, ""),
             self.soil_ph.value(),
             self.air_o2.percentage(),
             self.temperature.celsius()
@@ -118,13 +206,14 @@ impl EcosystemDisplay for EcosystemStateV2 {
     fn display_detailed(&self) -> String {
         format!(
             "=== Ecosystem State ===\n\
-             Plants: {:.2} kg biomass\n\
-             Microbes: {:.0} population\n\
-             Worms: {:.0} population\n\
-             Shrimp: {:.0} population\n\
+             Plants: {}\n\
+             Microbes: {}\n\
+             Worms: {}\n\
+             Shrimp: {}\n\
              \n\
-             Soil:\n\
-             - Nitrogen: {:.2}\n\
+             Soil ({}):\n\
+             - Nitrogen: {:.2} (NH3/NH4+ {:.2}, NO2- {:.2}, NO3- {:.2})\n\
+             - Toxicity: {:.2}\n\
              - pH: {:.2}\n\
              - Moisture: {:.2}\n\
              - Aeration: {:.2}\n\
@@ -133,6 +222,7 @@ impl EcosystemDisplay for EcosystemStateV2 {
              Water:\n\
              - Volume: {:.2} L\n\
              - Oxygen: {:.1}%\n\
+             - pH: {:.2} (DIC {:.2} mmol/L, alkalinity {:.2} meq/L)\n\
              \n\
              Air:\n\
              - Nitrogen: {:.1}%\n\
@@ -141,25 +231,40 @@ impl EcosystemDisplay for EcosystemStateV2 {
              \n\
              Environment:\n\
              - Temperature: {:.1}°C\n\
-             - Humidity: {:.1}%\n\
-             - Rocks: {}\n",
-            self.plant_biomass.value(),
-            self.microbe_pop.value(),
-            self.worm_pop.value(),
-            self.shrimp_pop.value(),
-            self.soil_nitrogen.value(),
+             - Humidity: {:.1}% (dew point {:.1}°C)\n\
+             - Rocks: {}\n\
+             - Window proximity: {} ({}, light {:.1})\n\
+             - Mass balance drift: {}\n",
+            self.describe_population("plants", self.plant_biomass.value(), 2, " kg biomass"),
+            self.describe_population("microbes", self.microbe_pop.value(), 0, " population"),
+            self.describe_population("worms", self.worm_pop.value(), 0, " population"),
+            self.describe_population("shrimp", self.shrimp_pop.value(), 0, " population"),
+            self.soil_type,
+            self.soil_nitrogen(),
+            self.soil_ammonia.value(),
+            self.soil_nitrite.value(),
+            self.soil_nitrate.value(),
+            self.toxicity(),
             self.soil_ph.value(),
             self.soil_moisture.value(),
             self.soil_aeration.value(),
             self.detritus.value(),
             self.water_liters.value(),
             self.water_o2.percentage(),
+            self.water_ph.value(),
+            self.water_dic,
+            self.water_alkalinity,
             self.air_n2.value(),
             self.air_o2.percentage(),
             self.air_co2.value(),
             self.temperature.celsius(),
             self.humidity.percentage(),
-            self.rocks
+            crate::v2::climate::dew_point(self.temperature, self.humidity),
+            self.rocks,
+            self.window_proximity,
+            self.day_phase,
+            self.light_level(),
+            self.ledger.last_report().map_or("not yet audited".to_string(), |report| report.to_string())
         )
     }
 }
@@ -189,65 +294,66 @@ impl EcosystemValidation for EcosystemStateV2 {
             warnings.push("Soil pH outside optimal range".to_string());
         }
         
-        if self.plant_biomass.value() < 0.1 {
+        if self.water_ph.value() < 6.0 || self.water_ph.value() > 8.5 {
+            warnings.push("Water pH outside optimal range".to_string());
+        }
+        
+        if self.species.contains("plants") && self.plant_biomass.value() < 0.1 {
             warnings.push("Plant biomass very low".to_string());
         }
         
-        if self.microbe_pop.value() < 100.0 {
+        if self.species.contains("microbes") && self.microbe_pop.value() < 100.0 {
             warnings.push("Microbe population critically low".to_string());
         }
         
+        if self.toxicity() > STRESSFUL_TOXICITY {
+            warnings.push("Ammonia/nitrite levels are harming animals".to_string());
+        }
+        
         warnings
     }
 }
 
 impl CollapseDetection for EcosystemStateV2 {
     fn is_collapsed(&self) -> bool {
-        self.plant_biomass.is_collapsed() ||
-        self.microbe_pop.is_collapsed() ||
-        self.worm_pop.is_collapsed() ||
-        self.shrimp_pop.is_collapsed()
+        !self.species.failed_species(self, self.collapse_criteria).is_empty() ||
+        self.toxicity() >= LETHAL_TOXICITY
     }
     
     fn collapse_risk(&self) -> f32 {
-        let mut risk_factors = Vec::new();
-        
         // Population risks
-        if self.plant_biomass.value() < 0.1 { risk_factors.push(0.8); }
-        if self.microbe_pop.value() < 100.0 { risk_factors.push(0.9); }
-        if self.worm_pop.value() < 1.0 { risk_factors.push(0.6); }
-        if self.shrimp_pop.value() < 1.0 { risk_factors.push(0.5); }
+        let mut risk_factors: Vec<f32> = self.species
+            .iter()
+            .map(|organism| organism.collapse_risk(self))
+            .collect();
         
         // Environmental risks
         if self.air_o2.is_dangerously_low() { risk_factors.push(0.9); }
         if self.soil_ph.value() < 5.5 || self.soil_ph.value() > 9.0 { risk_factors.push(0.8); }
         if !self.temperature.is_optimal() { risk_factors.push(0.3); }
+        if self.toxicity() > STRESSFUL_TOXICITY { risk_factors.push(0.7); }
         
         // Calculate overall risk (max of individual risks, but capped)
         risk_factors.iter().fold(0.0, |acc, &risk| acc.max(risk))
     }
     
     fn collapse_reasons(&self) -> Vec<CollapseReason> {
-        let mut reasons = Vec::new();
+        let mut reasons: Vec<CollapseReason> = self.species
+            .failed_species(self, self.collapse_criteria)
+            .into_iter()
+            .map(|organism| organism.collapse_reason())
+            .collect();
         
-        if self.plant_biomass.is_collapsed() {
-            reasons.push(CollapseReason::PlantsDied);
-        }
-        if self.microbe_pop.is_collapsed() {
-            reasons.push(CollapseReason::MicrobesDied);
-        }
-        if self.worm_pop.is_collapsed() {
-            reasons.push(CollapseReason::WormsDied);
-        }
-        if self.shrimp_pop.is_collapsed() {
-            reasons.push(CollapseReason::ShrimpDied);
-        }
         if self.air_o2.is_dangerously_low() {
             reasons.push(CollapseReason::OxygenDepletion);
         }
-        if self.soil_ph.value() < 4.0 || self.soil_ph.value() > 10.0 {
+        if self.soil_ph.value() < 4.0 || self.soil_ph.value() > 10.0
+            || self.water_ph.value() < 4.0 || self.water_ph.value() > 10.0 {
             reasons.push(CollapseReason::PhImbalance);
         }
+        if self.toxicity() >= LETHAL_TOXICITY {
+            reasons.push(CollapseReason::ToxicBuildup);
+        }
         
         reasons
     }
@@ -255,18 +361,33 @@ impl CollapseDetection for EcosystemStateV2 {
 
 impl EcosystemMonitoring for EcosystemStateV2 {
     fn key_metrics(&self) -> Vec<(String, f32)> {
-        vec![
-            ("Plant Biomass".to_string(), self.plant_biomass.value()),
-            ("Microbe Population".to_string(), self.microbe_pop.value()),
-            ("Worm Population".to_string(), self.worm_pop.value()),
-            ("Shrimp Population".to_string(), self.shrimp_pop.value()),
+        let populations = [
+            ("plants", "Plant Biomass", self.plant_biomass.value()),
+            ("microbes", "Microbe Population", self.microbe_pop.value()),
+            ("worms", "Worm Population", self.worm_pop.value()),
+            ("shrimp", "Shrimp Population", self.shrimp_pop.value()),
+        ];
+        let mut metrics: Vec<(String, f32)> = populations
+            .into_iter()
+            .filter(|(species, _, _)| self.species.contains(species))
+            .map(|(_, label, value)| (label.to_string(), value))
+            .collect();
+        
+        metrics.extend([
             ("Soil pH".to_string(), self.soil_ph.value()),
+            ("Water pH".to_string(), self.water_ph.value()),
             ("Air Oxygen".to_string(), self.air_o2.percentage()),
             ("Temperature".to_string(), self.temperature.celsius()),
             ("Humidity".to_string(), self.humidity.percentage()),
-            ("Soil Nitrogen".to_string(), self.soil_nitrogen.value()),
+            ("Soil Nitrogen".to_string(), self.soil_nitrogen()),
+            ("Soil Ammonia".to_string(), self.soil_ammonia.value()),
+            ("Soil Nitrite".to_string(), self.soil_nitrite.value()),
+            ("Soil Nitrate".to_string(), self.soil_nitrate.value()),
+            ("Toxicity".to_string(), self.toxicity()),
             ("Water Oxygen".to_string(), self.water_o2.percentage()),
-        ]
+            ("Light Level".to_string(), self.light_level()),
+        ]);
+        metrics
     }
     
     fn trend_indicators(&self) -> Vec<TrendIndicator> {
@@ -298,7 +419,17 @@ impl EcosystemMonitoring for EcosystemStateV2 {
             });
         }
         
-        if self.plant_biomass.value() < 0.1 {
+        if self.toxicity() > STRESSFUL_TOXICITY {
+            alerts.push(AlertCondition {
+                severity: AlertSeverity::Critical,
+                message: "Ammonia/nitrite building up".to_string(),
+                parameter: "toxicity".to_string(),
+                current_value: self.toxicity(),
+                threshold: STRESSFUL_TOXICITY,
+            });
+        }
+        
+        if self.species.contains("plants") && self.plant_biomass.value() < 0.1 {
             alerts.push(AlertCondition {
                 severity: AlertSeverity::Critical,
                 message: "Plant biomass very low".to_string(),
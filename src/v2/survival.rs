@@ -0,0 +1,535 @@
+// v2/survival.rs
+// Kaplan–Meier survival analysis of Monte Carlo batches
+
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::export::csv_field;
+use crate::v2::montecarlo::SimulationResult;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// z for a two-sided 95% interval
+const Z_95: f64 = 1.959964;
+
+/// When a run left the study: collapsed on `day`, or still going after `day` days
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Observation {
+    pub day: usize,
+    pub collapsed: bool,
+}
+
+impl Observation {
+    /// A collapse during day d + 1 of a run that completed d days is an event on day d + 1.
+    /// Runs that reached the day cap, or stopped on a simulation error, are right-censored
+    pub fn from_result(result: &SimulationResult) -> Self {
+        let collapsed = !result.survived && result.error.is_none() && !result.collapse_reasons.is_empty();
+        Self {
+            day: if collapsed { result.days_survived + 1 } else { result.days_survived },
+            collapsed,
+        }
+    }
+}
+
+/// One day of the Kaplan–Meier curve
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurvivalStep {
+    pub day: usize,
+    pub at_risk: usize,
+    pub collapses: usize,
+    pub censored: usize,
+    /// Probability of still being alive at the end of `day`
+    pub survival: f64,
+    /// Greenwood standard error of `survival`
+    pub std_error: f64,
+    /// 95% log-log confidence interval
+    pub ci_low: f64,
+    pub ci_high: f64,
+    /// Fraction of the runs alive at the start of the day that collapse during it
+    pub hazard: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KaplanMeier {
+    pub runs: usize,
+    pub collapses: usize,
+    /// One step per day from day 1 to the last observed day
+    pub steps: Vec<SurvivalStep>,
+}
+
+/// Median days to collapse with its 95% confidence interval; `None` when not reached
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MedianSurvival {
+    pub median: Option<usize>,
+    pub ci_low: Option<usize>,
+    pub ci_high: Option<usize>,
+}
+
+impl KaplanMeier {
+    pub fn estimate(observations: &[Observation]) -> Self {
+        let last_day = observations.iter().map(|o| o.day).max().unwrap_or(0);
+        let mut collapses = vec![0; last_day + 1];
+        let mut censored = vec![0; last_day + 1];
+        for observation in observations {
+            if observation.collapsed {
+                collapses[observation.day] += 1;
+            } else {
+                censored[observation.day] += 1;
+            }
+        }
+
+        let mut at_risk = observations.iter().filter(|o| o.day >= 1).count();
+        let mut survival = 1.0;
+        let mut greenwood = 0.0;
+        let mut steps = Vec::with_capacity(last_day);
+        for day in 1..=last_day {
+            let (d, n) = (collapses[day], at_risk);
+            let hazard = if n > 0 { d as f64 / n as f64 } else { 0.0 };
+            survival *= 1.0 - hazard;
+            if d > 0 && n > d {
+                greenwood += d as f64 / (n as f64 * (n - d) as f64);
+            }
+            let (ci_low, ci_high) = log_log_interval(survival, greenwood);
+
+            steps.push(SurvivalStep {
+                day,
+                at_risk: n,
+                collapses: d,
+                censored: censored[day],
+                survival,
+                std_error: survival * greenwood.sqrt(),
+                ci_low,
+                ci_high,
+                hazard,
+            });
+            at_risk -= d + censored[day];
+        }
+
+        Self {
+            runs: observations.len(),
+            collapses: observations.iter().filter(|o| o.collapsed).count(),
+            steps,
+        }
+    }
+
+    pub fn from_results<'a>(results: impl IntoIterator<Item = &'a SimulationResult>) -> Self {
+        let observations: Vec<_> = results.into_iter().map(Observation::from_result).collect();
+        Self::estimate(&observations)
+    }
+
+    /// Survival at the end of `day`
+    pub fn survival_at(&self, day: usize) -> f64 {
+        self.steps.iter().take_while(|s| s.day <= day).last().map_or(1.0, |s| s.survival)
+    }
+
+    /// First day the curve reaches 0.5, bounded by the days the interval's limits reach it
+    pub fn median(&self) -> MedianSurvival {
+        let first_day = |value: fn(&SurvivalStep) -> f64| self.steps.iter().find(|s| value(s) <= 0.5).map(|s| s.day);
+        MedianSurvival {
+            median: first_day(|s| s.survival),
+            ci_low: first_day(|s| s.ci_low),
+            ci_high: first_day(|s| s.ci_high),
+        }
+    }
+
+    /// Mean daily hazard over the days anyone was at risk
+    pub fn mean_hazard(&self) -> f64 {
+        let days_at_risk: usize = self.steps.iter().map(|s| s.at_risk).sum();
+        if days_at_risk == 0 {
+            0.0
+        } else {
+            self.collapses as f64 / days_at_risk as f64
+        }
+    }
+}
+
+/// Kalbfleisch–Prentice interval, which stays inside [0, 1]
+fn log_log_interval(survival: f64, greenwood: f64) -> (f64, f64) {
+    if survival <= 0.0 || survival >= 1.0 || greenwood <= 0.0 {
+        return (survival, survival);
+    }
+    let log_survival = survival.ln();
+    let se = greenwood.sqrt() / log_survival.abs();
+    (
+        survival.powf((Z_95 * se).exp()),
+        survival.powf((-Z_95 * se).exp()),
+    )
+}
+
+/// How runs are split into groups for comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// 10%-wide difficulty bands
+    Difficulty,
+    SoilType,
+    WindowProximity,
+    Rocks,
+}
+
+impl Grouping {
+    fn key(&self, result: &SimulationResult) -> String {
+        let snapshot = &result.config_snapshot;
+        match self {
+            Grouping::Difficulty => {
+                let band = ((snapshot.difficulty * 10.0).floor() as usize).min(9) * 10;
+                format!("{}–{}%", band, band + 10)
+            }
+            Grouping::SoilType => snapshot.soil_type.to_string(),
+            Grouping::WindowProximity => format!("window {}", snapshot.window_proximity),
+            Grouping::Rocks => format!("{} rocks", snapshot.rocks),
+        }
+    }
+}
+
+impl FromStr for Grouping {
+    type Err = EcosystemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "difficulty" => Ok(Grouping::Difficulty),
+            "soil" | "soil-type" => Ok(Grouping::SoilType),
+            "window" | "window-proximity" => Ok(Grouping::WindowProximity),
+            "rocks" => Ok(Grouping::Rocks),
+            _ => Err(EcosystemError::ConfigurationError {
+                message: format!("Unknown grouping '{}' (expected difficulty, soil, window or rocks)", s),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Grouping::Difficulty => write!(f, "difficulty"),
+            Grouping::SoilType => write!(f, "soil type"),
+            Grouping::WindowProximity => write!(f, "window proximity"),
+            Grouping::Rocks => write!(f, "rocks"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SurvivalGroup {
+    pub label: String,
+    pub curve: KaplanMeier,
+    observations: Vec<Observation>,
+}
+
+/// Result of a log-rank test that all groups share one survival curve
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRankTest {
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    pub p_value: f64,
+    /// Observed and expected collapses per group, in group order
+    pub observed: Vec<usize>,
+    pub expected: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GroupComparison {
+    pub grouping: Grouping,
+    pub groups: Vec<SurvivalGroup>,
+    /// `None` with fewer than two groups
+    pub log_rank: Option<LogRankTest>,
+}
+
+pub fn compare_groups(results: &[SimulationResult], grouping: Grouping) -> GroupComparison {
+    let mut by_key: BTreeMap<String, Vec<Observation>> = BTreeMap::new();
+    for result in results {
+        by_key.entry(grouping.key(result)).or_default().push(Observation::from_result(result));
+    }
+
+    let groups: Vec<SurvivalGroup> = by_key
+        .into_iter()
+        .map(|(label, observations)| SurvivalGroup {
+            label,
+            curve: KaplanMeier::estimate(&observations),
+            observations,
+        })
+        .collect();
+    let samples: Vec<&[Observation]> = groups.iter().map(|g| g.observations.as_slice()).collect();
+    let log_rank = log_rank_test(&samples);
+
+    GroupComparison { grouping, groups, log_rank }
+}
+
+pub fn log_rank_test(groups: &[&[Observation]]) -> Option<LogRankTest> {
+    let g = groups.len();
+    if g < 2 {
+        return None;
+    }
+    let last_day = groups.iter().flat_map(|o| o.iter()).map(|o| o.day).max().unwrap_or(0);
+
+    let mut observed = vec![0usize; g];
+    let mut expected = vec![0.0; g];
+    let mut covariance = vec![vec![0.0; g]; g];
+    for day in 1..=last_day {
+        let at_risk: Vec<f64> = groups.iter().map(|o| o.iter().filter(|o| o.day >= day).count() as f64).collect();
+        let collapses: Vec<usize> = groups.iter().map(|o| o.iter().filter(|o| o.collapsed && o.day == day).count()).collect();
+        let n: f64 = at_risk.iter().sum();
+        let d = collapses.iter().sum::<usize>() as f64;
+        if d == 0.0 || n < 1.0 {
+            continue;
+        }
+
+        let tie_correction = if n > 1.0 { (n - d) / (n - 1.0) } else { 0.0 };
+        for i in 0..g {
+            observed[i] += collapses[i];
+            expected[i] += d * at_risk[i] / n;
+            for j in 0..g {
+                let share = if i == j { 1.0 } else { 0.0 };
+                covariance[i][j] += d * at_risk[i] / n * (share - at_risk[j] / n) * tie_correction;
+            }
+        }
+    }
+
+    // Drop the last group: the deviations sum to zero, so the full matrix is singular
+    let deviations: Vec<f64> = (0..g - 1).map(|i| observed[i] as f64 - expected[i]).collect();
+    let reduced: Vec<Vec<f64>> = covariance[..g - 1].iter().map(|row| row[..g - 1].to_vec()).collect();
+    let chi_square = solve(reduced, deviations.clone())
+        .map(|x| x.iter().zip(&deviations).map(|(a, b)| a * b).sum::<f64>())
+        .unwrap_or(0.0)
+        .max(0.0);
+    let degrees_of_freedom = g - 1;
+
+    Some(LogRankTest {
+        chi_square,
+        degrees_of_freedom,
+        p_value: chi_square_p_value(chi_square, degrees_of_freedom),
+        observed,
+        expected,
+    })
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting; `None` if singular
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            b[col + 1 + offset] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Upper tail of the chi-square distribution, via the regularized incomplete gamma function
+fn chi_square_p_value(chi_square: f64, degrees_of_freedom: usize) -> f64 {
+    if chi_square <= 0.0 || degrees_of_freedom == 0 {
+        return 1.0;
+    }
+    let a = degrees_of_freedom as f64 / 2.0;
+    let x = chi_square / 2.0;
+    let prefix = (a * x.ln() - x - ln_gamma(a)).exp();
+
+    if x < a + 1.0 {
+        // Series for the lower tail
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..500 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term < sum * 1e-14 {
+                break;
+            }
+        }
+        (1.0 - prefix * sum).clamp(0.0, 1.0)
+    } else {
+        // Lentz continued fraction for the upper tail
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..500 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-14 {
+                break;
+            }
+        }
+        (prefix * h).clamp(0.0, 1.0)
+    }
+}
+
+/// Lanczos approximation of ln Γ(x)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let mut series = 1.000000000190015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+fn format_day(day: Option<usize>) -> String {
+    day.map_or_else(|| "not reached".to_string(), |d| d.to_string())
+}
+
+impl fmt::Display for MedianSurvival {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (95% CI {} – {})", format_day(self.median), format_day(self.ci_low), format_day(self.ci_high))
+    }
+}
+
+/// Survival curve as bars: solid up to the interval's lower limit, shaded across the interval
+pub fn print_survival_curve(curve: &KaplanMeier) {
+    const WIDTH: f64 = 40.0;
+    println!("\n📉 Survival Curve (Kaplan–Meier, runs reaching the day cap are censored):");
+    println!("   {:>4}  {:<40}  {:>6}  {:>13}  {:>6}  {:>7}", "Day", "", "S(t)", "95% CI", "Hazard", "At risk");
+
+    let stride = curve.steps.len().div_ceil(30).max(1);
+    let last = curve.steps.len().saturating_sub(1);
+    for (i, step) in curve.steps.iter().enumerate() {
+        if i % stride != 0 && i != last {
+            continue;
+        }
+        let solid = (step.ci_low * WIDTH).round() as usize;
+        let shaded = ((step.ci_high * WIDTH).round() as usize).saturating_sub(solid);
+        let bar = format!("{}{}", "█".repeat(solid), "▒".repeat(shaded));
+        println!(
+            "   {:>4}  {:<40}  {:>6.3}  {:>5.3}–{:<5.3}  {:>6.3}  {:>7}",
+            step.day, bar, step.survival, step.ci_low, step.ci_high, step.hazard, step.at_risk
+        );
+    }
+
+    println!("   Median survival: {}", curve.median());
+    println!("   Mean daily hazard: {:.4}", curve.mean_hazard());
+}
+
+pub fn print_group_comparison(comparison: &GroupComparison) {
+    println!("\n⚖️  Survival by {}:", comparison.grouping);
+    let last_day = comparison.groups.iter().flat_map(|g| g.curve.steps.last()).map(|s| s.day).max().unwrap_or(0);
+    println!(
+        "   {:<20} {:>6} {:>10} {:>10} {:>10}  Median days to collapse",
+        "Group", "Runs", "Collapses", "Expected", format!("S({})", last_day)
+    );
+    for (i, group) in comparison.groups.iter().enumerate() {
+        let expected = comparison.log_rank.as_ref().map_or(String::from("-"), |t| format!("{:.1}", t.expected[i]));
+        println!(
+            "   {:<20} {:>6} {:>10} {:>10} {:>10.3}  {}",
+            group.label,
+            group.curve.runs,
+            group.curve.collapses,
+            expected,
+            group.curve.survival_at(last_day),
+            group.curve.median()
+        );
+    }
+
+    match &comparison.log_rank {
+        Some(test) => {
+            let verdict = if test.p_value < 0.05 { "survival differs between groups" } else { "no significant difference" };
+            println!(
+                "   Log-rank test: χ² = {:.2}, df = {}, p = {:.4} ({})",
+                test.chi_square, test.degrees_of_freedom, test.p_value, verdict
+            );
+        }
+        None => println!("   Log-rank test needs at least two groups"),
+    }
+}
+
+/// Write one row per group and day: the curve, its interval and the daily hazard
+pub fn write_survival_csv(path: &str, groups: &[(&str, &KaplanMeier)]) -> EcosystemResult<()> {
+    let io_error = |e: std::io::Error| EcosystemError::ConfigurationError {
+        message: format!("Could not write {}: {}", path, e),
+    };
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(io_error)?);
+
+    writeln!(file, "group,day,at_risk,collapses,censored,survival,std_error,ci_low,ci_high,hazard").map_err(io_error)?;
+    for (label, curve) in groups {
+        for s in &curve.steps {
+            writeln!(
+                file,
+                "{},{},{},{},{},{:.6},{:.6},{:.6},{:.6},{:.6}",
+                csv_field(label), s.day, s.at_risk, s.collapses, s.censored, s.survival, s.std_error, s.ci_low, s.ci_high, s.hazard
+            )
+            .map_err(io_error)?;
+        }
+    }
+    file.flush().map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observations(data: &[(usize, bool)]) -> Vec<Observation> {
+        data.iter().map(|&(day, collapsed)| Observation { day, collapsed }).collect()
+    }
+
+    #[test]
+    fn test_kaplan_meier_with_censoring() {
+        // Classic worked example: collapses on days 1, 3, 3 and 5; censored at 2 and 5
+        let curve = KaplanMeier::estimate(&observations(&[(1, true), (2, false), (3, true), (3, true), (5, true), (5, false)]));
+
+        assert!((curve.survival_at(1) - 5.0 / 6.0).abs() < 1e-9);
+        assert!((curve.survival_at(2) - 5.0 / 6.0).abs() < 1e-9);
+        assert!((curve.survival_at(3) - 5.0 / 6.0 * 1.0 / 4.0 * 2.0).abs() < 1e-9);
+        assert!((curve.survival_at(5) - 5.0 / 12.0 * 0.5).abs() < 1e-9);
+        assert_eq!(curve.steps[2].at_risk, 4);
+        let median = curve.median();
+        assert_eq!(median.median, Some(3));
+        assert!(median.ci_low <= median.median);
+        for step in &curve.steps {
+            assert!(step.ci_low <= step.survival && step.survival <= step.ci_high);
+        }
+    }
+
+    #[test]
+    fn test_log_rank_separates_different_curves() {
+        let early: Vec<_> = observations(&(1..=20).map(|d| (d % 5 + 1, true)).collect::<Vec<_>>());
+        let late: Vec<_> = observations(&(1..=20).map(|d| (d % 5 + 20, d % 2 == 0)).collect::<Vec<_>>());
+
+        let different = log_rank_test(&[&early, &late]).unwrap();
+        assert!(different.p_value < 0.001);
+        assert_eq!(different.observed, vec![20, 10]);
+
+        let same = log_rank_test(&[&early, &early]).unwrap();
+        assert!(same.chi_square.abs() < 1e-9);
+        assert!((same.p_value - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chi_square_p_value() {
+        assert!((chi_square_p_value(3.841459, 1) - 0.05).abs() < 1e-5);
+        assert!((chi_square_p_value(5.991465, 2) - 0.05).abs() < 1e-5);
+        assert!((chi_square_p_value(0.5, 3) - 0.918891).abs() < 1e-5);
+    }
+}
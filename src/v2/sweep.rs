@@ -0,0 +1,310 @@
+// v2/sweep.rs
+// Survival over a grid of one or two configuration fields
+
+use crate::v2::config::{field_path, V2Config};
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::export::csv_field;
+use crate::v2::montecarlo::{MonteCarloConfig, run_parallel, worker_threads};
+use colored::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io::Write;
+use std::str::FromStr;
+
+/// One axis of a sweep: a field and the values it takes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepAxis {
+    /// `difficulty` or a path accepted by `V2Config::set_field`
+    pub field: String,
+    pub values: Vec<f32>,
+}
+
+impl SweepAxis {
+    /// Fix this axis at `value` for every run of a cell
+    fn apply(&self, mc_config: &mut MonteCarloConfig, value: f32) {
+        if self.field == "difficulty" {
+            // Difficulty scales the rate constants, so it has to be chosen before they are built
+            if let Some(dimension) = mc_config.dimensions.iter_mut().find(|d| d.name == "difficulty") {
+                dimension.low = value;
+                dimension.high = value;
+            }
+        } else {
+            mc_config.overrides.push((self.field.clone(), value));
+        }
+    }
+}
+
+/// `field=low:high:steps` for evenly spaced values or `field=v1,v2,...` for a list
+impl FromStr for SweepAxis {
+    type Err = EcosystemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| EcosystemError::ConfigurationError { message };
+        let (field, spec) = s
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("Cannot read axis '{}' (expected field=low:high:steps or field=v1,v2,...)", s)))?;
+        let field = field_path(field.trim()).to_string();
+
+        let number = |v: &str| v.trim().parse::<f32>().map_err(|_| invalid(format!("'{}' is not a number in axis '{}'", v, s)));
+        let values = if spec.contains(':') {
+            let parts: Vec<&str> = spec.split(':').collect();
+            let [low, high, steps] = parts[..] else {
+                return Err(invalid(format!("Cannot read axis '{}' (expected field=low:high:steps)", s)));
+            };
+            let (low, high) = (number(low)?, number(high)?);
+            let steps: usize = steps.trim().parse().map_err(|_| invalid(format!("'{}' is not a step count in axis '{}'", steps, s)))?;
+            match steps {
+                0 => Vec::new(),
+                1 => vec![low],
+                _ => (0..steps).map(|i| low + (high - low) * i as f32 / (steps - 1) as f32).collect(),
+            }
+        } else {
+            spec.split(',').map(number).collect::<EcosystemResult<Vec<f32>>>()?
+        };
+        if values.is_empty() {
+            return Err(invalid(format!("Axis '{}' has no values", s)));
+        }
+
+        // Catch unknown fields and out-of-range values before any runs start
+        for &value in &values {
+            match field.as_str() {
+                "difficulty" => {
+                    crate::v2::config::difficulty::DifficultyConfig::new(value)?;
+                }
+                path => V2Config::check_field(path, value)?,
+            }
+        }
+
+        Ok(SweepAxis { field, values })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepConfig {
+    pub x: SweepAxis,
+    pub y: Option<SweepAxis>,
+    /// Monte Carlo runs per cell
+    pub replicates: usize,
+    /// Everything not on an axis is sampled as in this batch configuration
+    pub base: MonteCarloConfig,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepCell {
+    pub x: f32,
+    pub y: Option<f32>,
+    pub runs: usize,
+    pub survivors: usize,
+    pub survival_rate: f32,
+    pub mean_days_survived: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepResults {
+    pub x: SweepAxis,
+    pub y: Option<SweepAxis>,
+    /// Row by row: every x for the first y, then the next y
+    pub cells: Vec<SweepCell>,
+}
+
+impl SweepResults {
+    fn cell(&self, xi: usize, yi: usize) -> &SweepCell {
+        &self.cells[yi * self.x.values.len() + xi]
+    }
+}
+
+/// Every cell reuses the same master seed, so replicate i starts from the same draws in
+/// every cell and differences between cells come from the axes rather than sampling noise
+pub fn run_sweep(sweep: &SweepConfig) -> EcosystemResult<SweepResults> {
+    let master_seed = sweep.base.seed.unwrap_or_else(|| StdRng::from_entropy().r#gen());
+    let y_values: Vec<Option<f32>> = match &sweep.y {
+        Some(axis) => axis.values.iter().copied().map(Some).collect(),
+        None => vec![None],
+    };
+    let total = sweep.x.values.len() * y_values.len();
+
+    println!("🧭 Sweeping {}{} with {} runs per cell", sweep.x.field,
+             sweep.y.as_ref().map_or(String::new(), |y| format!(" × {}", y.field)), sweep.replicates);
+    println!("   {} cells, {} runs, {} days each, master seed {}", total, total * sweep.replicates, sweep.base.day_cap, master_seed);
+
+    let mut cells = Vec::with_capacity(total);
+    for y in &y_values {
+        for &x in &sweep.x.values {
+            let mut mc_config = MonteCarloConfig {
+                num_runs: sweep.replicates,
+                show_progress: false,
+                ..sweep.base.clone()
+            };
+            sweep.x.apply(&mut mc_config, x);
+            if let (Some(axis), Some(y)) = (&sweep.y, y) {
+                axis.apply(&mut mc_config, *y);
+            }
+
+            let results = run_parallel(&mc_config, master_seed, worker_threads(&mc_config))?;
+            // Runs that stopped on a simulation error have no outcome, as in the Monte Carlo report
+            let finished: Vec<_> = results.iter().filter(|r| r.error.is_none()).collect();
+            let survivors = finished.iter().filter(|r| r.survived).count();
+            let runs = finished.len().max(1);
+            cells.push(SweepCell {
+                x,
+                y: *y,
+                runs: finished.len(),
+                survivors,
+                survival_rate: survivors as f32 / runs as f32,
+                mean_days_survived: finished.iter().map(|r| r.days_survived).sum::<usize>() as f32 / runs as f32,
+            });
+
+            if sweep.base.show_progress {
+                print!("\r   Cell {}/{}", cells.len(), total);
+                std::io::stdout().flush().ok();
+            }
+        }
+    }
+    if sweep.base.show_progress {
+        println!();
+    }
+
+    Ok(SweepResults { x: sweep.x.clone(), y: sweep.y.clone(), cells })
+}
+
+/// Red through yellow to green as `fraction` goes from 0 to 1
+fn heat(fraction: f32) -> (u8, u8, u8) {
+    let f = fraction.clamp(0.0, 1.0);
+    if f < 0.5 {
+        (200, (60.0 + 340.0 * f) as u8, 50)
+    } else {
+        ((200.0 - 340.0 * (f - 0.5)) as u8, 200, 50)
+    }
+}
+
+fn format_value(value: f32) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e6 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.3}", value).trim_end_matches('0').to_string()
+    }
+}
+
+pub fn print_sweep_results(results: &SweepResults, day_cap: usize) {
+    print_heatmap(results, "🌡️  Survival rate", |cell| cell.survival_rate, |cell| format!("{:.0}%", cell.survival_rate * 100.0));
+    print_heatmap(
+        results,
+        "📅 Mean days survived",
+        |cell| cell.mean_days_survived / day_cap.max(1) as f32,
+        |cell| format!("{:.1}", cell.mean_days_survived),
+    );
+
+    // Ties on survival go to the cell whose bottles lasted longer
+    let best = results.cells.iter().max_by(|a, b| {
+        a.survival_rate.total_cmp(&b.survival_rate).then(a.mean_days_survived.total_cmp(&b.mean_days_survived))
+    });
+    if let Some(best) = best {
+        let y = match (&results.y, best.y) {
+            (Some(axis), Some(value)) => format!(", {} = {}", axis.field, format_value(value)),
+            _ => String::new(),
+        };
+        println!("\n🏅 Best cell: {} = {}{} with {:.0}% survival", results.x.field, format_value(best.x), y, best.survival_rate * 100.0);
+    }
+}
+
+fn print_heatmap(results: &SweepResults, title: &str, shade: impl Fn(&SweepCell) -> f32, label: impl Fn(&SweepCell) -> String) {
+    const WIDTH: usize = 8;
+    let y_label = |yi: usize| results.y.as_ref().map_or(String::new(), |axis| format_value(axis.values[yi]));
+    let rows = results.y.as_ref().map_or(1, |axis| axis.values.len());
+    let label_width = (0..rows).map(|yi| y_label(yi).len()).max().unwrap_or(0).max(
+        results.y.as_ref().map_or(0, |axis| axis.field.len().min(24)),
+    );
+
+    println!("\n{}:", title);
+    if let Some(axis) = &results.y {
+        println!("   {:>width$}", axis.field, width = label_width);
+    }
+    // Highest y at the top, like a plot
+    for yi in (0..rows).rev() {
+        let mut line = format!("   {:>width$} │", y_label(yi), width = label_width);
+        for xi in 0..results.x.values.len() {
+            let cell = results.cell(xi, yi);
+            let (r, g, b) = heat(shade(cell));
+            line.push_str(&format!("{:^WIDTH$}", label(cell)).black().on_truecolor(r, g, b).to_string());
+        }
+        println!("{}", line);
+    }
+    let axis_line: String = results.x.values.iter().map(|v| format!("{:^WIDTH$}", format_value(*v))).collect();
+    println!("   {:>width$}  {}", "", axis_line, width = label_width);
+    println!("   {:>width$}  {:^span$}", "", results.x.field, width = label_width, span = WIDTH * results.x.values.len());
+}
+
+/// One row per cell
+pub fn write_sweep_csv(path: &str, results: &SweepResults) -> EcosystemResult<()> {
+    let io_error = |e: std::io::Error| EcosystemError::ConfigurationError {
+        message: format!("Could not write {}: {}", path, e),
+    };
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(io_error)?);
+
+    let y_field = results.y.as_ref().map_or("", |axis| axis.field.as_str());
+    writeln!(file, "{},{}runs,survivors,survival_rate,mean_days_survived",
+             csv_field(&results.x.field), if y_field.is_empty() { String::new() } else { format!("{},", csv_field(y_field)) })
+        .map_err(io_error)?;
+    for cell in &results.cells {
+        let y = cell.y.map_or(String::new(), |y| format!("{},", y));
+        writeln!(file, "{},{}{},{},{:.6},{:.4}", cell.x, y, cell.runs, cell.survivors, cell.survival_rate, cell.mean_days_survived)
+            .map_err(io_error)?;
+    }
+    file.flush().map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_axes() {
+        let rocks: SweepAxis = "rocks=0:4:3".parse().unwrap();
+        assert_eq!(rocks.field, "environment.rocks");
+        assert_eq!(rocks.values, vec![0.0, 2.0, 4.0]);
+
+        let rate: SweepAxis = "parameters.worm.death_rate=0.01,0.02".parse().unwrap();
+        assert_eq!(rate.values, vec![0.01, 0.02]);
+
+        assert!("parameters.worm.colour=1,2".parse::<SweepAxis>().is_err());
+        assert!("difficulty=0.5,1.5".parse::<SweepAxis>().is_err());
+        assert!("rocks=0:4".parse::<SweepAxis>().is_err());
+        assert!("rocks=-5,0".parse::<SweepAxis>().is_err());
+        assert!("parameters.microbial.growth_rate=-1:1:3".parse::<SweepAxis>().is_err());
+    }
+
+    #[test]
+    fn test_sweep_fixes_each_cell() {
+        let sweep = SweepConfig {
+            x: "difficulty=0.2,0.8".parse().unwrap(),
+            y: Some("rocks=0,5".parse().unwrap()),
+            replicates: 3,
+            base: MonteCarloConfig { day_cap: 2, show_progress: false, seed: Some(4), ..MonteCarloConfig::default() },
+        };
+        let results = run_sweep(&sweep).unwrap();
+
+        assert_eq!(results.cells.len(), 4);
+        assert_eq!((results.cell(1, 0).x, results.cell(1, 0).y), (0.8, Some(0.0)));
+        assert!(results.cells.iter().all(|c| c.runs == 3 && c.survivors <= 3));
+
+        let mut mc_config = MonteCarloConfig::default();
+        sweep.y.as_ref().unwrap().apply(&mut mc_config, 5.0);
+        sweep.x.apply(&mut mc_config, 0.8);
+        let run = &run_parallel(&MonteCarloConfig { num_runs: 1, show_progress: false, ..mc_config }, 1, 1).unwrap()[0];
+        assert_eq!(run.config_snapshot.rocks, 5);
+        assert_eq!(run.config_snapshot.difficulty, 0.8);
+    }
+
+    #[test]
+    fn test_stocking_axis_changes_the_outcome() {
+        let sweep = SweepConfig {
+            x: "microbes=100,5000".parse().unwrap(),
+            y: None,
+            replicates: 4,
+            base: MonteCarloConfig { day_cap: 30, show_progress: false, seed: Some(1), ..MonteCarloConfig::default() },
+        };
+        let results = run_sweep(&sweep).unwrap();
+        let (few, many) = (results.cell(0, 0), results.cell(1, 0));
+        assert_ne!((few.survivors, few.mean_days_survived), (many.survivors, many.mean_days_survived));
+    }
+}
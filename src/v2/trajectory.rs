@@ -0,0 +1,334 @@
+// v2/trajectory.rs
+// Half-day time series of Monte Carlo runs and the bands across a batch
+
+use crate::v2::errors::{EcosystemError, EcosystemResult};
+use crate::v2::montecarlo::SimulationResult;
+use crate::v2::state::EcosystemStateV2;
+use crate::v2::traits::EcosystemMonitoring;
+use serde::Serialize;
+use serde::ser::{SerializeMap, Serializer};
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Which runs keep their time series
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TrajectorySelection {
+    #[default]
+    None,
+    All,
+    /// Roughly this fraction of runs, picked from their seeds
+    Sample(f64),
+    Collapsed,
+    Survived,
+}
+
+impl TrajectorySelection {
+    /// Whether to record a run before it starts; the outcome filters are applied by `keeps`
+    pub fn records(&self, run_seed: u64) -> bool {
+        match self {
+            TrajectorySelection::None => false,
+            TrajectorySelection::Sample(fraction) => ((run_seed >> 11) as f64 / (1u64 << 53) as f64) < *fraction,
+            _ => true,
+        }
+    }
+
+    pub fn keeps(&self, result: &SimulationResult) -> bool {
+        match self {
+            TrajectorySelection::None => false,
+            TrajectorySelection::All | TrajectorySelection::Sample(_) => true,
+            TrajectorySelection::Collapsed => !result.collapse_reasons.is_empty(),
+            TrajectorySelection::Survived => result.survived,
+        }
+    }
+}
+
+impl FromStr for TrajectorySelection {
+    type Err = EcosystemError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EcosystemError::ConfigurationError {
+            message: format!("Unknown trajectory selection '{}' (expected all, collapsed, survived or sample:<fraction>)", s),
+        };
+        match s.to_lowercase().as_str() {
+            "none" => Ok(TrajectorySelection::None),
+            "all" => Ok(TrajectorySelection::All),
+            "collapsed" => Ok(TrajectorySelection::Collapsed),
+            "survived" | "survivors" => Ok(TrajectorySelection::Survived),
+            "sample" => Ok(TrajectorySelection::Sample(0.1)),
+            other => {
+                let fraction: f64 = other.strip_prefix("sample:").ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+                if (0.0..=1.0).contains(&fraction) {
+                    Ok(TrajectorySelection::Sample(fraction))
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for TrajectorySelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrajectorySelection::None => write!(f, "none"),
+            TrajectorySelection::All => write!(f, "all runs"),
+            TrajectorySelection::Sample(fraction) => write!(f, "{:.0}% sample of runs", fraction * 100.0),
+            TrajectorySelection::Collapsed => write!(f, "collapsed runs"),
+            TrajectorySelection::Survived => write!(f, "surviving runs"),
+        }
+    }
+}
+
+/// `key_metrics()` after every half-day; step 0 is the starting state
+#[derive(Debug, Clone, Default)]
+pub struct Trajectory {
+    pub metrics: Vec<String>,
+    /// `values[step][metric]`
+    pub values: Vec<Vec<f32>>,
+}
+
+impl Trajectory {
+    pub fn record(&mut self, state: &EcosystemStateV2) {
+        let metrics = state.key_metrics();
+        if self.metrics.is_empty() {
+            self.metrics = metrics.iter().map(|(name, _)| name.clone()).collect();
+        }
+        self.values.push(metrics.into_iter().map(|(_, value)| value).collect());
+    }
+
+    pub fn value(&self, step: usize, metric: &str) -> Option<f32> {
+        let index = self.metrics.iter().position(|m| m == metric)?;
+        self.values.get(step).map(|values| values[index])
+    }
+}
+
+/// Day number and phase of a step; runs start at dawn of day 1
+pub fn step_label(step: usize) -> (usize, &'static str) {
+    match step {
+        0 => (1, "start"),
+        s if s % 2 == 1 => (s.div_ceil(2), "day"),
+        s => (s / 2, "night"),
+    }
+}
+
+/// One point of a band: percentiles over the runs still going at that step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandPoint {
+    pub step: usize,
+    pub runs: usize,
+    pub p5: f32,
+    pub median: f32,
+    pub p95: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricBands {
+    pub metric: String,
+    pub points: Vec<BandPoint>,
+}
+
+/// Median and 5–95% band of every metric across the recorded runs
+pub fn trajectory_bands(results: &[SimulationResult]) -> Vec<MetricBands> {
+    let trajectories: Vec<&Trajectory> = results.iter().filter_map(|r| r.trajectory.as_ref()).collect();
+    let steps = trajectories.iter().map(|t| t.values.len()).max().unwrap_or(0);
+
+    metric_names(&trajectories)
+        .into_iter()
+        .map(|metric| {
+            let points = (0..steps)
+                .filter_map(|step| {
+                    let mut values: Vec<f32> = trajectories
+                        .iter()
+                        .filter_map(|t| t.value(step, &metric))
+                        .filter(|v| v.is_finite())
+                        .collect();
+                    if values.is_empty() {
+                        return None;
+                    }
+                    values.sort_by(f32::total_cmp);
+                    Some(BandPoint {
+                        step,
+                        runs: values.len(),
+                        p5: percentile(&values, 0.05),
+                        median: percentile(&values, 0.5),
+                        p95: percentile(&values, 0.95),
+                    })
+                })
+                .collect();
+            MetricBands { metric, points }
+        })
+        .collect()
+}
+
+/// Every metric any run recorded, in the order they were first seen
+fn metric_names(trajectories: &[&Trajectory]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for trajectory in trajectories {
+        for metric in &trajectory.metrics {
+            if !names.contains(metric) {
+                names.push(metric.clone());
+            }
+        }
+    }
+    names
+}
+
+/// Linear interpolation between the closest ranks of sorted values
+fn percentile(sorted: &[f32], p: f32) -> f32 {
+    let rank = p * (sorted.len() - 1) as f32;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f32)
+}
+
+pub fn print_trajectory_bands(bands: &[MetricBands], metrics: &[&str]) {
+    const WIDTH: usize = 32;
+    for metric in metrics {
+        let Some(band) = bands.iter().find(|b| b.metric == *metric) else {
+            continue;
+        };
+        let low = band.points.iter().map(|p| p.p5).fold(f32::INFINITY, f32::min);
+        let high = band.points.iter().map(|p| p.p95).fold(f32::NEG_INFINITY, f32::max);
+        let scale = |value: f32| {
+            let span = (high - low).max(f32::EPSILON);
+            (((value - low) / span) * (WIDTH - 1) as f32).round() as usize
+        };
+
+        println!("\n📈 {} (median ● with 5–95% band, {:.2} to {:.2}):", band.metric, low, high);
+        let daily: Vec<&BandPoint> = band.points.iter().filter(|p| p.step % 2 == 1).collect();
+        let stride = daily.len().div_ceil(15).max(1);
+        for (i, point) in daily.iter().enumerate() {
+            if i % stride != 0 && i != daily.len() - 1 {
+                continue;
+            }
+            let mut row = [' '; WIDTH];
+            for cell in &mut row[scale(point.p5)..=scale(point.p95)] {
+                *cell = '░';
+            }
+            row[scale(point.median)] = '●';
+            let (day, _) = step_label(point.step);
+            println!(
+                "   Day {:>3} │{}│ {:>9.3} [{:.3} – {:.3}] {:>5} runs",
+                day, row.iter().collect::<String>(), point.median, point.p5, point.p95, point.runs
+            );
+        }
+    }
+}
+
+/// One column of the trajectory file
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Column {
+    Integers(Vec<usize>),
+    Flags(Vec<bool>),
+    Labels(Vec<&'static str>),
+    Values(Vec<Option<f32>>),
+}
+
+/// Columns in order, written as a JSON object of equal-length arrays
+struct Columns(Vec<(String, Column)>);
+
+impl Serialize for Columns {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, column) in &self.0 {
+            map.serialize_entry(name, column)?;
+        }
+        map.end()
+    }
+}
+
+/// Write every recorded step of every run as columns; metrics a run lacks are `null`
+pub fn write_trajectories(path: &str, results: &[SimulationResult]) -> EcosystemResult<usize> {
+    let recorded: Vec<(&SimulationResult, &Trajectory)> = results
+        .iter()
+        .filter_map(|r| r.trajectory.as_ref().map(|t| (r, t)))
+        .collect();
+    let trajectories: Vec<&Trajectory> = recorded.iter().map(|(_, t)| *t).collect();
+
+    let mut run_id = Vec::new();
+    let mut step = Vec::new();
+    let mut day = Vec::new();
+    let mut phase = Vec::new();
+    let mut survived = Vec::new();
+    for (result, trajectory) in &recorded {
+        for s in 0..trajectory.values.len() {
+            let (d, p) = step_label(s);
+            run_id.push(result.run_id);
+            step.push(s);
+            day.push(d);
+            phase.push(p);
+            survived.push(result.survived);
+        }
+    }
+
+    let mut columns = vec![
+        ("run_id".to_string(), Column::Integers(run_id)),
+        ("step".to_string(), Column::Integers(step)),
+        ("day".to_string(), Column::Integers(day)),
+        ("phase".to_string(), Column::Labels(phase)),
+        ("survived".to_string(), Column::Flags(survived)),
+    ];
+    for metric in metric_names(&trajectories) {
+        let values = trajectories
+            .iter()
+            .flat_map(|t| (0..t.values.len()).map(|s| t.value(s, &metric)).collect::<Vec<_>>())
+            .collect();
+        columns.push((metric, Column::Values(values)));
+    }
+
+    let error = |e: &dyn fmt::Display| EcosystemError::ConfigurationError {
+        message: format!("Could not write {}: {}", path, e),
+    };
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(|e| error(&e))?);
+    serde_json::to_writer(&mut file, &Columns(columns)).map_err(|e| error(&e))?;
+    writeln!(file).map_err(|e| error(&e))?;
+    file.flush().map_err(|e| error(&e))?;
+    Ok(recorded.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::montecarlo::{MonteCarloConfig, run_monte_carlo_v2};
+
+    #[test]
+    fn test_selection_and_bands() {
+        let mc_config = |selection| MonteCarloConfig {
+            num_runs: 20,
+            day_cap: 3,
+            show_progress: false,
+            seed: Some(9),
+            record_trajectories: selection,
+            ..MonteCarloConfig::default()
+        };
+
+        let none = run_monte_carlo_v2(mc_config(TrajectorySelection::None)).unwrap();
+        assert!(none.results.iter().all(|r| r.trajectory.is_none()));
+
+        let all = run_monte_carlo_v2(mc_config(TrajectorySelection::All)).unwrap();
+        for result in &all.results {
+            let trajectory = result.trajectory.as_ref().unwrap();
+            // Starting state plus both half-days of every day survived but the last
+            assert!(trajectory.values.len() >= 2 * result.days_survived);
+            assert_eq!(trajectory.value(0, "Soil pH"), Some(result.config_snapshot.initial_state.soil_ph));
+        }
+
+        let sample = run_monte_carlo_v2(mc_config(TrajectorySelection::Sample(0.5))).unwrap();
+        let sampled = sample.results.iter().filter(|r| r.trajectory.is_some()).count();
+        assert!(sampled > 0 && sampled < 20);
+
+        let bands = trajectory_bands(&all.results);
+        let oxygen = bands.iter().find(|b| b.metric == "Air Oxygen").unwrap();
+        assert_eq!(oxygen.points[0].runs, 20);
+        assert!(oxygen.points.iter().all(|p| p.p5 <= p.median && p.median <= p.p95));
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!("collapsed".parse::<TrajectorySelection>().unwrap(), TrajectorySelection::Collapsed);
+        assert_eq!("sample:0.25".parse::<TrajectorySelection>().unwrap(), TrajectorySelection::Sample(0.25));
+        assert!("sample:2".parse::<TrajectorySelection>().is_err());
+        assert!("some".parse::<TrajectorySelection>().is_err());
+    }
+}
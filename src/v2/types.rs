@@ -1,44 +1,66 @@
 // v2/types.rs
 // Type-safe wrappers for ecosystem values
 
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Biomass(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Population(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Ph(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Temperature(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Humidity(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Oxygen(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct CarbonDioxide(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Nitrogen(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct WaterVolume(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Moisture(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Aeration(f32);
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Detritus(f32);
 
+/// Position of the sun over one day; the daytime half-day is split into dawn, noon and dusk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DayPhase {
+    Dawn,
+    Noon,
+    Dusk,
+    Night,
+}
+
 // Error types for validation
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValidationError {
@@ -46,7 +68,7 @@ pub enum ValidationError {
     TemperatureOutOfRange(f32),
     HumidityOutOfRange(f32),
     NegativeValue(String),
-    ZeroOrNegativePopulation(String),
+    NegativePopulation(String),
 }
 
 impl fmt::Display for ValidationError {
@@ -56,7 +78,7 @@ impl fmt::Display for ValidationError {
             ValidationError::TemperatureOutOfRange(value) => write!(f, "Temperature {} is out of acceptable range", value),
             ValidationError::HumidityOutOfRange(value) => write!(f, "Humidity {} is out of range (0-100)", value),
             ValidationError::NegativeValue(param) => write!(f, "Parameter {} cannot be negative", param),
-            ValidationError::ZeroOrNegativePopulation(pop) => write!(f, "Population {} must be positive", pop),
+            ValidationError::NegativePopulation(pop) => write!(f, "Population {} cannot be negative", pop),
         }
     }
 }
@@ -85,8 +107,8 @@ impl Biomass {
 // Population implementation
 impl Population {
     pub fn new(value: f32) -> Result<Self, ValidationError> {
-        if value <= 0.0 {
-            Err(ValidationError::ZeroOrNegativePopulation("population".to_string()))
+        if value < 0.0 {
+            Err(ValidationError::NegativePopulation("population".to_string()))
         } else {
             Ok(Population(value))
         }
@@ -181,6 +203,52 @@ impl Oxygen {
     }
 }
 
+// DayPhase implementation
+impl DayPhase {
+    const DAYTIME: [DayPhase; 3] = [DayPhase::Dawn, DayPhase::Noon, DayPhase::Dusk];
+    const NIGHTTIME: [DayPhase; 1] = [DayPhase::Night];
+
+    /// Phases making up one half-day step of the simulation
+    pub fn half_day(is_day: bool) -> &'static [DayPhase] {
+        if is_day { &Self::DAYTIME } else { &Self::NIGHTTIME }
+    }
+
+    /// Length of the phase as a fraction of a half-day
+    pub fn duration(&self) -> f32 {
+        match self {
+            DayPhase::Dawn => 0.25,
+            DayPhase::Noon => 0.5,
+            DayPhase::Dusk => 0.25,
+            DayPhase::Night => 1.0,
+        }
+    }
+
+    /// Fraction of peak sunlight reaching the bottle during the phase
+    pub fn solar_fraction(&self) -> f32 {
+        match self {
+            DayPhase::Dawn => 0.4,
+            DayPhase::Noon => 1.0,
+            DayPhase::Dusk => 0.4,
+            DayPhase::Night => 0.0,
+        }
+    }
+
+    pub fn is_daylight(&self) -> bool {
+        *self != DayPhase::Night
+    }
+}
+
+impl fmt::Display for DayPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DayPhase::Dawn => write!(f, "Dawn"),
+            DayPhase::Noon => write!(f, "Noon"),
+            DayPhase::Dusk => write!(f, "Dusk"),
+            DayPhase::Night => write!(f, "Night"),
+        }
+    }
+}
+
 // Helper macro for simple positive value types
 macro_rules! impl_positive_value {
     ($type:ident, $name:literal) => {
@@ -205,4 +273,29 @@ impl_positive_value!(Nitrogen, "nitrogen");
 impl_positive_value!(WaterVolume, "water_volume");
 impl_positive_value!(Moisture, "moisture");
 impl_positive_value!(Aeration, "aeration");
-impl_positive_value!(Detritus, "detritus");
\ No newline at end of file
+impl_positive_value!(Detritus, "detritus");
+
+// Stored as plain numbers and checked by `new` when read back
+macro_rules! impl_number_conversions {
+    ($($type:ident),+) => {
+        $(
+            impl TryFrom<f32> for $type {
+                type Error = ValidationError;
+
+                fn try_from(value: f32) -> Result<Self, Self::Error> {
+                    $type::new(value)
+                }
+            }
+
+            impl From<$type> for f32 {
+                fn from(value: $type) -> f32 {
+                    value.0
+                }
+            }
+        )+
+    };
+}
+
+impl_number_conversions!(
+    Biomass, Population, Ph, Temperature, Humidity, Oxygen, CarbonDioxide, Nitrogen, WaterVolume, Moisture, Aeration, Detritus
+);